@@ -1,286 +1,899 @@
+
+use std::collections::HashMap;
+
 use leptos::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::model::analytics::AnalyticsReport;
 use crate::model::book::Book;
+use crate::model::collection::{Collection, CollectionDetail};
+use crate::model::health::HealthReport;
 use crate::model::movie::Movie;
-use crate::model::search::{FacetInfo, SearchResponse};
+use crate::model::series::Series;
+use crate::model::person::{MigrationResult, PersonDetail};
+use crate::model::review::{FlagReason, Review, ReviewPage, ReviewStatus};
+use crate::model::saved_search::{Notification, NotificationFeed, SavedSearch};
+use crate::model::search::{
+    BookFilters, FacetInfo, MovieFilters, SearchRequest, SearchResponse, SeriesFilters,
+    SimilarItem, Suggestion,
+};
+use crate::model::search_settings::{SearchSettings, SettingsPreview};
+use crate::model::tag::{ItemTags, TagOverview};
+use crate::model::user::{ImportResult, ListStatus, User, WatchlistResponse};
 use crate::model::web_result::WebResult;
 #[cfg(feature = "ssr")]
 use crate::model::search::{FacetValue, SearchHit};
-
 #[cfg(feature = "ssr")]
-const HITS_PER_PAGE: usize = 12;
-
-#[server]
-pub async fn search_items(
-    query: String,
-    index: String,
-    genres: Option<Vec<String>>,
-    year_min: Option<i32>,
-    year_max: Option<i32>,
-    rating_min: Option<f64>,
-    page: Option<usize>,
-    sort: Option<String>,
-) -> Result<SearchResponse, ServerFnError> {
-    use crate::server::meilisearch::{build_filter, get_client};
-
-    let genres = genres.unwrap_or_default();
-    let client = get_client();
-    let ms_index = client.index(&index);
-    let current_page = page.unwrap_or(1);
-    let offset = (current_page - 1) * HITS_PER_PAGE;
-
-    let filter = build_filter(&genres, year_min, year_max, rating_min);
+use crate::server::telemetry::server_error;
 
-    let mut search = ms_index.search();
-    search.with_query(&query);
-    search.with_limit(HITS_PER_PAGE);
-    search.with_offset(offset);
+pub use search::{search_items, SearchItems};
 
-    if let Some(ref f) = filter {
-        search.with_filter(f);
-    }
+// One argument per search control, mirroring `SearchRequest`. `#[server]`
+// drops lint attributes from the client stub, so the allow goes on a module
+// holding just this function.
+#[allow(clippy::too_many_arguments)]
+mod search {
+    use super::*;
 
-    let sort_vec: Vec<String> = sort.into_iter().collect();
-    let sort_refs: Vec<&str> = sort_vec.iter().map(|s| s.as_str()).collect();
-    if !sort_refs.is_empty() {
-        search.with_sort(&sort_refs);
-    }
+    #[server]
+    pub async fn search_items(
+        query: String,
+        index: String,
+        genres: Option<Vec<String>>,
+        tags: Option<Vec<String>>,
+        year_min: Option<i32>,
+        year_max: Option<i32>,
+        rating_min: Option<f64>,
+        page: Option<usize>,
+        sort: Option<String>,
+        semantic_ratio: Option<f32>,
+        movie_filters: Option<MovieFilters>,
+        book_filters: Option<BookFilters>,
+        series_filters: Option<SeriesFilters>,
+    ) -> Result<SearchResponse, ServerFnError> {
+        use crate::model::analytics::SearchEvent;
+        use crate::server::analytics;
+        use crate::server::collections;
+        use crate::server::config::config;
+        use crate::server::did_you_mean;
+        use crate::server::embedding;
+        use crate::server::meilisearch::{get_search_index, prepare_search};
+        use crate::server::schema::schema;
+        use crate::server::web_fallback;
 
-    search.with_show_ranking_score(true);
+        let started = std::time::Instant::now();
+        let schema = schema(&index).map_err(server_error)?;
+        let sort = sort
+            .map(|s| schema.check_sort(&s))
+            .transpose()
+            .map_err(server_error)?;
 
-    if index == "movies" {
-        let results = search
-            .execute::<Movie>()
-            .await
-            .map_err(|e| ServerFnError::new(format!("Search failed: {e}")))?;
-
-        let total_hits = results.estimated_total_hits.unwrap_or(0);
-        let total_pages = (total_hits + HITS_PER_PAGE - 1) / HITS_PER_PAGE;
-
-        let hits: Vec<SearchHit> = results
-            .hits
-            .into_iter()
-            .map(|h| {
-                let m = h.result;
-                SearchHit {
-                    id: m.id,
-                    title: m.title,
-                    title_en: m.title_en,
-                    description: m.description,
-                    creator: m.director,
-                    year: m.year,
-                    genres: m.genres,
-                    rating: m.rating,
-                    image_url: m.poster_url,
-                    language: m.language,
-                    index: "movies".to_string(),
-                }
-            })
-            .collect();
-
-        Ok(SearchResponse {
-            hits,
-            total_hits,
-            page: current_page,
-            total_pages,
-            processing_time_ms: results.processing_time_ms,
-        })
-    } else if index == "web" {
-        let results = search
-            .execute::<WebResult>()
-            .await
-            .map_err(|e| ServerFnError::new(format!("Search failed: {e}")))?;
-
-        let total_hits = results.estimated_total_hits.unwrap_or(0);
-        let total_pages = (total_hits + HITS_PER_PAGE - 1) / HITS_PER_PAGE;
-
-        let hits: Vec<SearchHit> = results
-            .hits
-            .into_iter()
-            .map(|h| {
-                let w = h.result;
-                SearchHit {
-                    id: w.id,
-                    title: w.title,
-                    title_en: w.title_en,
-                    description: w.description,
-                    creator: w.url.clone(),
-                    year: w.year,
-                    genres: w.genres,
-                    rating: w.rating,
-                    image_url: w.image_url,
-                    language: w.language,
-                    index: "web".to_string(),
-                }
-            })
-            .collect();
-
-        Ok(SearchResponse {
-            hits,
-            total_hits,
-            page: current_page,
-            total_pages,
-            processing_time_ms: results.processing_time_ms,
-        })
-    } else {
-        let results = search
-            .execute::<Book>()
+        let request = SearchRequest {
+            query,
+            index: index.clone(),
+            genres: genres.unwrap_or_default(),
+            tags: tags.unwrap_or_default(),
+            year_min,
+            year_max,
+            rating_min,
+            page,
+            sort: sort.clone(),
+            semantic_ratio,
+            movie_filters: movie_filters.unwrap_or_default(),
+            book_filters: book_filters.unwrap_or_default(),
+            series_filters: series_filters.unwrap_or_default(),
+        };
+        let prepared = prepare_search(&request, schema)
             .await
-            .map_err(|e| ServerFnError::new(format!("Search failed: {e}")))?;
-
-        let total_hits = results.estimated_total_hits.unwrap_or(0);
-        let total_pages = (total_hits + HITS_PER_PAGE - 1) / HITS_PER_PAGE;
-
-        let hits: Vec<SearchHit> = results
-            .hits
-            .into_iter()
-            .map(|h| {
-                let b = h.result;
-                SearchHit {
-                    id: b.id,
-                    title: b.title,
-                    title_en: b.title_en,
-                    description: b.description,
-                    creator: b.author,
-                    year: b.year,
-                    genres: b.genres,
-                    rating: b.rating,
-                    image_url: b.cover_url,
-                    language: b.language,
-                    index: "books".to_string(),
-                }
-            })
-            .collect();
-
-        Ok(SearchResponse {
-            hits,
-            total_hits,
-            page: current_page,
-            total_pages,
-            processing_time_ms: results.processing_time_ms,
-        })
+            .map_err(server_error)?;
+        let (raw_query, query, filter) = (prepared.raw_query, prepared.query, prepared.filter);
+
+        let ms_index = get_search_index(&index);
+        let current_page = page.unwrap_or(1).max(1);
+        let hits_per_page = config().search.hits_per_page;
+        let offset = (current_page - 1) * hits_per_page;
+
+        // Hybrid search only applies to indexes with an embedder and to non-empty queries
+        let semantic_ratio = semantic_ratio
+            .filter(|r| *r > 0.0 && index != "web" && !query.is_empty() && embedding::is_enabled())
+            .map(|r| r.min(1.0));
+        let query_vector = semantic_ratio.and_then(|_| embedding::query_vector(&query));
+
+        let mut search = ms_index.search();
+        search.with_query(&query);
+        search.with_limit(hits_per_page);
+        search.with_offset(offset);
+
+        if let Some(ratio) = semantic_ratio {
+            search.with_hybrid(embedding::EMBEDDER, ratio);
+            if let Some(ref vector) = query_vector {
+                search.with_vector(vector);
+            }
+        }
+
+        if let Some(ref f) = filter {
+            search.with_filter(f);
+        }
+
+        let sort_vec: Vec<String> = sort.into_iter().collect();
+        let sort_refs: Vec<&str> = sort_vec.iter().map(|s| s.as_str()).collect();
+        if !sort_refs.is_empty() {
+            search.with_sort(&sort_refs);
+        }
+
+        search.with_show_ranking_score(true);
+
+        let mut response = if index == "movies" {
+            let results = search
+                .execute::<Movie>()
+                .await
+                .map_err(|e| server_error(format!("Search failed: {e}")))?;
+
+            let total_hits = results.estimated_total_hits.unwrap_or(0);
+            let total_pages = total_hits.div_ceil(hits_per_page);
+
+            let hits: Vec<SearchHit> = results
+                .hits
+                .into_iter()
+                .map(|h| SearchHit::from(h.result))
+                .collect();
+
+            SearchResponse {
+                hits,
+                total_hits,
+                page: current_page,
+                total_pages,
+                processing_time_ms: results.processing_time_ms,
+                web_hits: vec![],
+                suggestions: vec![],
+                collections: vec![],
+                search_id: None,
+            }
+        } else if index == "series" {
+            let results = search
+                .execute::<Series>()
+                .await
+                .map_err(|e| server_error(format!("Search failed: {e}")))?;
+
+            let total_hits = results.estimated_total_hits.unwrap_or(0);
+            let total_pages = total_hits.div_ceil(hits_per_page);
+
+            let hits: Vec<SearchHit> = results
+                .hits
+                .into_iter()
+                .map(|h| SearchHit::from(h.result))
+                .collect();
+
+            SearchResponse {
+                hits,
+                total_hits,
+                page: current_page,
+                total_pages,
+                processing_time_ms: results.processing_time_ms,
+                web_hits: vec![],
+                suggestions: vec![],
+                collections: vec![],
+                search_id: None,
+            }
+        } else if index == "web" {
+            let results = search
+                .execute::<WebResult>()
+                .await
+                .map_err(|e| server_error(format!("Search failed: {e}")))?;
+
+            let total_hits = results.estimated_total_hits.unwrap_or(0);
+            let total_pages = total_hits.div_ceil(hits_per_page);
+
+            let hits: Vec<SearchHit> = results
+                .hits
+                .into_iter()
+                .map(|h| web_hit(h.result))
+                .collect();
+
+            SearchResponse {
+                hits,
+                total_hits,
+                page: current_page,
+                total_pages,
+                processing_time_ms: results.processing_time_ms,
+                web_hits: vec![],
+                suggestions: vec![],
+                collections: vec![],
+                search_id: None,
+            }
+        } else {
+            let results = search
+                .execute::<Book>()
+                .await
+                .map_err(|e| server_error(format!("Search failed: {e}")))?;
+
+            let total_hits = results.estimated_total_hits.unwrap_or(0);
+            let total_pages = total_hits.div_ceil(hits_per_page);
+
+            let hits: Vec<SearchHit> = results
+                .hits
+                .into_iter()
+                .map(|h| SearchHit::from(h.result))
+                .collect();
+
+            SearchResponse {
+                hits,
+                total_hits,
+                page: current_page,
+                total_pages,
+                processing_time_ms: results.processing_time_ms,
+                web_hits: vec![],
+                suggestions: vec![],
+                collections: vec![],
+                search_id: None,
+            }
+        };
+
+        if response.total_hits == 0 && index != "web" && web_fallback::should_run(&query) {
+            response.web_hits = web_fallback::fallback_results(&query)
+                .await
+                .into_iter()
+                .map(web_hit)
+                .collect();
+        }
+
+        collections::annotate(&mut response.hits).await;
+        if current_page == 1 && index != "web" && !query.is_empty() {
+            response.collections = collections::search(&query).await;
+        }
+
+        if response.total_hits < did_you_mean::FEW_HITS && index != "web" && !query.is_empty() {
+            response.suggestions = did_you_mean::suggestions(&index, &raw_query).await;
+        }
+
+        if analytics::is_enabled() {
+            let id = analytics::new_event_id();
+            analytics::log_search(SearchEvent {
+                id: id.clone(),
+                at: crate::server::users::now(),
+                index,
+                query,
+                filter,
+                hits: response.total_hits,
+                shown: response.hits.len(),
+                page: current_page,
+                latency_ms: started.elapsed().as_millis() as u64,
+                processing_ms: response.processing_time_ms,
+                client: analytics::client().await,
+            });
+            response.search_id = Some(id);
+        }
+
+        Ok(response)
     }
 }
 
+#[server]
+pub async fn semantic_search_available() -> Result<bool, ServerFnError> {
+    Ok(crate::server::embedding::is_enabled())
+}
+
+// The `/readyz` report, polled by the degraded-mode banner
+#[server]
+pub async fn get_backend_status() -> Result<HealthReport, ServerFnError> {
+    Ok(crate::server::health::readiness().await)
+}
+
+#[server]
+pub async fn suggest(prefix: String, index: String) -> Result<Vec<Suggestion>, ServerFnError> {
+    use crate::server::suggest::suggestions;
+
+    suggestions(&index, &prefix)
+        .await
+        .map_err(server_error)
+}
+
 #[server]
 pub async fn get_movie(id: i64) -> Result<Movie, ServerFnError> {
-    use crate::server::meilisearch::get_client;
+    use crate::server::meilisearch::get_index;
 
-    let client = get_client();
-    let index = client.index("movies");
+    let index = get_index("movies");
     let movie: Movie = index
         .get_document(&id.to_string())
         .await
-        .map_err(|e| ServerFnError::new(format!("Movie not found: {e}")))?;
+        .map_err(|e| server_error(format!("Movie not found: {e}")))?;
     Ok(movie)
 }
 
 #[server]
 pub async fn get_book(id: i64) -> Result<Book, ServerFnError> {
-    use crate::server::meilisearch::get_client;
+    use crate::server::meilisearch::get_index;
 
-    let client = get_client();
-    let index = client.index("books");
+    let index = get_index("books");
     let book: Book = index
         .get_document(&id.to_string())
         .await
-        .map_err(|e| ServerFnError::new(format!("Book not found: {e}")))?;
+        .map_err(|e| server_error(format!("Book not found: {e}")))?;
     Ok(book)
 }
 
+#[server]
+pub async fn get_series(id: i64) -> Result<Series, ServerFnError> {
+    use crate::server::meilisearch::get_index;
+
+    let index = get_index("series");
+    let series: Series = index
+        .get_document(&id.to_string())
+        .await
+        .map_err(|e| server_error(format!("Series not found: {e}")))?;
+    Ok(series)
+}
+
+// Exact ISBN lookup; either length is accepted, with or without hyphens
+#[server]
+pub async fn find_book_by_isbn(isbn: String) -> Result<Option<i64>, ServerFnError> {
+    use crate::model::isbn::normalize_isbn;
+    use crate::server::filter::Filter;
+    use crate::server::meilisearch::get_search_index;
+
+    let Some(isbn) = normalize_isbn(&isbn) else {
+        return Ok(None);
+    };
+    let filter = Filter::eq("isbn", isbn.as_str()).to_expression();
+
+    let index = get_search_index("books");
+    let mut search = index.search();
+    search.with_query("");
+    search.with_limit(1);
+    if let Some(ref f) = filter {
+        search.with_filter(f);
+    }
+    let results = search
+        .execute::<Book>()
+        .await
+        .map_err(|e| server_error(format!("ISBN lookup failed: {e}")))?;
+    Ok(results.hits.into_iter().next().map(|h| h.result.id))
+}
+
+#[server]
+pub async fn get_similar(index: String, id: i64) -> Result<Vec<SimilarItem>, ServerFnError> {
+    use crate::server::meilisearch::get_index;
+    use crate::server::similar::similar;
+
+    let ms_index = get_index(&index);
+    let source = match index.as_str() {
+        "movies" => ms_index
+            .get_document::<Movie>(&id.to_string())
+            .await
+            .map(SearchHit::from),
+        "books" => ms_index
+            .get_document::<Book>(&id.to_string())
+            .await
+            .map(SearchHit::from),
+        _ => return Err(server_error(format!("Unknown index: {index}"))),
+    }
+    .map_err(|e| server_error(format!("Item not found: {e}")))?;
+
+    Ok(similar(&source).await)
+}
+
+#[server]
+pub async fn get_person(id: i64) -> Result<PersonDetail, ServerFnError> {
+    use crate::server::people;
+
+    let person = people::get(id).await.map_err(server_error)?;
+    let mut works = people::works(id).await.map_err(server_error)?;
+    crate::server::collections::annotate(&mut works).await;
+    Ok(PersonDetail { person, works })
+}
+
+#[server]
+pub async fn migrate_people() -> Result<MigrationResult, ServerFnError> {
+    use crate::server::{people, users};
+
+    users::require_admin().await.map_err(server_error)?;
+    people::migrate().await.map_err(server_error)
+}
+
+#[server]
+pub async fn list_collections() -> Result<Vec<Collection>, ServerFnError> {
+    crate::server::collections::list()
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn get_collection(id: i64) -> Result<CollectionDetail, ServerFnError> {
+    crate::server::collections::get(id)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn save_collection(collection: Collection) -> Result<Collection, ServerFnError> {
+    use crate::server::{collections, users};
+
+    users::require_admin().await.map_err(server_error)?;
+    collections::save(collection).await.map_err(server_error)
+}
+
+#[server]
+pub async fn delete_collection(id: i64) -> Result<(), ServerFnError> {
+    use crate::server::{collections, users};
+
+    users::require_admin().await.map_err(server_error)?;
+    collections::delete(id).await.map_err(server_error)
+}
+
+#[server]
+pub async fn current_user() -> Result<Option<User>, ServerFnError> {
+    Ok(crate::server::users::current_user().await)
+}
+
+#[server]
+pub async fn register(username: String, password: String) -> Result<User, ServerFnError> {
+    use crate::server::users;
+
+    let (user, token) = users::register(&username, &password)
+        .await
+        .map_err(server_error)?;
+    users::set_session_cookie(&token);
+    Ok(user)
+}
+
+#[server]
+pub async fn login(username: String, password: String) -> Result<User, ServerFnError> {
+    use crate::server::users;
+
+    let (user, token) = users::login(&username, &password)
+        .await
+        .map_err(server_error)?;
+    users::set_session_cookie(&token);
+    Ok(user)
+}
+
+#[server]
+pub async fn logout() -> Result<(), ServerFnError> {
+    use crate::server::users;
+
+    if let Some(token) = users::request_token().await {
+        users::logout(&token).await.map_err(server_error)?;
+    }
+    users::clear_session_cookie();
+    Ok(())
+}
+
+#[server]
+pub async fn get_watchlist_statuses() -> Result<HashMap<String, ListStatus>, ServerFnError> {
+    use crate::server::{users, watchlist};
+
+    // Visitors without an account simply have nothing on their lists
+    let Some(user) = users::current_user().await else {
+        return Ok(HashMap::new());
+    };
+    watchlist::statuses(user.id)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn set_watchlist_status(
+    index: String,
+    id: i64,
+    status: Option<ListStatus>,
+) -> Result<(), ServerFnError> {
+    use crate::server::{users, watchlist};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    watchlist::set_status(user.id, &index, id, status)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn search_watchlist(
+    query: String,
+    index: Option<String>,
+    status: Option<ListStatus>,
+    genre: Option<String>,
+) -> Result<WatchlistResponse, ServerFnError> {
+    use crate::server::{users, watchlist};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    watchlist::search(
+        user.id,
+        query.trim(),
+        index.as_deref(),
+        status,
+        genre.as_deref(),
+    )
+    .await
+    .map_err(server_error)
+}
+
+#[server]
+pub async fn export_watchlist() -> Result<String, ServerFnError> {
+    use crate::server::{users, watchlist};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    let export = watchlist::export(user.id)
+        .await
+        .map_err(server_error)?;
+    serde_json::to_string_pretty(&export)
+        .map_err(|e| server_error(format!("Failed to serialise list: {e}")))
+}
+
+#[server]
+pub async fn import_watchlist(json: String) -> Result<ImportResult, ServerFnError> {
+    use crate::model::user::WatchlistExport;
+    use crate::server::{users, watchlist};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    let export: WatchlistExport = serde_json::from_str(&json)
+        .map_err(|e| server_error(format!("Invalid list file: {e}")))?;
+    watchlist::import(user.id, export)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn get_reviews(
+    index: String,
+    id: i64,
+    query: String,
+    page: usize,
+) -> Result<ReviewPage, ServerFnError> {
+    use crate::server::{reviews, users};
+
+    let viewer = users::current_user().await;
+    reviews::page(&index, id, query.trim(), page, viewer.as_ref())
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn submit_review(
+    index: String,
+    id: i64,
+    score: u8,
+    body: String,
+) -> Result<Review, ServerFnError> {
+    use crate::server::{reviews, users};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    reviews::submit(&user, &index, id, score, body)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn delete_review(index: String, id: i64) -> Result<(), ServerFnError> {
+    use crate::server::{reviews, users};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    reviews::delete(&user, &index, id)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn flag_review(review_id: String, reason: FlagReason) -> Result<bool, ServerFnError> {
+    use crate::server::{reviews, users};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    reviews::flag(&user, &review_id, reason)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn search_reviews(query: String, flagged_only: bool) -> Result<Vec<Review>, ServerFnError> {
+    use crate::server::{reviews, users};
+
+    users::require_admin().await.map_err(server_error)?;
+    reviews::search(query.trim(), flagged_only)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn moderate_review(
+    review_id: String,
+    status: ReviewStatus,
+) -> Result<Review, ServerFnError> {
+    use crate::server::{reviews, users};
+
+    users::require_admin().await.map_err(server_error)?;
+    reviews::moderate(&review_id, status)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn save_search(
+    name: String,
+    request: SearchRequest,
+    webhook_url: Option<String>,
+) -> Result<SavedSearch, ServerFnError> {
+    use crate::server::{saved_searches, users};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    saved_searches::create(&user, name, request, webhook_url)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn list_saved_searches() -> Result<Vec<SavedSearch>, ServerFnError> {
+    use crate::server::{saved_searches, users};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    saved_searches::list(&user).await.map_err(server_error)
+}
+
+#[server]
+pub async fn get_saved_search(id: String) -> Result<SavedSearch, ServerFnError> {
+    use crate::server::{saved_searches, users};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    saved_searches::owned(&user, &id)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn delete_saved_search(id: String) -> Result<(), ServerFnError> {
+    use crate::server::{saved_searches, users};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    saved_searches::delete(&user, &id)
+        .await
+        .map_err(server_error)
+}
+
+// Runs one saved search now instead of waiting for the scheduled job
+#[server]
+pub async fn check_saved_search(id: String) -> Result<Option<Notification>, ServerFnError> {
+    use crate::server::{saved_searches, users};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    let mut search = saved_searches::owned(&user, &id)
+        .await
+        .map_err(server_error)?;
+    saved_searches::run(&mut search)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn get_notifications() -> Result<NotificationFeed, ServerFnError> {
+    use crate::server::{saved_searches, users};
+
+    // Signed-out visitors simply have none
+    match users::current_user().await {
+        Some(user) => saved_searches::notifications(&user)
+            .await
+            .map_err(server_error),
+        None => Ok(NotificationFeed::default()),
+    }
+}
+
+#[server]
+pub async fn mark_notifications_read() -> Result<(), ServerFnError> {
+    use crate::server::{saved_searches, users};
+
+    let user = users::require_user().await.map_err(server_error)?;
+    saved_searches::mark_read(&user)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn log_click(
+    search_id: String,
+    index: String,
+    id: i64,
+    position: usize,
+) -> Result<(), ServerFnError> {
+    crate::server::analytics::log_click(search_id, index, id, position);
+    Ok(())
+}
+
+#[server]
+pub async fn get_analytics_report(days: u64) -> Result<AnalyticsReport, ServerFnError> {
+    use crate::server::{analytics, users};
+
+    users::require_admin().await.map_err(server_error)?;
+    Ok(analytics::report(days.clamp(1, 365)).await)
+}
+
+#[server]
+pub async fn get_item_tags(index: String, id: i64) -> Result<ItemTags, ServerFnError> {
+    use crate::server::{tags, users};
+
+    let viewer = users::current_user().await;
+    tags::item_tags(&index, id, viewer.as_ref())
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn set_item_tags(
+    index: String,
+    id: i64,
+    tags: Vec<String>,
+) -> Result<ItemTags, ServerFnError> {
+    use crate::server::users;
+
+    let user = users::require_user().await.map_err(server_error)?;
+    crate::server::tags::set_tags(&user, &index, id, &tags)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn get_tag_overview() -> Result<TagOverview, ServerFnError> {
+    use crate::server::{tags, users};
+
+    users::require_admin().await.map_err(server_error)?;
+    tags::overview().await.map_err(server_error)
+}
+
+#[server]
+pub async fn merge_tags(from: String, to: String) -> Result<TagOverview, ServerFnError> {
+    use crate::server::{tags, users};
+
+    users::require_admin().await.map_err(server_error)?;
+    tags::merge(&from, &to)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn ban_tag(tag: String) -> Result<TagOverview, ServerFnError> {
+    use crate::server::{tags, users};
+
+    users::require_admin().await.map_err(server_error)?;
+    tags::ban(&tag)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn unban_tag(tag: String) -> Result<TagOverview, ServerFnError> {
+    use crate::server::{tags, users};
+
+    users::require_admin().await.map_err(server_error)?;
+    tags::unban(&tag)
+        .await
+        .map_err(server_error)
+}
+
 #[server]
 pub async fn create_movie(movie: Movie) -> Result<(), ServerFnError> {
-    use crate::server::meilisearch::get_client;
+    use crate::server::embedding::with_vectors;
+    use crate::server::meilisearch::get_index;
+    use crate::server::people::link_one;
+    use crate::server::users;
 
-    let client = get_client();
-    let index = client.index("movies");
+    users::require_admin().await.map_err(server_error)?;
+    let mut movie = movie;
+    movie.normalize();
+    movie.validate().map_err(server_error)?;
+    link_one(Some(&mut movie), None).await.map_err(server_error)?;
+
+    let index = get_index("movies");
     index
-        .add_documents(&[movie], Some("id"))
+        .add_documents(&with_vectors(&[movie]).map_err(server_error)?, Some("id"))
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to create movie: {e}")))?;
+        .map_err(|e| server_error(format!("Failed to create movie: {e}")))?;
     Ok(())
 }
 
 #[server]
 pub async fn create_book(book: Book) -> Result<(), ServerFnError> {
-    use crate::server::meilisearch::get_client;
+    use crate::server::embedding::with_vectors;
+    use crate::server::meilisearch::get_index;
+    use crate::server::people::link_one;
+    use crate::server::users;
+
+    users::require_admin().await.map_err(server_error)?;
+    let mut book = book;
+    book.normalize();
+    book.validate().map_err(server_error)?;
+    link_one(None, Some(&mut book)).await.map_err(server_error)?;
 
-    let client = get_client();
-    let index = client.index("books");
+    let index = get_index("books");
     index
-        .add_documents(&[book], Some("id"))
+        .add_documents(&with_vectors(&[book]).map_err(server_error)?, Some("id"))
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to create book: {e}")))?;
+        .map_err(|e| server_error(format!("Failed to create book: {e}")))?;
     Ok(())
 }
 
 #[server]
 pub async fn update_movie(movie: Movie) -> Result<(), ServerFnError> {
-    use crate::server::meilisearch::get_client;
+    use crate::server::embedding::with_vectors;
+    use crate::server::meilisearch::get_index;
+    use crate::server::people::link_one;
+    use crate::server::users;
+
+    users::require_admin().await.map_err(server_error)?;
+    let mut movie = movie;
+    movie.normalize();
+    movie.validate().map_err(server_error)?;
+    link_one(Some(&mut movie), None).await.map_err(server_error)?;
 
-    let client = get_client();
-    let index = client.index("movies");
+    let index = get_index("movies");
     index
-        .add_documents(&[movie], Some("id"))
+        .add_documents(&with_vectors(&[movie]).map_err(server_error)?, Some("id"))
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to update movie: {e}")))?;
+        .map_err(|e| server_error(format!("Failed to update movie: {e}")))?;
     Ok(())
 }
 
 #[server]
 pub async fn update_book(book: Book) -> Result<(), ServerFnError> {
-    use crate::server::meilisearch::get_client;
+    use crate::server::embedding::with_vectors;
+    use crate::server::meilisearch::get_index;
+    use crate::server::people::link_one;
+    use crate::server::users;
 
-    let client = get_client();
-    let index = client.index("books");
+    users::require_admin().await.map_err(server_error)?;
+    let mut book = book;
+    book.normalize();
+    book.validate().map_err(server_error)?;
+    link_one(None, Some(&mut book)).await.map_err(server_error)?;
+
+    let index = get_index("books");
     index
-        .add_documents(&[book], Some("id"))
+        .add_documents(&with_vectors(&[book]).map_err(server_error)?, Some("id"))
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to update book: {e}")))?;
+        .map_err(|e| server_error(format!("Failed to update book: {e}")))?;
     Ok(())
 }
 
 #[server]
 pub async fn delete_movie(id: i64) -> Result<(), ServerFnError> {
-    use crate::server::meilisearch::get_client;
+    use crate::server::meilisearch::get_index;
+    use crate::server::users;
 
-    let client = get_client();
-    let index = client.index("movies");
+    users::require_admin().await.map_err(server_error)?;
+    let index = get_index("movies");
     index
         .delete_document(&id.to_string())
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to delete movie: {e}")))?;
+        .map_err(|e| server_error(format!("Failed to delete movie: {e}")))?;
     Ok(())
 }
 
 #[server]
 pub async fn delete_book(id: i64) -> Result<(), ServerFnError> {
-    use crate::server::meilisearch::get_client;
+    use crate::server::meilisearch::get_index;
+    use crate::server::users;
 
-    let client = get_client();
-    let index = client.index("books");
+    users::require_admin().await.map_err(server_error)?;
+    let index = get_index("books");
     index
         .delete_document(&id.to_string())
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to delete book: {e}")))?;
+        .map_err(|e| server_error(format!("Failed to delete book: {e}")))?;
     Ok(())
 }
 
 #[server]
 pub async fn seed_data() -> Result<SeedResult, ServerFnError> {
-    use crate::server::seed;
+    use crate::server::{collections, people, reviews, seed, tags, users};
 
-    let movies_count = seed::seed_movies()
-        .await
-        .map_err(|e| ServerFnError::new(e))?;
-    let books_count = seed::seed_books()
-        .await
-        .map_err(|e| ServerFnError::new(e))?;
+    users::require_admin().await.map_err(server_error)?;
+    let movies_count = seed::seed_movies().await.map_err(server_error)?;
+    let books_count = seed::seed_books().await.map_err(server_error)?;
+    let series_count = seed::seed_series().await.map_err(server_error)?;
+    people::migrate().await.map_err(server_error)?;
+    collections::seed().await.map_err(server_error)?;
+    // Seeding replaced the documents, and with them their user ratings and tags
+    reviews::refresh_all().await.map_err(server_error)?;
+    tags::refresh_all().await.map_err(server_error)?;
 
     Ok(SeedResult {
         movies_count,
         books_count,
+        series_count,
     })
 }
 
@@ -288,43 +901,50 @@ pub async fn seed_data() -> Result<SeedResult, ServerFnError> {
 pub struct SeedResult {
     pub movies_count: usize,
     pub books_count: usize,
+    pub series_count: usize,
 }
 
 #[server]
 pub async fn get_facets(index: String) -> Result<FacetInfo, ServerFnError> {
-    use crate::server::meilisearch::get_client;
+    use crate::server::meilisearch::get_search_index;
+    use crate::server::schema::schema;
 
-    let client = get_client();
-    let ms_index = client.index(&index);
+    let schema = schema(&index).map_err(server_error)?;
+    let facets = schema.facets;
+
+    let ms_index = get_search_index(&index);
 
     let mut search = ms_index.search();
     search.with_query("");
     search.with_limit(0);
-    search.with_facets(meilisearch_sdk::search::Selectors::Some(&[
-        "genres",
-        "year",
-        "language",
-    ]));
+    search.with_facets(meilisearch_sdk::search::Selectors::Some(facets));
 
     if index == "movies" {
         let results = search
             .execute::<Movie>()
             .await
-            .map_err(|e| ServerFnError::new(format!("Facet query failed: {e}")))?;
+            .map_err(|e| server_error(format!("Facet query failed: {e}")))?;
+
+        Ok(parse_facets(results.facet_distribution))
+    } else if index == "series" {
+        let results = search
+            .execute::<Series>()
+            .await
+            .map_err(|e| server_error(format!("Facet query failed: {e}")))?;
 
         Ok(parse_facets(results.facet_distribution))
     } else if index == "web" {
         let results = search
             .execute::<WebResult>()
             .await
-            .map_err(|e| ServerFnError::new(format!("Facet query failed: {e}")))?;
+            .map_err(|e| server_error(format!("Facet query failed: {e}")))?;
 
         Ok(parse_facets(results.facet_distribution))
     } else {
         let results = search
             .execute::<Book>()
             .await
-            .map_err(|e| ServerFnError::new(format!("Facet query failed: {e}")))?;
+            .map_err(|e| server_error(format!("Facet query failed: {e}")))?;
 
         Ok(parse_facets(results.facet_distribution))
     }
@@ -332,12 +952,10 @@ pub async fn get_facets(index: String) -> Result<FacetInfo, ServerFnError> {
 
 #[server]
 pub async fn search_web_and_import(query: String) -> Result<SearchResponse, ServerFnError> {
-    use crate::server::meilisearch::{configure_web_index, get_client};
     use crate::server::searxng::search_web;
+    use crate::server::web_fallback::import_web_results;
 
-    let web_results = search_web(&query)
-        .await
-        .map_err(|e| ServerFnError::new(e))?;
+    let web_results = search_web(&query).await.map_err(server_error)?;
 
     if web_results.is_empty() {
         return Ok(SearchResponse {
@@ -346,42 +964,22 @@ pub async fn search_web_and_import(query: String) -> Result<SearchResponse, Serv
             page: 1,
             total_pages: 0,
             processing_time_ms: 0,
+            web_hits: vec![],
+            suggestions: vec![],
+            collections: vec![],
+            search_id: None,
         });
     }
 
-    // Configure web index and add documents
-    configure_web_index()
+    import_web_results(&web_results)
         .await
-        .map_err(|e| ServerFnError::new(e))?;
-
-    let client = get_client();
-    let index = client.index("web");
-
-    index
-        .add_documents(&web_results, Some("id"))
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to index web results: {e}")))?;
+        .map_err(server_error)?;
 
     // Wait briefly for indexing
     actix_web::rt::time::sleep(std::time::Duration::from_millis(500)).await;
 
     let total_hits = web_results.len();
-    let hits: Vec<SearchHit> = web_results
-        .into_iter()
-        .map(|w| SearchHit {
-            id: w.id,
-            title: w.title,
-            title_en: w.title_en,
-            description: w.description,
-            creator: w.url.clone(),
-            year: w.year,
-            genres: w.genres,
-            rating: w.rating,
-            image_url: w.image_url,
-            language: w.language,
-            index: "web".to_string(),
-        })
-        .collect();
+    let hits: Vec<SearchHit> = web_results.into_iter().map(web_hit).collect();
 
     Ok(SearchResponse {
         hits,
@@ -389,22 +987,98 @@ pub async fn search_web_and_import(query: String) -> Result<SearchResponse, Serv
         page: 1,
         total_pages: 1,
         processing_time_ms: 0,
+        web_hits: vec![],
+        suggestions: vec![],
+        collections: vec![],
+        search_id: None,
     })
 }
 
 #[server]
 pub async fn get_web_result(id: i64) -> Result<WebResult, ServerFnError> {
-    use crate::server::meilisearch::get_client;
+    use crate::server::meilisearch::get_index;
 
-    let client = get_client();
-    let index = client.index("web");
+    let index = get_index("web");
     let result: WebResult = index
         .get_document(&id.to_string())
         .await
-        .map_err(|e| ServerFnError::new(format!("Web result not found: {e}")))?;
+        .map_err(|e| server_error(format!("Web result not found: {e}")))?;
     Ok(result)
 }
 
+#[server]
+pub async fn get_search_settings(index: String) -> Result<SearchSettings, ServerFnError> {
+    use crate::server::search_settings;
+
+    search_settings::latest(&index).await.map_err(server_error)
+}
+
+// The attributes typo tolerance can be turned off for
+#[server]
+pub async fn get_searchable_attributes(index: String) -> Result<Vec<String>, ServerFnError> {
+    use crate::server::schema::schema;
+
+    let schema = schema(&index).map_err(server_error)?;
+    Ok(schema.searchable.iter().map(|a| a.to_string()).collect())
+}
+
+#[server]
+pub async fn get_search_settings_history(
+    index: String,
+) -> Result<Vec<SearchSettings>, ServerFnError> {
+    use crate::server::search_settings;
+
+    search_settings::history(&index).await.map_err(server_error)
+}
+
+#[server]
+pub async fn save_search_settings(
+    settings: SearchSettings,
+) -> Result<SearchSettings, ServerFnError> {
+    use crate::server::{search_settings, users};
+
+    users::require_admin().await.map_err(server_error)?;
+    search_settings::check(&settings).map_err(server_error)?;
+    search_settings::save(settings)
+        .await
+        .map_err(server_error)
+}
+
+#[server]
+pub async fn preview_search_settings(
+    settings: SearchSettings,
+    query: String,
+) -> Result<SettingsPreview, ServerFnError> {
+    use crate::server::{search_settings, users};
+
+    users::require_admin().await.map_err(server_error)?;
+    search_settings::check(&settings).map_err(server_error)?;
+    search_settings::preview(&settings, &query)
+        .await
+        .map_err(server_error)
+}
+
+#[cfg(feature = "ssr")]
+fn web_hit(w: WebResult) -> SearchHit {
+    SearchHit {
+        id: w.id,
+        title: w.title,
+        title_en: w.title_en,
+        description: w.description,
+        creator: w.url,
+        creator_id: None,
+        year: w.year,
+        genres: w.genres,
+        rating: w.rating,
+        image_url: w.image_url,
+        language: w.language,
+        index: "web".to_string(),
+        series: None,
+        user_rating: None,
+        user_rating_count: 0,
+    }
+}
+
 #[cfg(feature = "ssr")]
 fn parse_facets(
     facet_distribution: Option<
@@ -441,6 +1115,62 @@ fn parse_facets(
                 })
                 .collect();
         }
+        if let Some(countries) = dist.get("countries") {
+            info.countries = countries
+                .iter()
+                .map(|(k, v)| FacetValue {
+                    value: k.clone(),
+                    count: *v,
+                })
+                .collect();
+        }
+        if let Some(certifications) = dist.get("certification") {
+            info.certifications = certifications
+                .iter()
+                .map(|(k, v)| FacetValue {
+                    value: k.clone(),
+                    count: *v,
+                })
+                .collect();
+        }
+        if let Some(publishers) = dist.get("publisher") {
+            info.publishers = publishers
+                .iter()
+                .map(|(k, v)| FacetValue {
+                    value: k.clone(),
+                    count: *v,
+                })
+                .collect();
+        }
+        if let Some(formats) = dist.get("format") {
+            info.formats = formats
+                .iter()
+                .map(|(k, v)| FacetValue {
+                    value: k.clone(),
+                    count: *v,
+                })
+                .collect();
+        }
+        if let Some(tags) = dist.get("tags") {
+            info.tags = tags
+                .iter()
+                .map(|(k, v)| FacetValue {
+                    value: k.clone(),
+                    count: *v,
+                })
+                .collect();
+            // A tag cloud reads alphabetically
+            info.tags.sort_by(|a, b| a.value.cmp(&b.value));
+        }
+        if let Some(statuses) = dist.get("status") {
+            info.statuses = statuses
+                .iter()
+                .map(|(k, v)| FacetValue {
+                    value: k.clone(),
+                    count: *v,
+                })
+                .collect();
+        }
     }
 
     info
@@ -5,11 +5,21 @@ use leptos_router::{
     path,
 };
 
-use crate::pages::{detail::DetailPage, home::HomePage, not_found::NotFoundPage};
+use crate::components::account::{provide_account, use_account, AccountMenu, AdminOnly};
+use crate::components::status_banner::StatusBanner;
+use crate::pages::{
+    admin_analytics::AnalyticsAdminPage, admin_collections::CollectionsAdminPage, admin_reviews::ReviewsAdminPage,
+    admin_settings::SearchSettingsPage, admin_tags::TagsAdminPage,
+    collection::CollectionPage, detail::DetailPage, home::HomePage, login::LoginPage,
+    not_found::NotFoundPage, notifications::NotificationsPage, person::PersonPage,
+    saved_searches::SavedSearchesPage, watchlist::WatchlistPage,
+};
 
 #[component]
 pub fn App() -> impl IntoView {
     provide_meta_context();
+    provide_account();
+    let is_admin = use_account().is_admin();
 
     view! {
         <Stylesheet id="leptos" href="/pkg/meilisearch-search-app.css"/>
@@ -20,8 +30,17 @@ pub fn App() -> impl IntoView {
             <nav class="navbar">
                 <div class="navbar-inner">
                     <a href="/" class="logo">"Meilisearch 検索"</a>
+                    <Show when=move || is_admin.get()>
+                        <a href="/admin/collections" class="nav-link">"コレクション"</a>
+                        <a href="/admin/search-settings" class="nav-link">"検索設定"</a>
+                        <a href="/admin/reviews" class="nav-link">"レビュー管理"</a>
+                        <a href="/admin/tags" class="nav-link">"タグ管理"</a>
+                        <a href="/admin/analytics" class="nav-link">"検索分析"</a>
+                    </Show>
+                    <AccountMenu/>
                 </div>
             </nav>
+            <StatusBanner/>
             <main class="main-content">
                 <Routes fallback=NotFoundPage>
                     <Route path=path!("/") view=HomePage/>
@@ -31,6 +50,30 @@ pub fn App() -> impl IntoView {
                     <Route path=path!("/book/:id") view=move || {
                         view! { <DetailPage index="books".to_string()/> }
                     }/>
+                    <Route path=path!("/series/:id") view=move || {
+                        view! { <DetailPage index="series".to_string()/> }
+                    }/>
+                    <Route path=path!("/person/:id") view=PersonPage/>
+                    <Route path=path!("/collection/:id") view=CollectionPage/>
+                    <Route path=path!("/login") view=LoginPage/>
+                    <Route path=path!("/watchlist") view=WatchlistPage/>
+                    <Route path=path!("/saved") view=SavedSearchesPage/>
+                    <Route path=path!("/notifications") view=NotificationsPage/>
+                    <Route path=path!("/admin/collections") view=move || {
+                        view! { <AdminOnly><CollectionsAdminPage/></AdminOnly> }
+                    }/>
+                    <Route path=path!("/admin/search-settings") view=move || {
+                        view! { <AdminOnly><SearchSettingsPage/></AdminOnly> }
+                    }/>
+                    <Route path=path!("/admin/reviews") view=move || {
+                        view! { <AdminOnly><ReviewsAdminPage/></AdminOnly> }
+                    }/>
+                    <Route path=path!("/admin/tags") view=move || {
+                        view! { <AdminOnly><TagsAdminPage/></AdminOnly> }
+                    }/>
+                    <Route path=path!("/admin/analytics") view=move || {
+                        view! { <AdminOnly><AnalyticsAdminPage/></AdminOnly> }
+                    }/>
                     <Route path=path!("/web/:id") view=move || {
                         view! { <DetailPage index="web".to_string()/> }
                     }/>
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::api::{current_user, get_notifications, get_watchlist_statuses, logout};
+use crate::model::user::{ListStatus, User};
+
+// The signed-in user and what is on their lists, shared by the navbar, the
+// list buttons on cards and detail pages, and the watchlist page
+#[derive(Debug, Clone, Copy)]
+pub struct Account {
+    pub user: RwSignal<Option<User>>,
+    // `movies:12` → status
+    pub statuses: RwSignal<HashMap<String, ListStatus>>,
+    // Unread saved-search notifications, shown as a badge
+    pub unread: RwSignal<usize>,
+}
+
+impl Account {
+    pub fn reload(&self) {
+        let account = *self;
+        spawn_local(async move {
+            let user = current_user().await.ok().flatten();
+            let (statuses, unread) = if user.is_some() {
+                (
+                    get_watchlist_statuses().await.unwrap_or_default(),
+                    get_notifications().await.map(|f| f.unread).unwrap_or(0),
+                )
+            } else {
+                (HashMap::new(), 0)
+            };
+            account.user.set(user);
+            account.statuses.set(statuses);
+            account.unread.set(unread);
+        });
+    }
+
+    pub fn status_of(&self, index: &str, id: i64) -> Option<ListStatus> {
+        self.statuses
+            .with(|s| s.get(&format!("{index}:{id}")).copied())
+    }
+}
+
+pub fn provide_account() {
+    let account = Account {
+        user: RwSignal::new(None),
+        statuses: RwSignal::new(HashMap::new()),
+        unread: RwSignal::new(0),
+    };
+    provide_context(account);
+    // The session cookie is HttpOnly, so the user is looked up once hydrated
+    Effect::new(move |_| account.reload());
+}
+
+pub fn use_account() -> Account {
+    expect_context::<Account>()
+}
+
+impl Account {
+    // Only changes on login and logout, unlike `user`, which every reload sets
+    pub fn is_admin(&self) -> Memo<bool> {
+        let user = self.user;
+        Memo::new(move |_| user.with(|u| u.as_ref().is_some_and(|u| u.is_admin)))
+    }
+}
+
+// Wraps the admin pages; the server functions behind them check again
+#[component]
+pub fn AdminOnly(children: ChildrenFn) -> impl IntoView {
+    let account = use_account();
+    let is_admin = account.is_admin();
+
+    move || {
+        if is_admin.get() {
+            children().into_any()
+        } else {
+            view! {
+                <div class="admin-page">
+                    <p class="watchlist-empty">
+                        "このページは管理者のみ利用できます。"
+                        <a href="/login">"ログイン"</a>
+                    </p>
+                </div>
+            }
+            .into_any()
+        }
+    }
+}
+
+#[component]
+pub fn AccountMenu() -> impl IntoView {
+    let account = use_account();
+
+    let on_logout = move |_| {
+        spawn_local(async move {
+            let _ = logout().await;
+            account.reload();
+        });
+    };
+
+    view! {
+        <div class="account-menu">
+            {move || match account.user.get() {
+                Some(user) => view! {
+                    <a href="/watchlist" class="nav-link">"マイリスト"</a>
+                    <a href="/saved" class="nav-link">"保存した検索"</a>
+                    <a href="/notifications" class="nav-link">
+                        "通知"
+                        {move || {
+                            let unread = account.unread.get();
+                            (unread > 0).then(|| view! { <span class="notification-badge">{unread}</span> })
+                        }}
+                    </a>
+                    <span class="account-name">{user.username}</span>
+                    <button class="page-btn" on:click=on_logout>"ログアウト"</button>
+                }.into_any(),
+                None => view! {
+                    <a href="/login" class="nav-link">"ログイン"</a>
+                }.into_any(),
+            }}
+        </div>
+    }
+}
@@ -1,13 +1,61 @@
 use leptos::prelude::*;
 use wasm_bindgen::JsCast;
 
-use crate::model::search::FacetInfo;
+use crate::model::book::BookFormat;
+use crate::model::movie::country_label;
+use crate::model::search::{BookFilters, FacetInfo, FacetValue, MovieFilters, SeriesFilters};
+use crate::model::series::SeriesStatus;
+use crate::model::tag::cloud_weight;
+
+fn toggle(values: &mut Vec<String>, value: &str) {
+    if values.iter().any(|v| v == value) {
+        values.retain(|v| v != value);
+    } else {
+        values.push(value.to_string());
+    }
+}
+
+// Checkboxes for an index-specific facet, e.g. countries or publishers
+fn filter_checkboxes<T: Clone + Send + Sync + 'static>(
+    values: Vec<FacetValue>,
+    filters: ReadSignal<T>,
+    set_filters: WriteSignal<T>,
+    selected: fn(&mut T) -> &mut Vec<String>,
+    label: fn(&str) -> &str,
+) -> impl IntoView {
+    view! {
+        <div class="genre-checkboxes">
+            {values.into_iter().map(|fv| {
+                let value = fv.value.clone();
+                let value_for_check = value.clone();
+                let text = format!("{} ({})", label(&value), fv.count);
+                view! {
+                    <label class="genre-checkbox">
+                        <input
+                            type="checkbox"
+                            prop:checked=move || {
+                                let mut current = filters.get();
+                                selected(&mut current).contains(&value_for_check)
+                            }
+                            on:change=move |_| {
+                                set_filters.update(|f| toggle(selected(f), &value));
+                            }
+                        />
+                        <span>{text}</span>
+                    </label>
+                }
+            }).collect_view()}
+        </div>
+    }
+}
 
 #[component]
 pub fn FacetPanel(
     facets: Signal<Option<Result<FacetInfo, ServerFnError>>>,
     selected_genres: ReadSignal<Vec<String>>,
     set_selected_genres: WriteSignal<Vec<String>>,
+    selected_tags: ReadSignal<Vec<String>>,
+    set_selected_tags: WriteSignal<Vec<String>>,
     year_min: ReadSignal<Option<i32>>,
     set_year_min: WriteSignal<Option<i32>>,
     year_max: ReadSignal<Option<i32>>,
@@ -15,7 +63,32 @@ pub fn FacetPanel(
     rating_min: ReadSignal<Option<f64>>,
     set_rating_min: WriteSignal<Option<f64>>,
     set_sort: WriteSignal<Option<String>>,
+    semantic_available: Signal<bool>,
+    semantic_ratio: ReadSignal<f32>,
+    set_semantic_ratio: WriteSignal<f32>,
+    movie_filters: ReadSignal<MovieFilters>,
+    set_movie_filters: WriteSignal<MovieFilters>,
+    book_filters: ReadSignal<BookFilters>,
+    set_book_filters: WriteSignal<BookFilters>,
+    series_filters: ReadSignal<SeriesFilters>,
+    set_series_filters: WriteSignal<SeriesFilters>,
+    // Series span several years, so the year range matches any overlap
+    period_years: Signal<bool>,
 ) -> impl IntoView {
+    // Movie-only sections appear once the index reports movie facets
+    let movie_facets = Signal::derive(move || {
+        facets
+            .get()
+            .and_then(|r| r.ok())
+            .filter(|info| !info.countries.is_empty() || !info.certifications.is_empty())
+    });
+    let book_facets = Signal::derive(move || {
+        facets
+            .get()
+            .and_then(|r| r.ok())
+            .filter(|info| !info.publishers.is_empty() || !info.formats.is_empty())
+    });
+
     view! {
         <aside class="facet-panel">
             <h3 class="facet-title">"フィルタ"</h3>
@@ -42,12 +115,57 @@ pub fn FacetPanel(
                     <option value="rating:desc">"評価 (高い順)"</option>
                     <option value="rating:asc">"評価 (低い順)"</option>
                     <option value="title:asc">"タイトル (A→Z)"</option>
+                    {move || movie_facets.get().is_some().then(|| view! {
+                        <option value="runtime_minutes:asc">"上映時間 (短い順)"</option>
+                        <option value="runtime_minutes:desc">"上映時間 (長い順)"</option>
+                        <option value="release_date:desc">"公開日 (新しい順)"</option>
+                    })}
+                    {move || {
+                        let catalogue = movie_facets.get().is_some()
+                            || book_facets.get().is_some()
+                            || period_years.get();
+                        catalogue.then(|| view! {
+                            <option value="user_rating:desc">"みんなの評価 (高い順)"</option>
+                            <option value="user_rating_count:desc">"レビュー数 (多い順)"</option>
+                        })
+                    }}
+                    {move || period_years.get().then(|| view! {
+                        <option value="episode_count:desc">"話数 (多い順)"</option>
+                    })}
+                    {move || book_facets.get().is_some().then(|| view! {
+                        <option value="publication_date:desc">"刊行日 (新しい順)"</option>
+                        <option value="series_number:asc">"巻数順"</option>
+                    })}
                 </select>
             </div>
 
+            // Keyword ↔ semantic balance
+            {move || semantic_available.get().then(|| view! {
+                <div class="facet-section">
+                    <h4>"検索モード"</h4>
+                    <div class="semantic-filter">
+                        <span class="semantic-label">"キーワード"</span>
+                        <input
+                            type="range"
+                            min="0"
+                            max="1"
+                            step="0.1"
+                            class="semantic-slider"
+                            prop:value=move || semantic_ratio.get().to_string()
+                            on:input=move |ev| {
+                                let target = ev.target().unwrap();
+                                let input = target.unchecked_ref::<web_sys::HtmlInputElement>();
+                                set_semantic_ratio.set(input.value().parse().unwrap_or(0.0));
+                            }
+                        />
+                        <span class="semantic-label">"意味"</span>
+                    </div>
+                </div>
+            })}
+
             // Year range
             <div class="facet-section">
-                <h4>"年範囲"</h4>
+                <h4>{move || if period_years.get() { "放送年 (期間が重なる作品)" } else { "年範囲" }}</h4>
                 <div class="range-inputs">
                     <input
                         type="number"
@@ -77,6 +195,106 @@ pub fn FacetPanel(
                 </div>
             </div>
 
+            // Movie runtime, countries and certification
+            {move || movie_facets.get().map(|info| view! {
+                <div class="facet-section">
+                    <h4>"上映時間 (分)"</h4>
+                    <div class="range-inputs">
+                        <input
+                            type="number"
+                            min="1"
+                            class="range-input"
+                            placeholder="最短"
+                            prop:value=move || movie_filters.get().runtime_min.map(|v| v.to_string()).unwrap_or_default()
+                            on:change=move |ev| {
+                                let target = ev.target().unwrap();
+                                let input = target.unchecked_ref::<web_sys::HtmlInputElement>();
+                                let val = input.value().parse().ok();
+                                set_movie_filters.update(|f| f.runtime_min = val);
+                            }
+                        />
+                        <span class="range-sep">"〜"</span>
+                        <input
+                            type="number"
+                            min="1"
+                            class="range-input"
+                            placeholder="最長"
+                            prop:value=move || movie_filters.get().runtime_max.map(|v| v.to_string()).unwrap_or_default()
+                            on:change=move |ev| {
+                                let target = ev.target().unwrap();
+                                let input = target.unchecked_ref::<web_sys::HtmlInputElement>();
+                                let val = input.value().parse().ok();
+                                set_movie_filters.update(|f| f.runtime_max = val);
+                            }
+                        />
+                    </div>
+                </div>
+                <div class="facet-section">
+                    <h4>"製作国"</h4>
+                    {filter_checkboxes(
+                        info.countries,
+                        movie_filters,
+                        set_movie_filters,
+                        |f| &mut f.countries,
+                        country_label,
+                    )}
+                </div>
+                <div class="facet-section">
+                    <h4>"レーティング"</h4>
+                    {filter_checkboxes(
+                        info.certifications,
+                        movie_filters,
+                        set_movie_filters,
+                        |f| &mut f.certifications,
+                        |c| c,
+                    )}
+                </div>
+            })}
+
+            // Book publisher and format
+            {move || book_facets.get().map(|info| view! {
+                <div class="facet-section">
+                    <h4>"出版社"</h4>
+                    {filter_checkboxes(
+                        info.publishers,
+                        book_filters,
+                        set_book_filters,
+                        |f| &mut f.publishers,
+                        |p| p,
+                    )}
+                </div>
+                <div class="facet-section">
+                    <h4>"形態"</h4>
+                    {filter_checkboxes(
+                        info.formats,
+                        book_filters,
+                        set_book_filters,
+                        |f| &mut f.formats,
+                        BookFormat::label_of,
+                    )}
+                </div>
+            })}
+
+            // Series broadcast status
+            {move || {
+                facets
+                    .get()
+                    .and_then(|r| r.ok())
+                    .filter(|info| !info.statuses.is_empty())
+                    .map(|info| view! {
+                        <div class="facet-section">
+                            <h4>"放送状況"</h4>
+                            {filter_checkboxes(
+                                info.statuses,
+                                series_filters,
+                                set_series_filters,
+                                |f| &mut f.statuses,
+                                SeriesStatus::label_of,
+                            )}
+                        </div>
+                    })
+            }}
+
             // Rating minimum
             <div class="facet-section">
                 <h4>"最低評価"</h4>
@@ -149,15 +367,57 @@ pub fn FacetPanel(
                 }}
             </div>
 
+            // User tags, sized by how many items carry them
+            {move || {
+                facets
+                    .get()
+                    .and_then(|r| r.ok())
+                    .filter(|info| !info.tags.is_empty())
+                    .map(|info| {
+                        let max = info.tags.iter().map(|fv| fv.count).max().unwrap_or(0);
+                        view! {
+                            <div class="facet-section">
+                                <h4>"タグ"</h4>
+                                <div class="tag-cloud">
+                                    {info.tags.into_iter().map(|fv| {
+                                        let tag = fv.value.clone();
+                                        let tag_for_class = tag.clone();
+                                        let class = format!("tag-cloud-item weight-{}", cloud_weight(fv.count, max));
+                                        view! {
+                                            <button
+                                                class=move || {
+                                                    if selected_tags.get().contains(&tag_for_class) {
+                                                        format!("{class} active")
+                                                    } else {
+                                                        class.clone()
+                                                    }
+                                                }
+                                                title=format!("{} 件", fv.count)
+                                                on:click=move |_| set_selected_tags.update(|t| toggle(t, &tag))
+                                            >
+                                                {fv.value}
+                                            </button>
+                                        }
+                                    }).collect_view()}
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+
             // Clear filters
             <button
                 class="clear-filters"
                 on:click=move |_| {
                     set_selected_genres.set(vec![]);
+                    set_selected_tags.set(vec![]);
                     set_year_min.set(None);
                     set_year_max.set(None);
                     set_rating_min.set(None);
                     set_sort.set(None);
+                    set_movie_filters.set(MovieFilters::default());
+                    set_book_filters.set(BookFilters::default());
+                    set_series_filters.set(SeriesFilters::default());
                 }
             >
                 "フィルタをクリア"
@@ -1,5 +1,12 @@
+pub mod account;
 pub mod facet_panel;
 pub mod pagination;
+pub mod related_items;
 pub mod result_card;
+pub mod reviews;
+pub mod save_search;
 pub mod search_bar;
 pub mod search_results;
+pub mod status_banner;
+pub mod tags;
+pub mod watchlist_buttons;
@@ -0,0 +1,38 @@
+use leptos::prelude::*;
+
+use crate::api::get_similar;
+use crate::components::result_card::ResultCard;
+
+#[component]
+pub fn RelatedItems(index: &'static str, id: Signal<i64>) -> impl IntoView {
+    let related = Resource::new(
+        move || id.get(),
+        move |id| async move { get_similar(index.to_string(), id).await },
+    );
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || {
+                related.get().and_then(|result| result.ok()).filter(|items| !items.is_empty()).map(|items| {
+                    view! {
+                        <section class="related">
+                            <h2 class="related-title">"関連作品"</h2>
+                            <div class="related-carousel">
+                                {items.into_iter().map(|item| view! {
+                                    <div class="related-item">
+                                        <ResultCard hit=item.hit/>
+                                        <ul class="related-reasons">
+                                            {item.reasons.into_iter().map(|r| view! {
+                                                <li class="related-reason">{r}</li>
+                                            }).collect_view()}
+                                        </ul>
+                                    </div>
+                                }).collect_view()}
+                            </div>
+                        </section>
+                    }
+                })
+            }}
+        </Suspense>
+    }
+}
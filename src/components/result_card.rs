@@ -1,19 +1,29 @@
 use leptos::prelude::*;
 
+use crate::components::watchlist_buttons::WatchlistButtons;
 use crate::model::search::SearchHit;
 
 #[component]
-pub fn ResultCard(hit: SearchHit) -> impl IntoView {
+pub fn ResultCard(
+    hit: SearchHit,
+    // `click_params` of the search the card was found with
+    #[prop(default = None)] click: Option<String>,
+) -> impl IntoView {
     let detail_url = if hit.index == "movies" {
         format!("/movie/{}", hit.id)
+    } else if hit.index == "series" {
+        format!("/series/{}", hit.id)
     } else if hit.index == "web" {
         format!("/web/{}", hit.id)
     } else {
         format!("/book/{}", hit.id)
     };
+    let detail_url = format!("{detail_url}{}", click.unwrap_or_default());
 
     let type_label = if hit.index == "movies" {
         "映画"
+    } else if hit.index == "series" {
+        "シリーズ"
     } else if hit.index == "web" {
         "Web"
     } else {
@@ -21,13 +31,16 @@ pub fn ResultCard(hit: SearchHit) -> impl IntoView {
     };
 
     let is_web = hit.index == "web";
+    let list_index = hit.index.clone();
     let rating_display = format!("{:.1}", hit.rating);
-    let creator_year = if is_web {
-        hit.creator.clone()
-    } else {
-        format!("{} ({})", hit.creator, hit.year)
-    };
+    let person_url = hit.creator_id.map(|id| format!("/person/{id}"));
+    let creator = hit.creator.clone();
+    let year = (!is_web).then(|| format!(" ({})", hit.year));
     let show_rating = !is_web || hit.rating > 0.0;
+    let user_rating = hit
+        .user_rating
+        .filter(|_| hit.user_rating_count > 0)
+        .map(|r| format!("みんな {r:.1} ({})", hit.user_rating_count));
 
     let description_truncated = if hit.description.chars().count() > 120 {
         let truncated: String = hit.description.chars().take(120).collect();
@@ -37,7 +50,9 @@ pub fn ResultCard(hit: SearchHit) -> impl IntoView {
     };
 
     view! {
-        <a href=detail_url class="result-card">
+        <div class="result-card">
+            // Covers the whole card; the creator link sits above it
+            <a href=detail_url class="card-link" aria-label=hit.title.clone()></a>
             <div class="card-header">
                 <span class="card-type">{type_label}</span>
                 {if show_rating {
@@ -45,18 +60,31 @@ pub fn ResultCard(hit: SearchHit) -> impl IntoView {
                 } else {
                     None
                 }}
+                {user_rating.map(|r| view! { <span class="card-user-rating">{r}</span> })}
             </div>
+            {hit.series.map(|series| view! {
+                <a href=format!("/collection/{}", series.collection_id) class="card-series">
+                    {format!("{} #{}/{}", series.collection_name, series.position, series.total)}
+                </a>
+            })}
             <h3 class="card-title">{hit.title.clone()}</h3>
             {hit.title_en.map(|en| view! {
                 <p class="card-title-en">{en}</p>
             })}
-            <p class="card-creator">{creator_year}</p>
+            <p class="card-creator">
+                {match person_url {
+                    Some(url) => view! { <a href=url class="card-creator-link">{creator}</a> }.into_any(),
+                    None => view! { <span>{creator}</span> }.into_any(),
+                }}
+                {year}
+            </p>
             <p class="card-description">{description_truncated}</p>
             <div class="card-genres">
                 {hit.genres.into_iter().map(|g| view! {
                     <span class="genre-tag">{g}</span>
                 }).collect_view()}
             </div>
-        </a>
+            <WatchlistButtons index=list_index id=hit.id/>
+        </div>
     }
 }
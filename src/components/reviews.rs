@@ -0,0 +1,240 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::api::{delete_review, flag_review, get_reviews, submit_review};
+use crate::components::account::use_account;
+use crate::components::pagination::Pagination;
+use crate::model::review::{FlagReason, RatingSummary, Review, SCORE_MAX, SCORE_MIN};
+use crate::pages::admin_settings::{input_value, textarea_value};
+
+#[component]
+pub fn Reviews(index: &'static str, id: Signal<i64>) -> impl IntoView {
+    let account = use_account();
+    let (query, set_query) = signal(String::new());
+    let (page, set_page) = signal(1usize);
+    // Bumped after a change so the page and summary are fetched again
+    let (version, set_version) = signal(0u32);
+    let (message, set_message) = signal(Option::<String>::None);
+
+    let reviews = Resource::new(
+        move || (id.get(), query.get(), page.get(), version.get(), account.user.get()),
+        move |(id, query, page, _, _)| async move {
+            get_reviews(index.to_string(), id, query, page).await
+        },
+    );
+
+    let total_pages = Signal::derive(move || {
+        reviews
+            .get()
+            .and_then(|r| r.ok())
+            .map(|p| p.total_pages)
+            .unwrap_or(0)
+    });
+
+    let reload = move || set_version.update(|v| *v += 1);
+
+    view! {
+        <section class="reviews">
+            <h2 class="related-title">"レビュー"</h2>
+            {move || message.get().map(|msg| view! {
+                <div class="seed-message">{msg}</div>
+            })}
+            <Suspense fallback=|| ()>
+                {move || reviews.get().map(|result| match result {
+                    Ok(review_page) => view! {
+                        <RatingHistogram summary=review_page.summary.clone()/>
+                        {account.user.get().is_some().then(|| view! {
+                            <ReviewForm
+                                index=index
+                                id=id.get_untracked()
+                                own=review_page.own.clone()
+                                on_change=reload
+                                set_message=set_message
+                            />
+                        })}
+                        <input
+                            type="text"
+                            class="admin-input review-search"
+                            placeholder="レビューを検索"
+                            prop:value=query
+                            on:change=move |ev| {
+                                set_page.set(1);
+                                set_query.set(input_value(&ev));
+                            }
+                        />
+                        {review_page.reviews.is_empty().then(|| view! {
+                            <p class="watchlist-empty">"まだレビューはありません"</p>
+                        })}
+                        <ul class="review-list">
+                            {review_page.reviews.into_iter().map(|review| view! {
+                                <ReviewItem review=review on_change=reload set_message=set_message/>
+                            }).collect_view()}
+                        </ul>
+                    }.into_any(),
+                    Err(e) => view! {
+                        <div class="error">"エラー: "{e.to_string()}</div>
+                    }.into_any(),
+                })}
+            </Suspense>
+            <Pagination
+                current_page=Signal::derive(move || page.get())
+                total_pages=total_pages
+                on_page_change=move |p| set_page.set(p)
+            />
+        </section>
+    }
+}
+
+#[component]
+fn RatingHistogram(summary: RatingSummary) -> impl IntoView {
+    let headline = match summary.average {
+        Some(avg) => format!("みんなの評価 {avg:.1} / 10 ({} 件)", summary.count),
+        None => "まだ評価はありません".to_string(),
+    };
+
+    view! {
+        <div class="rating-summary">
+            <p class="rating-headline">{headline}</p>
+            {(summary.count > 0).then(|| view! {
+                <ol class="rating-histogram">
+                    {(SCORE_MIN..=SCORE_MAX).rev().map(|score| {
+                        let count = summary.histogram[usize::from(score) - 1];
+                        let width = format!("width: {:.0}%", summary.bar_width(score) * 100.0);
+                        view! {
+                            <li>
+                                <span class="rating-score">{score.to_string()}</span>
+                                <span class="rating-bar"><span style=width></span></span>
+                                <span class="rating-count">{count.to_string()}</span>
+                            </li>
+                        }
+                    }).collect_view()}
+                </ol>
+            })}
+        </div>
+    }
+}
+
+#[component]
+fn ReviewForm(
+    index: &'static str,
+    id: i64,
+    own: Option<Review>,
+    on_change: impl Fn() + Copy + Send + Sync + 'static,
+    set_message: WriteSignal<Option<String>>,
+) -> impl IntoView {
+    let has_own = own.is_some();
+    let (score, set_score) = signal(own.as_ref().map(|r| r.score).unwrap_or(SCORE_MAX));
+    let (body, set_body) = signal(own.map(|r| r.body).unwrap_or_default());
+
+    let on_submit = move |_| {
+        spawn_local(async move {
+            match submit_review(index.to_string(), id, score.get_untracked(), body.get_untracked()).await {
+                Ok(_) => {
+                    set_message.set(Some("レビューを保存しました".to_string()));
+                    on_change();
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    let on_delete = move |_| {
+        spawn_local(async move {
+            match delete_review(index.to_string(), id).await {
+                Ok(()) => {
+                    set_message.set(Some("レビューを削除しました".to_string()));
+                    on_change();
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    view! {
+        <div class="review-form admin-card">
+            <div class="admin-field">
+                <label>"評価"</label>
+                <select
+                    class="sort-select"
+                    on:change=move |ev| {
+                        if let Ok(s) = input_value(&ev).parse() {
+                            set_score.set(s);
+                        }
+                    }
+                >
+                    {(SCORE_MIN..=SCORE_MAX).rev().map(|s| view! {
+                        <option value=s.to_string() selected=move || score.get() == s>{s.to_string()}</option>
+                    }).collect_view()}
+                </select>
+            </div>
+            <div class="admin-field">
+                <label>"レビュー (任意)"</label>
+                <textarea
+                    class="admin-textarea"
+                    rows="4"
+                    prop:value=body
+                    on:input=move |ev| set_body.set(textarea_value(&ev))
+                ></textarea>
+            </div>
+            <div class="collection-actions">
+                <button class="web-import-btn" on:click=on_submit>
+                    {if has_own { "更新" } else { "投稿" }}
+                </button>
+                {has_own.then(|| view! {
+                    <button class="page-btn" on:click=on_delete>"削除"</button>
+                })}
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn ReviewItem(
+    review: Review,
+    on_change: impl Fn() + Copy + Send + Sync + 'static,
+    set_message: WriteSignal<Option<String>>,
+) -> impl IntoView {
+    let account = use_account();
+    let review_id = StoredValue::new(review.id.clone());
+    let can_flag = move || account.user.get().is_some_and(|u| u.id != review.user_id);
+
+    let on_flag = move |ev: leptos::ev::Event| {
+        let Some(reason) = FlagReason::ALL
+            .into_iter()
+            .find(|r| r.as_str() == input_value(&ev))
+        else {
+            return;
+        };
+        spawn_local(async move {
+            match flag_review(review_id.get_value(), reason).await {
+                Ok(hidden) => {
+                    set_message.set(Some("報告しました。ご協力ありがとうございます".to_string()));
+                    if hidden {
+                        on_change();
+                    }
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    view! {
+        <li class="review-item">
+            <div class="review-header">
+                <span class="review-score">{format!("★ {}", review.score)}</span>
+                <span class="review-author">{review.username.clone()}</span>
+            </div>
+            {(!review.body.is_empty()).then(|| view! {
+                <p class="review-body">{review.body.clone()}</p>
+            })}
+            {move || can_flag().then(|| view! {
+                <select class="review-flag" on:change=on_flag>
+                    <option value="">"報告する..."</option>
+                    {FlagReason::ALL.into_iter().map(|r| view! {
+                        <option value=r.as_str()>{r.label()}</option>
+                    }).collect_view()}
+                </select>
+            })}
+        </li>
+    }
+}
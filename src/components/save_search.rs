@@ -0,0 +1,78 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::api::save_search;
+use crate::components::account::use_account;
+use crate::model::search::SearchRequest;
+use crate::pages::admin_settings::input_value;
+
+// "この検索を保存" above the results, for signed-in users with a search entered
+#[component]
+pub fn SaveSearch(request: Signal<SearchRequest>) -> impl IntoView {
+    let account = use_account();
+    let (open, set_open) = signal(false);
+    let (name, set_name) = signal(String::new());
+    let (webhook_url, set_webhook_url) = signal(String::new());
+    let (message, set_message) = signal(Option::<String>::None);
+
+    let savable = move || {
+        account.user.with(Option::is_some)
+            && request.with(|r| r.index != "web" && !r.is_blank())
+    };
+
+    let on_save = move |_| {
+        let request = request.get_untracked();
+        let name = name.get_untracked();
+        let webhook_url = Some(webhook_url.get_untracked()).filter(|u| !u.trim().is_empty());
+        spawn_local(async move {
+            match save_search(name, request, webhook_url).await {
+                Ok(saved) => {
+                    set_message.set(Some(format!(
+                        "「{}」を保存しました。新着は通知に届きます",
+                        saved.name
+                    )));
+                    set_open.set(false);
+                    set_name.set(String::new());
+                    set_webhook_url.set(String::new());
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    view! {
+        {move || savable().then(|| view! {
+            <div class="save-search">
+                {move || if open.get() {
+                    view! {
+                        <div class="save-search-form">
+                            <input
+                                type="text"
+                                class="admin-input"
+                                placeholder="名前 (例: 新着SF 8点以上)"
+                                prop:value=name
+                                on:input=move |ev| set_name.set(input_value(&ev))
+                            />
+                            <input
+                                type="url"
+                                class="admin-input"
+                                placeholder="Webhook URL (任意)"
+                                prop:value=webhook_url
+                                on:input=move |ev| set_webhook_url.set(input_value(&ev))
+                            />
+                            <button class="web-import-btn" on:click=on_save>"保存"</button>
+                            <button class="page-btn" on:click=move |_| set_open.set(false)>"キャンセル"</button>
+                        </div>
+                    }.into_any()
+                } else {
+                    view! {
+                        <button class="page-btn" on:click=move |_| set_open.set(true)>"この検索を保存"</button>
+                    }.into_any()
+                }}
+            </div>
+        })}
+        {move || message.get().map(|msg| view! {
+            <div class="seed-message">{msg}</div>
+        })}
+    }
+}
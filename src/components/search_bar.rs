@@ -1,4 +1,21 @@
 use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_router::hooks::use_navigate;
+use leptos_use::signal_debounced;
+
+use crate::api::{find_book_by_isbn, suggest};
+use crate::model::isbn::normalize_isbn;
+use crate::model::query::{parse_query, quoted};
+use crate::model::search::{Suggestion, SuggestionKind};
+
+// Splits off the word being typed, which is what suggestions complete
+fn split_last_word(query: &str) -> (&str, &str) {
+    // By char, as the separator may be a multi-byte full-width space
+    match query.char_indices().rev().find(|(_, c)| c.is_whitespace()) {
+        Some((i, c)) => query.split_at(i + c.len_utf8()),
+        None => ("", query),
+    }
+}
 
 #[component]
 pub fn SearchBar(
@@ -6,10 +23,85 @@ pub fn SearchBar(
     set_query: WriteSignal<String>,
     index: ReadSignal<String>,
     set_index: WriteSignal<String>,
+    set_selected_genres: WriteSignal<Vec<String>>,
     on_seed: impl Fn() + 'static + Clone,
     seeding: ReadSignal<bool>,
 ) -> impl IntoView {
     let on_seed_clone = on_seed.clone();
+    let parse_error = move || parse_query(&query.get()).err();
+
+    let (suggestions, set_suggestions) = signal(Vec::<Suggestion>::new());
+    let (highlighted, set_highlighted) = signal(Option::<usize>::None);
+    let (open, set_open) = signal(false);
+    let debounced_query: Signal<String> = signal_debounced(query, 150.0);
+    // Responses can arrive out of order; only those of the latest request are shown
+    let request_seq = StoredValue::new(0u64);
+
+    Effect::new(move |_| {
+        let q = debounced_query.get();
+        let idx = index.get();
+        request_seq.update_value(|n| *n += 1);
+        let seq = request_seq.get_value();
+        let (_, word) = split_last_word(&q);
+        // Field-scoped words and negations are left to the query syntax
+        if !open.get_untracked() || word.is_empty() || word.contains(':') || word.starts_with('-') {
+            set_suggestions.set(vec![]);
+            return;
+        }
+        let word = word.to_string();
+        spawn_local(async move {
+            let result = suggest(word, idx).await.unwrap_or_default();
+            if request_seq.get_value() != seq {
+                return;
+            }
+            set_highlighted.set(None);
+            set_suggestions.set(result);
+        });
+    });
+
+    // A query that is nothing but a valid ISBN opens that book directly
+    let navigate = use_navigate();
+    Effect::new(move |_| {
+        let Some(isbn) = normalize_isbn(&debounced_query.get()) else {
+            return;
+        };
+        let navigate = navigate.clone();
+        spawn_local(async move {
+            if let Ok(Some(id)) = find_book_by_isbn(isbn).await {
+                navigate(&format!("/book/{id}"), Default::default());
+            }
+        });
+    });
+
+    let apply = move |suggestion: Suggestion| {
+        let q = query.get_untracked();
+        let (head, _) = split_last_word(&q);
+        let next = match suggestion.kind {
+            SuggestionKind::Title | SuggestionKind::TitleEn | SuggestionKind::Popular => {
+                format!("{head}{}", quoted(&suggestion.value))
+            }
+            SuggestionKind::Creator => {
+                let field = match index.get_untracked().as_str() {
+                    "books" => "author",
+                    "series" => "studio",
+                    _ => "director",
+                };
+                format!("{head}{field}:\"{}\"", suggestion.value)
+            }
+            SuggestionKind::Genre => {
+                set_selected_genres.update(|genres| {
+                    if !genres.contains(&suggestion.value) {
+                        genres.push(suggestion.value.clone());
+                    }
+                });
+                head.trim_end().to_string()
+            }
+        };
+        set_query.set(next);
+        set_open.set(false);
+        set_suggestions.set(vec![]);
+        set_highlighted.set(None);
+    };
 
     view! {
         <div class="search-bar">
@@ -21,16 +113,79 @@ pub fn SearchBar(
                 <input
                     type="text"
                     class="search-input"
-                    placeholder="検索... (例: 宮崎, fantasy, SF)"
+                    placeholder="検索... (例: 宮崎, fantasy, SF, ISBN)"
+                    title="director:宮崎 year:1990..2000 rating:>=8 -アクション \"千と千尋\" のように絞り込めます"
                     prop:value=query
                     on:input=move |ev| {
                         use wasm_bindgen::JsCast;
                         let target = ev.target().unwrap();
                         let input = target.unchecked_ref::<web_sys::HtmlInputElement>();
+                        set_open.set(true);
                         set_query.set(input.value());
                     }
+                    on:keydown=move |ev| {
+                        let count = suggestions.with_untracked(|s| s.len());
+                        if !open.get_untracked() || count == 0 {
+                            return;
+                        }
+                        match ev.key().as_str() {
+                            "ArrowDown" => {
+                                ev.prevent_default();
+                                set_highlighted.update(|h| *h = Some(h.map_or(0, |i| (i + 1) % count)));
+                            }
+                            "ArrowUp" => {
+                                ev.prevent_default();
+                                set_highlighted.update(|h| *h = Some(h.map_or(count - 1, |i| (i + count - 1) % count)));
+                            }
+                            "Enter" => {
+                                if let Some(i) = highlighted.get_untracked() {
+                                    ev.prevent_default();
+                                    if let Some(s) = suggestions.with_untracked(|s| s.get(i).cloned()) {
+                                        apply(s);
+                                    }
+                                }
+                            }
+                            "Escape" => {
+                                set_open.set(false);
+                                set_highlighted.set(None);
+                            }
+                            _ => {}
+                        }
+                    }
+                    on:blur=move |_| set_open.set(false)
                 />
+                {move || {
+                    let items = suggestions.get();
+                    (open.get() && !items.is_empty()).then(|| view! {
+                        <ul class="suggestions" role="listbox">
+                            {items.into_iter().enumerate().map(|(i, s)| {
+                                let label = s.kind.label();
+                                let value = s.value.clone();
+                                let count = s.count;
+                                view! {
+                                    <li
+                                        class=move || if highlighted.get() == Some(i) { "suggestion active" } else { "suggestion" }
+                                        role="option"
+                                        // mousedown fires before the input loses focus
+                                        on:mousedown=move |ev| {
+                                            ev.prevent_default();
+                                            apply(s.clone());
+                                        }
+                                        on:mouseenter=move |_| set_highlighted.set(Some(i))
+                                    >
+                                        <span class="suggestion-badge">{label}</span>
+                                        <span class="suggestion-value">{value}</span>
+                                        {count.map(|c| view! { <span class="suggestion-count">{c}</span> })}
+                                    </li>
+                                }
+                            }).collect_view()}
+                        </ul>
+                    })
+                }}
             </div>
+            {move || parse_error().map(|e| view! {
+                <p class="query-error">{e.to_string()}</p>
+            })}
             <div class="search-controls">
                 <div class="index-toggle">
                     <button
@@ -45,6 +200,12 @@ pub fn SearchBar(
                     >
                         "書籍"
                     </button>
+                    <button
+                        class=move || if index.get() == "series" { "toggle-btn active" } else { "toggle-btn" }
+                        on:click=move |_| set_index.set("series".to_string())
+                    >
+                        "シリーズ"
+                    </button>
                     <button
                         class=move || if index.get() == "web" { "toggle-btn active" } else { "toggle-btn" }
                         on:click=move |_| set_index.set("web".to_string())
@@ -63,3 +224,17 @@ pub fn SearchBar(
         </div>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_last_word_is_split_off() {
+        assert_eq!(split_last_word("ghibli"), ("", "ghibli"));
+        assert_eq!(split_last_word("director:宮崎 とな"), ("director:宮崎 ", "とな"));
+        assert_eq!(split_last_word("宮崎　とな"), ("宮崎　", "とな"));
+        assert_eq!(split_last_word("dune "), ("dune ", ""));
+        assert_eq!(split_last_word(""), ("", ""));
+    }
+}
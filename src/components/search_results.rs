@@ -1,8 +1,42 @@
 use leptos::prelude::*;
 
 use crate::components::result_card::ResultCard;
+use crate::model::analytics::click_params;
+use crate::model::collection::Collection;
 use crate::model::search::SearchResponse;
 
+fn did_you_mean(
+    suggestions: Vec<String>,
+    on_suggestion: impl Fn(String) + 'static + Copy + Send,
+) -> Option<impl IntoView> {
+    (!suggestions.is_empty()).then(|| view! {
+        <p class="did-you-mean">
+            "もしかして: "
+            {suggestions.into_iter().map(|s| {
+                let label = s.clone();
+                view! {
+                    <button class="did-you-mean-link" on:click=move |_| on_suggestion(s.clone())>
+                        {label}
+                    </button>
+                }
+            }).collect_view()}
+        </p>
+    })
+}
+
+fn collection_links(collections: Vec<Collection>) -> Option<impl IntoView> {
+    (!collections.is_empty()).then(|| view! {
+        <div class="collection-links">
+            <span class="collection-links-label">"コレクション: "</span>
+            {collections.into_iter().map(|c| view! {
+                <a href=format!("/collection/{}", c.id) class="collection-link">
+                    {format!("{} ({})", c.name, c.members.len())}
+                </a>
+            }).collect_view()}
+        </div>
+    })
+}
+
 #[component]
 pub fn SearchResults(
     results: Signal<Option<Result<SearchResponse, ServerFnError>>>,
@@ -10,6 +44,7 @@ pub fn SearchResults(
     query: Signal<String>,
     on_web_import: impl Fn() + 'static + Copy + Send,
     web_importing: Signal<bool>,
+    on_suggestion: impl Fn(String) + 'static + Copy + Send,
 ) -> impl IntoView {
     view! {
         <div class="search-results">
@@ -26,12 +61,33 @@ pub fn SearchResults(
                             </div>
                         }.into_any(),
                         Some(Ok(response)) => {
-                            if response.hits.is_empty() {
+                            let suggestions = did_you_mean(response.suggestions.clone(), on_suggestion);
+                            let collections = collection_links(response.collections.clone());
+                            let search_id = response.search_id.clone();
+                            if response.hits.is_empty() && !response.web_hits.is_empty() {
+                                view! {
+                                    <div>
+                                        <div class="no-results">
+                                            <p>"結果が見つかりませんでした"</p>
+                                            {suggestions}
+                                        </div>
+                                        <section class="web-fallback">
+                                            <h3 class="web-fallback-title">"Webからの結果"</h3>
+                                            <div class="results-grid">
+                                                {response.web_hits.into_iter().map(|hit| {
+                                                    view! { <ResultCard hit=hit/> }
+                                                }).collect_view()}
+                                            </div>
+                                        </section>
+                                    </div>
+                                }.into_any()
+                            } else if response.hits.is_empty() {
                                 let q = query.get();
                                 let has_query = !q.is_empty();
                                 view! {
                                     <div class="no-results">
                                         <p>"結果が見つかりませんでした"</p>
+                                        {suggestions}
                                         {if has_query {
                                             Some(view! {
                                                 <button
@@ -54,13 +110,18 @@ pub fn SearchResults(
                             } else {
                                 view! {
                                     <div>
+                                        {collections}
                                         <div class="results-meta">
                                             <span>{response.total_hits}" 件の結果"</span>
                                             <span class="processing-time">"("{response.processing_time_ms}" ms)"</span>
                                         </div>
+                                        {suggestions}
                                         <div class="results-grid">
-                                            {response.hits.into_iter().map(|hit| {
-                                                view! { <ResultCard hit=hit/> }
+                                            {response.hits.into_iter().enumerate().map(|(i, hit)| {
+                                                let click = search_id
+                                                    .as_deref()
+                                                    .map(|sid| click_params(sid, i + 1));
+                                                view! { <ResultCard hit=hit click=click/> }
                                             }).collect_view()}
                                         </div>
                                     </div>
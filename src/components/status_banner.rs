@@ -0,0 +1,49 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_use::use_interval_fn;
+
+use crate::api::get_backend_status;
+
+const POLL_MS: u64 = 30_000;
+
+// Degraded-mode banner above every page, shown while `/readyz` reports a
+// failed dependency or the server cannot be reached at all
+#[component]
+pub fn StatusBanner() -> impl IntoView {
+    let message = RwSignal::new(None::<String>);
+    let unavailable = RwSignal::new(false);
+
+    let refresh = move || {
+        spawn_local(async move {
+            match get_backend_status().await {
+                Ok(report) => {
+                    unavailable.set(report.status == crate::model::health::Readiness::Unavailable);
+                    message.set(report.banner());
+                }
+                Err(_) => {
+                    unavailable.set(true);
+                    message.set(Some(
+                        "サーバーに接続できません。しばらくしてから再度お試しください".to_string(),
+                    ));
+                }
+            }
+        });
+    };
+    // Checked once hydrated, then polled so the banner clears on recovery
+    Effect::new(move |_| refresh());
+    let _ = use_interval_fn(refresh, POLL_MS);
+
+    move || {
+        message.get().map(|text| {
+            view! {
+                <div
+                    class="status-banner"
+                    class:status-banner-down=move || unavailable.get()
+                    role="status"
+                >
+                    {text}
+                </div>
+            }
+        })
+    }
+}
@@ -0,0 +1,80 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::api::{get_item_tags, set_item_tags};
+use crate::components::account::use_account;
+use crate::model::tag::split_tags;
+use crate::pages::admin_settings::input_value;
+
+// Every tag users put on an item, with an editor for the signed-in user's own
+#[component]
+pub fn Tags(index: &'static str, id: Signal<i64>) -> impl IntoView {
+    let account = use_account();
+    let (version, set_version) = signal(0u32);
+    let (draft, set_draft) = signal(String::new());
+    let (message, set_message) = signal(Option::<String>::None);
+
+    let tags = Resource::new(
+        move || (id.get(), version.get(), account.user.get()),
+        move |(id, _, _)| async move { get_item_tags(index.to_string(), id).await },
+    );
+
+    // The editor starts from the user's current tags
+    Effect::new(move |_| {
+        if let Some(Ok(item_tags)) = tags.get() {
+            set_draft.set(item_tags.own.join(", "));
+        }
+    });
+
+    let on_save = move |_| {
+        let id = id.get_untracked();
+        let list = split_tags(&draft.get_untracked());
+        spawn_local(async move {
+            match set_item_tags(index.to_string(), id, list).await {
+                Ok(_) => {
+                    set_message.set(None);
+                    set_version.update(|v| *v += 1);
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    view! {
+        <section class="item-tags">
+            <h2 class="related-title">"タグ"</h2>
+            <Suspense fallback=|| ()>
+                {move || tags.get().map(|result| match result {
+                    Ok(item_tags) if item_tags.tags.is_empty() => view! {
+                        <p class="watchlist-empty">"まだタグはありません"</p>
+                    }.into_any(),
+                    Ok(item_tags) => view! {
+                        <div class="detail-tags">
+                            {item_tags.tags.into_iter().map(|fv| view! {
+                                <span class="genre-tag">{format!("{} ({})", fv.value, fv.count)}</span>
+                            }).collect_view()}
+                        </div>
+                    }.into_any(),
+                    Err(e) => view! {
+                        <div class="error">"エラー: "{e.to_string()}</div>
+                    }.into_any(),
+                })}
+            </Suspense>
+            {move || account.user.get().is_some().then(|| view! {
+                <div class="tag-editor">
+                    <input
+                        type="text"
+                        class="admin-input"
+                        placeholder="泣ける, 週末向け, 原作あり"
+                        prop:value=draft
+                        on:input=move |ev| set_draft.set(input_value(&ev))
+                    />
+                    <button class="web-import-btn" on:click=on_save>"タグを保存"</button>
+                </div>
+            })}
+            {move || message.get().map(|msg| view! {
+                <div class="seed-message">{msg}</div>
+            })}
+        </section>
+    }
+}
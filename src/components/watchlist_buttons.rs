@@ -0,0 +1,55 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::api::set_watchlist_status;
+use crate::components::account::use_account;
+use crate::model::user::{is_listable, ListStatus};
+
+// "見たい / 見た" (or "読みたい / 読んだ" for books). Pressing the active
+// button takes the item off the list again. Hidden until someone is signed in.
+#[component]
+pub fn WatchlistButtons(index: String, id: i64) -> impl IntoView {
+    let account = use_account();
+    let listable = is_listable(&index);
+    let index = StoredValue::new(index);
+
+    let set_status = move |status: Option<ListStatus>| {
+        let index = index.get_value();
+        let key = format!("{index}:{id}");
+        // Shown straight away; a failed request reloads the real state
+        account.statuses.update(|s| match status {
+            Some(status) => {
+                s.insert(key, status);
+            }
+            None => {
+                s.remove(&key);
+            }
+        });
+        spawn_local(async move {
+            if set_watchlist_status(index, id, status).await.is_err() {
+                account.reload();
+            }
+        });
+    };
+
+    move || {
+        (listable && account.user.with(Option::is_some)).then(|| {
+            let current = account.status_of(&index.get_value(), id);
+            view! {
+                <div class="watchlist-buttons">
+                    {[ListStatus::Want, ListStatus::Done].into_iter().map(|status| {
+                        let active = current == Some(status);
+                        view! {
+                            <button
+                                class=if active { "watchlist-btn active" } else { "watchlist-btn" }
+                                on:click=move |_| set_status((!active).then_some(status))
+                            >
+                                {status.label(&index.get_value())}
+                            </button>
+                        }
+                    }).collect_view()}
+                </div>
+            }
+        })
+    }
+}
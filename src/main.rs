@@ -6,26 +6,134 @@ async fn main() -> std::io::Result<()> {
     use leptos::prelude::*;
     use leptos_actix::{generate_route_list, LeptosRoutes};
     use meilisearch_search_app::app::App;
+    use meilisearch_search_app::server::config::{self, AppConfig};
+    use meilisearch_search_app::server::{health, keys, metrics, telemetry};
+    use std::time::Duration;
 
-    // Explicitly register server functions (inventory crate may not work in all environments)
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::SearchItems>();
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::GetMovie>();
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::GetBook>();
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::CreateMovie>();
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::CreateBook>();
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::UpdateMovie>();
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::UpdateBook>();
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::DeleteMovie>();
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::DeleteBook>();
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::SeedData>();
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::GetFacets>();
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::SearchWebAndImport>();
-    server_fn::actix::register_explicit::<meilisearch_search_app::api::GetWebResult>();
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    // Invalid settings stop the server before it binds, listing every problem
+    let app_config = match AppConfig::load(args.config.as_deref()) {
+        Ok(app_config) => app_config,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    if args.print_config {
+        print!("{}", app_config.to_toml());
+        return Ok(());
+    }
+    let log_format = app_config.log.format;
+    config::init(app_config);
+    telemetry::init(log_format);
+    if let Some(command) = args.keys {
+        if let Err(e) = run_keys_command(command).await {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(username) = args.create_admin {
+        if let Err(e) = run_create_admin(&username).await {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Explicitly register server functions (inventory crate may not work in all environments),
+    // naming each one's path for the request metrics
+    macro_rules! register {
+        ($($name:ident),* $(,)?) => {$(
+            server_fn::actix::register_explicit::<meilisearch_search_app::api::$name>();
+            metrics::name_server_fn(
+                <meilisearch_search_app::api::$name as server_fn::ServerFn>::PATH,
+                stringify!($name),
+            );
+        )*};
+    }
+    register!(
+        SearchItems, SemanticSearchAvailable, GetBackendStatus, Suggest, GetMovie, GetBook, GetSeries,
+        FindBookByIsbn, GetSimilar, GetPerson, MigratePeople, ListCollections, GetCollection,
+        SaveCollection, DeleteCollection, CurrentUser, Register, Login, Logout,
+        GetWatchlistStatuses, SetWatchlistStatus, SearchWatchlist, ExportWatchlist,
+        ImportWatchlist, GetReviews, SubmitReview, DeleteReview, FlagReview, SearchReviews,
+        ModerateReview, GetItemTags, SetItemTags, GetTagOverview, MergeTags, BanTag, UnbanTag,
+        SaveSearch, ListSavedSearches, GetSavedSearch, DeleteSavedSearch, CheckSavedSearch,
+        GetNotifications, MarkNotificationsRead, LogClick, GetAnalyticsReport, CreateMovie,
+        CreateBook, UpdateMovie, UpdateBook, DeleteMovie, DeleteBook, SeedData, GetFacets,
+        SearchWebAndImport, GetWebResult, GetSearchSettings, GetSearchSettingsHistory,
+        SaveSearchSettings, PreviewSearchSettings,
+    );
 
     let conf = get_configuration(None).unwrap();
     let addr = conf.leptos_options.site_addr;
 
-    println!("Starting server at http://{}", addr);
+    tracing::info!("Starting server at http://{addr}");
+
+    // Swaps the master key for scoped keys before the first request, then picks
+    // up rotations; until a refresh succeeds requests keep the master key
+    if keys::enabled() {
+        if let Err(e) = keys::refresh().await {
+            tracing::warn!(error = %e, "scoped API keys not set up, using the master key");
+        }
+        actix_web::rt::spawn(async move {
+            loop {
+                actix_web::rt::time::sleep(keys::REFRESH_INTERVAL).await;
+                if let Err(e) = keys::refresh().await {
+                    tracing::warn!(error = %e, "scoped API keys not refreshed");
+                }
+            }
+        });
+    }
+
+    // Re-runs saved searches and records new matches as notifications
+    if let Some(period) = meilisearch_search_app::server::saved_searches::interval() {
+        actix_web::rt::spawn(async move {
+            let mut interval = actix_web::rt::time::interval(period);
+            loop {
+                interval.tick().await;
+                let (notified, errors) =
+                    meilisearch_search_app::server::saved_searches::run_all().await;
+                if notified > 0 {
+                    tracing::info!(notified, "saved searches re-run");
+                }
+                for error in errors {
+                    tracing::warn!(%error, "saved search failed");
+                }
+            }
+        });
+    }
+
+    // Deletes analytics events past the retention period
+    if meilisearch_search_app::server::analytics::is_enabled() {
+        actix_web::rt::spawn(async move {
+            let mut interval = actix_web::rt::time::interval(Duration::from_secs(24 * 60 * 60));
+            loop {
+                interval.tick().await;
+                if let Err(e) = meilisearch_search_app::server::analytics::purge().await {
+                    tracing::warn!(error = %e, "analytics purge failed");
+                }
+            }
+        });
+    }
+
+    // Keeps the index document gauges current
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(metrics::POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = metrics::poll_index_documents().await {
+                tracing::warn!(error = %e, "index document counts not polled");
+            }
+        }
+    });
 
     HttpServer::new(move || {
         let routes = generate_route_list(App);
@@ -33,8 +141,15 @@ async fn main() -> std::io::Result<()> {
         let site_root = leptos_options.site_root.clone().to_string();
 
         App::new()
+            .wrap(middleware::from_fn(metrics::track_requests))
+            // Outermost, so the request span and id cover the other middleware
+            .wrap(middleware::from_fn(telemetry::trace_requests))
+            .route("/metrics", web::get().to(metrics::endpoint))
+            .route("/healthz", web::get().to(health::liveness_endpoint))
+            .route("/readyz", web::get().to(health::readiness_endpoint))
             .service(Files::new("/pkg", format!("{site_root}/pkg")))
             .service(Files::new("/assets", &site_root))
+            .route("/feeds/saved/{file}", web::get().to(saved_search_feed))
             .leptos_routes(routes, {
                 let leptos_options = leptos_options.clone();
                 move || {
@@ -60,11 +175,154 @@ async fn main() -> std::io::Result<()> {
             .app_data(web::Data::new(leptos_options.to_owned()))
             .default_service(web::route().to(not_found))
     })
-    .bind(&addr)?
+    .bind(addr)?
     .run()
     .await
 }
 
+#[cfg(feature = "ssr")]
+const USAGE: &str = "Usage: meilisearch-search-app [--config <file>] [--print-config]
+       meilisearch-search-app [--config <file>] keys list|rotate|revoke <uid>
+       meilisearch-search-app [--config <file>] admin create <username> < password
+
+  --config <file>   Read settings from <file> instead of CONFIG_FILE or ./config.toml
+  --print-config    Print the effective settings (master key hidden) and exit
+  keys list         List the Meilisearch API keys, marking the ones the server would use
+  keys rotate       Create new search and admin keys; servers switch within a minute
+  keys revoke <uid> Delete an API key
+  admin create <username>
+                    Create the account of a name listed in users.admins, reading the
+                    password from the first line of stdin";
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Default)]
+struct Args {
+    config: Option<std::path::PathBuf>,
+    print_config: bool,
+    keys: Option<KeysCommand>,
+    create_admin: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug)]
+enum KeysCommand {
+    List,
+    Rotate,
+    Revoke(String),
+}
+
+#[cfg(feature = "ssr")]
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+        let mut parsed = Args::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    let path = args.next().ok_or("--config needs a file")?;
+                    parsed.config = Some(path.into());
+                }
+                "--print-config" => parsed.print_config = true,
+                "keys" => {
+                    parsed.keys = Some(match args.next().as_deref() {
+                        Some("list") => KeysCommand::List,
+                        Some("rotate") => KeysCommand::Rotate,
+                        Some("revoke") => {
+                            KeysCommand::Revoke(args.next().ok_or("keys revoke needs a key uid")?)
+                        }
+                        _ => return Err("keys needs list, rotate or revoke".to_string()),
+                    });
+                }
+                "admin" => match (args.next().as_deref(), args.next()) {
+                    (Some("create"), Some(username)) => parsed.create_admin = Some(username),
+                    _ => return Err("admin needs create <username>".to_string()),
+                },
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                other => return Err(format!("Unknown argument `{other}`")),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+// Key management runs with the master key and needs no server
+#[cfg(feature = "ssr")]
+async fn run_keys_command(command: KeysCommand) -> Result<(), String> {
+    use meilisearch_search_app::server::{config::config, keys};
+
+    match command {
+        KeysCommand::List => {
+            let now = time::OffsetDateTime::now_utc();
+            for key in keys::list().await? {
+                println!("{}", keys::describe(&key, config(), now));
+            }
+        }
+        KeysCommand::Rotate => {
+            for key in keys::rotate().await? {
+                println!("created {}  {}", key.uid, key.name.unwrap_or_default());
+            }
+            println!(
+                "Running servers switch to the new keys within {}s; the old keys stay valid \
+                 until they expire or `keys revoke <uid>` deletes them.",
+                keys::REFRESH_INTERVAL.as_secs()
+            );
+        }
+        KeysCommand::Revoke(uid) => {
+            keys::revoke(&uid).await?;
+            println!("revoked {uid}");
+        }
+    }
+    Ok(())
+}
+
+// Admin accounts are only made here, by whoever runs the server
+#[cfg(feature = "ssr")]
+async fn run_create_admin(username: &str) -> Result<(), String> {
+    use meilisearch_search_app::server::users;
+
+    let mut password = String::new();
+    std::io::stdin()
+        .read_line(&mut password)
+        .map_err(|e| format!("Failed to read the password: {e}"))?;
+    let user = users::create_admin(username, password.trim_end_matches(['\r', '\n'])).await?;
+    println!("created admin {} (id {})", user.username, user.id);
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+#[derive(serde::Deserialize)]
+struct FeedQuery {
+    token: String,
+}
+
+// `/feeds/saved/{id}.atom?token=…`, the per-search Atom feed
+#[cfg(feature = "ssr")]
+async fn saved_search_feed(
+    request: actix_web::HttpRequest,
+    file: actix_web::web::Path<String>,
+    query: actix_web::web::Query<FeedQuery>,
+) -> actix_web::HttpResponse {
+    let Some(id) = file.strip_suffix(".atom") else {
+        return actix_web::HttpResponse::NotFound().finish();
+    };
+    let base_url = {
+        let info = request.connection_info();
+        format!("{}://{}", info.scheme(), info.host())
+    };
+    match meilisearch_search_app::server::saved_searches::feed(id, &query.token, &base_url).await {
+        Ok(Some(xml)) => actix_web::HttpResponse::Ok()
+            .content_type("application/atom+xml; charset=utf-8")
+            .body(xml),
+        Ok(None) => actix_web::HttpResponse::NotFound().finish(),
+        Err(error) => {
+            tracing::warn!(%error, "saved search feed failed");
+            actix_web::HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
 #[cfg(feature = "ssr")]
 async fn not_found() -> actix_web::HttpResponse {
     actix_web::HttpResponse::NotFound()
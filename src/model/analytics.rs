@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::search::FacetValue;
+
+// Positions reported in the CTR table, one page of results
+pub const CTR_POSITIONS: usize = 12;
+// A failed search followed this soon by a successful one counts as a rewrite
+pub const REFORMULATION_WINDOW_SECS: u64 = 60;
+pub const REPORT_ROWS: usize = 20;
+
+// One `search_items` call, stored in the `search_events` index
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchEvent {
+    // Random hex, carried to the detail page so clicks can refer back to it
+    pub id: String,
+    pub at: u64,
+    pub index: String,
+    // The normalised keyword part of the query
+    pub query: String,
+    // The Meilisearch filter expression the search ran with
+    #[serde(default)]
+    pub filter: Option<String>,
+    pub hits: usize,
+    // Hits on the returned page, i.e. the positions a user could click
+    pub shown: usize,
+    pub page: usize,
+    // Time spent in `search_items`, including fallbacks and annotations
+    pub latency_ms: u64,
+    // Meilisearch's own processing time
+    pub processing_ms: usize,
+    // The client address with the host part zeroed, see `anonymize_ip`
+    pub client: String,
+}
+
+// A detail page opened from a result, stored in the `click_events` index
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClickEvent {
+    pub id: String,
+    pub at: u64,
+    pub search_id: String,
+    pub index: String,
+    pub item_id: i64,
+    // 1-based position on the result page
+    pub position: usize,
+}
+
+// Query string added to result links so the detail page can log the click
+pub fn click_params(search_id: &str, position: usize) -> String {
+    format!("?sid={search_id}&pos={position}")
+}
+
+// Drops the host part of an address: the last octet of IPv4 and everything
+// after the /48 prefix of IPv6. Accepts `ip:port` as given by proxies.
+pub fn anonymize_ip(addr: &str) -> String {
+    let ip = addr
+        .parse::<IpAddr>()
+        .or_else(|_| addr.parse::<SocketAddr>().map(|s| s.ip()));
+    match ip {
+        Ok(IpAddr::V4(v4)) => {
+            let [a, b, c, _] = v4.octets();
+            format!("{a}.{b}.{c}.0")
+        }
+        Ok(IpAddr::V6(v6)) => {
+            let s = v6.segments();
+            format!("{:x}:{:x}:{:x}::", s[0], s[1], s[2])
+        }
+        Err(_) => "unknown".to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PositionCtr {
+    pub position: usize,
+    // Searches that showed at least this many results
+    pub impressions: usize,
+    pub clicks: usize,
+}
+
+impl PositionCtr {
+    pub fn rate(&self) -> f64 {
+        if self.impressions == 0 {
+            0.0
+        } else {
+            self.clicks as f64 / self.impressions as f64
+        }
+    }
+}
+
+// CTR per result position from the facet counts of `shown` over searches and
+// `position` over clicks
+pub fn ctr_by_position(shown: &[FacetValue], clicks: &[FacetValue]) -> Vec<PositionCtr> {
+    let count_of = |values: &[FacetValue], p: usize| -> usize {
+        values
+            .iter()
+            .filter(|fv| fv.value.parse::<usize>().ok() == Some(p))
+            .map(|fv| fv.count)
+            .sum()
+    };
+    (1..=CTR_POSITIONS)
+        .map(|position| PositionCtr {
+            position,
+            impressions: shown
+                .iter()
+                .filter(|fv| fv.value.parse::<usize>().is_ok_and(|n| n >= position))
+                .map(|fv| fv.count)
+                .sum(),
+            clicks: count_of(clicks, position),
+        })
+        .collect()
+}
+
+// A zero-result query and what the same client searched for next
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Reformulation {
+    pub index: String,
+    pub from: String,
+    pub to: String,
+    pub count: usize,
+}
+
+// Synonym candidates: a search with no hits followed within
+// `REFORMULATION_WINDOW_SECS` by a successful one from the same client
+pub fn reformulations(events: &[SearchEvent]) -> Vec<Reformulation> {
+    let mut by_client: HashMap<&str, Vec<&SearchEvent>> = HashMap::new();
+    for event in events.iter().filter(|e| !e.query.is_empty()) {
+        by_client.entry(&event.client).or_default().push(event);
+    }
+
+    let mut pairs: HashMap<(String, String, String), usize> = HashMap::new();
+    for mut session in by_client.into_values() {
+        session.sort_by_key(|e| e.at);
+        for pair in session.windows(2) {
+            let (failed, next) = (pair[0], pair[1]);
+            if failed.hits == 0
+                && next.hits > 0
+                && next.index == failed.index
+                && next.query != failed.query
+                && next.at.saturating_sub(failed.at) <= REFORMULATION_WINDOW_SECS
+            {
+                let key = (failed.index.clone(), failed.query.clone(), next.query.clone());
+                *pairs.entry(key).or_default() += 1;
+            }
+        }
+    }
+
+    let mut list: Vec<Reformulation> = pairs
+        .into_iter()
+        .map(|((index, from, to), count)| Reformulation {
+            index,
+            from,
+            to,
+            count,
+        })
+        .collect();
+    list.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.from.cmp(&b.from))
+            .then_with(|| a.to.cmp(&b.to))
+    });
+    list.truncate(REPORT_ROWS);
+    list
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AnalyticsReport {
+    pub days: u64,
+    pub searches: usize,
+    pub clicks: usize,
+    pub top_queries: Vec<FacetValue>,
+    pub zero_result_queries: Vec<FacetValue>,
+    pub ctr: Vec<PositionCtr>,
+    pub slow_searches: Vec<SearchEvent>,
+    pub reformulations: Vec<Reformulation>,
+    // Events older than this many days are deleted; 0 means nothing is logged
+    pub retention_days: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fv(value: &str, count: usize) -> FacetValue {
+        FacetValue {
+            value: value.to_string(),
+            count,
+        }
+    }
+
+    fn event(client: &str, at: u64, query: &str, hits: usize) -> SearchEvent {
+        SearchEvent {
+            id: format!("{client}-{at}"),
+            at,
+            index: "books".into(),
+            query: query.into(),
+            filter: None,
+            hits,
+            shown: hits.min(12),
+            page: 1,
+            latency_ms: 10,
+            processing_ms: 1,
+            client: client.into(),
+        }
+    }
+
+    #[test]
+    fn anonymize_drops_the_host_part() {
+        assert_eq!(anonymize_ip("203.0.113.57"), "203.0.113.0");
+        assert_eq!(anonymize_ip("203.0.113.57:51234"), "203.0.113.0");
+        assert_eq!(anonymize_ip("2001:db8:85a3:8d3:1319:8a2e:370:7348"), "2001:db8:85a3::");
+        assert_eq!(anonymize_ip("[2001:db8::1]:443"), "2001:db8:0::");
+        assert_eq!(anonymize_ip("not an ip"), "unknown");
+    }
+
+    #[test]
+    fn ctr_counts_searches_that_reached_the_position() {
+        let shown = [fv("12", 6), fv("2", 3), fv("0", 5)];
+        let clicks = [fv("1", 3), fv("2", 1), fv("12", 3)];
+        let ctr = ctr_by_position(&shown, &clicks);
+        assert_eq!(ctr.len(), CTR_POSITIONS);
+        assert_eq!((ctr[0].impressions, ctr[0].clicks), (9, 3));
+        assert_eq!((ctr[1].impressions, ctr[1].clicks), (9, 1));
+        assert_eq!((ctr[2].impressions, ctr[2].clicks), (6, 0));
+        assert_eq!(ctr[11].rate(), 0.5);
+    }
+
+    #[test]
+    fn reformulations_pair_failures_with_the_next_search() {
+        let events = [
+            event("a", 100, "ハリポタ", 0),
+            event("a", 120, "ハリー・ポッター", 8),
+            event("b", 500, "ハリポタ", 0),
+            event("b", 530, "ハリー・ポッター", 8),
+            // Too late to be a rewrite
+            event("c", 100, "ハリポタ", 0),
+            event("c", 400, "三体", 3),
+            // Another client in between does not break a session
+            event("d", 100, "ぐいんさーが", 0),
+            event("a", 130, "x", 1),
+            event("d", 110, "グイン・サーガ", 12),
+        ];
+        let list = reformulations(&events);
+        assert_eq!(list.len(), 2);
+        assert_eq!((list[0].from.as_str(), list[0].to.as_str(), list[0].count), ("ハリポタ", "ハリー・ポッター", 2));
+        assert_eq!(list[1].to, "グイン・サーガ");
+    }
+}
@@ -1,19 +1,201 @@
 use serde::{Deserialize, Serialize};
 
+use crate::model::date::is_date;
+use crate::model::isbn::normalize_isbn;
+use crate::model::reading::Readings;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BookFormat {
+    Hardcover,
+    Paperback,
+    Ebook,
+    Audiobook,
+}
+
+impl BookFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BookFormat::Hardcover => "単行本",
+            BookFormat::Paperback => "文庫・ペーパーバック",
+            BookFormat::Ebook => "電子書籍",
+            BookFormat::Audiobook => "オーディオブック",
+        }
+    }
+
+    // Facet values arrive as the serialised name
+    pub fn label_of(value: &str) -> &str {
+        match value {
+            "hardcover" => BookFormat::Hardcover.label(),
+            "paperback" => BookFormat::Paperback.label(),
+            "ebook" => BookFormat::Ebook.label(),
+            "audiobook" => BookFormat::Audiobook.label(),
+            _ => value,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Book {
     pub id: i64,
     pub title: String,
     #[serde(default)]
     pub title_en: Option<String>,
+    #[serde(default)]
+    pub title_reading: Option<Readings>,
     pub description: String,
     pub author: String,
+    #[serde(default)]
+    pub author_reading: Option<Readings>,
+    // The `people` document this name was linked to
+    #[serde(default)]
+    pub author_id: Option<i64>,
     pub year: i32,
     pub genres: Vec<String>,
     pub rating: f64,
     #[serde(default)]
     pub cover_url: Option<String>,
+    // Language of this edition
     pub language: String,
     #[serde(default)]
     pub pages: Option<i32>,
+    // ISBN-13 without hyphens once normalised
+    #[serde(default)]
+    pub isbn: Option<String>,
+    #[serde(default)]
+    pub publisher: Option<String>,
+    // YYYY-MM-DD of this edition; `year` stays the original publication year
+    #[serde(default)]
+    pub publication_date: Option<String>,
+    #[serde(default)]
+    pub format: Option<BookFormat>,
+    #[serde(default)]
+    pub translators: Vec<String>,
+    #[serde(default)]
+    pub original_title: Option<String>,
+    #[serde(default)]
+    pub original_language: Option<String>,
+    // Volume number within a series, e.g. 1 for "1Q84 BOOK1"
+    #[serde(default)]
+    pub series_number: Option<u32>,
+    // Average of visible user reviews, written back by `server::reviews`
+    #[serde(default)]
+    pub user_rating: Option<f64>,
+    #[serde(default)]
+    pub user_rating_count: u32,
+    // Tags enough users agree on, written back by `server::tags`
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl Book {
+    // Tidies hand-written seed and admin input before it is indexed. An ISBN
+    // that fails its checksum is left as typed so `validate` can report it.
+    pub fn normalize(&mut self) {
+        if let Some(isbn) = self.isbn.as_deref().and_then(normalize_isbn) {
+            self.isbn = Some(isbn);
+        }
+        self.isbn = self.isbn.take().filter(|i| !i.trim().is_empty());
+        self.publisher = self
+            .publisher
+            .take()
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty());
+        self.translators.retain(|t| !t.trim().is_empty());
+        if self.original_language.is_none() {
+            self.original_language = Some(self.language.clone());
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(isbn) = self
+            .isbn
+            .as_ref()
+            .filter(|i| normalize_isbn(i).as_deref() != Some(i.as_str()))
+        {
+            return Err(format!("Invalid ISBN: {isbn}"));
+        }
+        if let Some(d) = self.publication_date.as_ref().filter(|d| !is_date(d)) {
+            return Err(format!("Publication date must be YYYY-MM-DD: {d}"));
+        }
+        if self.series_number == Some(0) {
+            return Err("Series number starts at 1".to_string());
+        }
+        if self.pages.is_some_and(|p| p <= 0) {
+            return Err("Page count must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book() -> Book {
+        Book {
+            id: 28,
+            title: "三体".into(),
+            title_en: None,
+            title_reading: None,
+            description: String::new(),
+            author: "劉慈欣".into(),
+            author_reading: None,
+            author_id: None,
+            year: 2008,
+            genres: vec![],
+            rating: 8.2,
+            cover_url: None,
+            language: "ja".into(),
+            pages: Some(400),
+            isbn: None,
+            publisher: None,
+            publication_date: None,
+            format: None,
+            translators: vec![],
+            original_title: None,
+            original_language: None,
+            series_number: None,
+            user_rating: None,
+            user_rating_count: 0,
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn normalize_canonicalises_isbn_and_fills_language() {
+        let mut b = book();
+        b.isbn = Some("4-15-209870-8".into());
+        b.publisher = Some(" 早川書房 ".into());
+        b.normalize();
+        assert_eq!(b.isbn.as_deref(), Some("9784152098702"));
+        assert_eq!(b.publisher.as_deref(), Some("早川書房"));
+        assert_eq!(b.original_language.as_deref(), Some("ja"));
+        assert!(b.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_malformed_details() {
+        let mut b = book();
+        b.isbn = Some("978-4-15-209870-3".into());
+        b.normalize();
+        assert_eq!(
+            b.validate().unwrap_err(),
+            "Invalid ISBN: 978-4-15-209870-3"
+        );
+
+        let mut b = book();
+        b.publication_date = Some("2019/07/04".into());
+        assert!(b.validate().is_err());
+
+        let mut b = book();
+        b.series_number = Some(0);
+        assert!(b.validate().is_err());
+    }
+
+    #[test]
+    fn formats_use_lowercase_names() {
+        assert_eq!(BookFormat::label_of("ebook"), "電子書籍");
+        assert_eq!(BookFormat::label_of("scroll"), "scroll");
+    }
 }
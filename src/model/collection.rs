@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::search::SearchHit;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CollectionKind {
+    // Numbered works, e.g. a novel series and its films
+    Series,
+    // A hand-picked list where the order is only presentation
+    #[default]
+    List,
+}
+
+impl CollectionKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CollectionKind::Series => "シリーズ",
+            CollectionKind::List => "リスト",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CollectionMember {
+    pub index: String,
+    pub id: i64,
+}
+
+impl CollectionMember {
+    // `movies:12`, the form stored in the filterable `member_keys` attribute
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.index, self.id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Collection {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub kind: CollectionKind,
+    // In display order; for a series this is the reading/viewing order
+    #[serde(default)]
+    pub members: Vec<CollectionMember>,
+    // Denormalised on save so the collection is findable by its members' titles
+    #[serde(default)]
+    pub member_titles: Vec<String>,
+    #[serde(default)]
+    pub member_keys: Vec<String>,
+}
+
+impl Collection {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Collection name is required".to_string());
+        }
+        if let Some(m) = self
+            .members
+            .iter()
+            .find(|m| !matches!(m.index.as_str(), "movies" | "books"))
+        {
+            return Err(format!("Unknown index: {}", m.index));
+        }
+        if let Some((i, m)) = self
+            .members
+            .iter()
+            .enumerate()
+            .find(|(i, m)| self.members[..*i].contains(m))
+        {
+            return Err(format!(
+                "Duplicate member at position {}: {}",
+                i + 1,
+                m.key()
+            ));
+        }
+        Ok(())
+    }
+
+    // Moves the member at `from` so it ends up at `to`, ignoring out-of-range positions
+    pub fn move_member(&mut self, from: usize, to: usize) {
+        if from >= self.members.len() || to >= self.members.len() {
+            return;
+        }
+        let member = self.members.remove(from);
+        self.members.insert(to, member);
+    }
+
+    // 1-based position of a member, as shown on result cards
+    pub fn position_of(&self, index: &str, id: i64) -> Option<usize> {
+        self.members
+            .iter()
+            .position(|m| m.index == index && m.id == id)
+            .map(|p| p + 1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionDetail {
+    pub collection: Collection,
+    pub members: Vec<SearchHit>,
+}
+
+// Where a hit sits within a series, shown on result cards
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SeriesPosition {
+    pub collection_id: i64,
+    pub collection_name: String,
+    pub position: usize,
+    pub total: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(index: &str, id: i64) -> CollectionMember {
+        CollectionMember {
+            index: index.to_string(),
+            id,
+        }
+    }
+
+    fn dune() -> Collection {
+        Collection {
+            id: 3,
+            name: "デューン".to_string(),
+            kind: CollectionKind::Series,
+            members: vec![
+                member("books", 19),
+                member("movies", 23),
+                member("movies", 99),
+            ],
+            ..Collection::default()
+        }
+    }
+
+    #[test]
+    fn validates_name_indexes_and_duplicates() {
+        assert!(dune().validate().is_ok());
+
+        let mut c = dune();
+        c.name = "  ".to_string();
+        assert!(c.validate().is_err());
+
+        let mut c = dune();
+        c.members.push(member("web", 1));
+        assert_eq!(c.validate().unwrap_err(), "Unknown index: web");
+
+        let mut c = dune();
+        c.members.push(member("books", 19));
+        assert_eq!(
+            c.validate().unwrap_err(),
+            "Duplicate member at position 4: books:19"
+        );
+    }
+
+    #[test]
+    fn moves_members_and_reports_positions() {
+        let mut c = dune();
+        c.move_member(2, 0);
+        assert_eq!(c.position_of("movies", 99), Some(1));
+        assert_eq!(c.position_of("books", 19), Some(2));
+        c.move_member(0, 5);
+        assert_eq!(c.position_of("movies", 99), Some(1));
+        assert_eq!(c.position_of("books", 1), None);
+    }
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+}
+
+// One dependency check of `/readyz`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    // Optional dependencies only degrade the service when they fail
+    pub required: bool,
+    #[serde(default)]
+    pub detail: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Readiness {
+    Ready,
+    // Searching works but an optional dependency such as SearXNG is down
+    Degraded,
+    Unavailable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HealthReport {
+    pub status: Readiness,
+    pub checks: Vec<Check>,
+}
+
+impl HealthReport {
+    pub fn new(checks: Vec<Check>) -> Self {
+        let failed = |required: bool| {
+            checks
+                .iter()
+                .any(|c| c.required == required && c.status == CheckStatus::Failed)
+        };
+        let status = if failed(true) {
+            Readiness::Unavailable
+        } else if failed(false) {
+            Readiness::Degraded
+        } else {
+            Readiness::Ready
+        };
+        HealthReport { status, checks }
+    }
+
+    // Text of the degraded-mode banner, `None` when everything works
+    pub fn banner(&self) -> Option<String> {
+        let failed: Vec<&str> = self
+            .checks
+            .iter()
+            .filter(|c| c.status == CheckStatus::Failed)
+            .map(|c| c.name.as_str())
+            .collect();
+        match self.status {
+            Readiness::Ready => None,
+            Readiness::Degraded => Some(format!(
+                "一部の機能が利用できません ({})。検索はそのまま使えます",
+                failed.join(", ")
+            )),
+            Readiness::Unavailable => Some(format!(
+                "検索サービスに接続できません ({})。しばらくしてから再度お試しください",
+                failed.join(", ")
+            )),
+        }
+    }
+}
+
+// Expected attributes an index's settings are missing
+pub fn missing_attributes<'a>(expected: &[&'a str], actual: &[String]) -> Vec<&'a str> {
+    expected
+        .iter()
+        .copied()
+        .filter(|a| !actual.iter().any(|b| b == a))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(name: &str, required: bool, ok: bool) -> Check {
+        Check {
+            name: name.to_string(),
+            status: if ok { CheckStatus::Ok } else { CheckStatus::Failed },
+            required,
+            detail: None,
+            elapsed_ms: 1,
+        }
+    }
+
+    #[test]
+    fn status_follows_required_checks() {
+        let ready = HealthReport::new(vec![check("meilisearch", true, true), check("searxng", false, true)]);
+        assert_eq!(ready.status, Readiness::Ready);
+        assert_eq!(ready.banner(), None);
+
+        let degraded = HealthReport::new(vec![check("meilisearch", true, true), check("searxng", false, false)]);
+        assert_eq!(degraded.status, Readiness::Degraded);
+        assert!(degraded.banner().unwrap().contains("searxng"));
+
+        let down = HealthReport::new(vec![check("meilisearch", true, false), check("searxng", false, false)]);
+        assert_eq!(down.status, Readiness::Unavailable);
+        assert!(down.banner().unwrap().contains("meilisearch, searxng"));
+    }
+
+    #[test]
+    fn missing_attributes_keeps_expected_order() {
+        let actual = vec!["year".to_string(), "genres".to_string()];
+        assert_eq!(missing_attributes(&["genres", "tags", "year", "rating"], &actual), vec!["tags", "rating"]);
+    }
+}
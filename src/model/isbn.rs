@@ -0,0 +1,119 @@
+// ISBN checksum validation and normalisation. Books are indexed under their
+// ISBN-13 without hyphens, so "4-06-274868-3" and "978-4-06-274868-1" both
+// resolve to the same document.
+
+fn digits(input: &str) -> Vec<char> {
+    input
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn is_isbn10(chars: &[char]) -> bool {
+    if chars.len() != 10 {
+        return false;
+    }
+    let mut sum = 0;
+    for (i, c) in chars.iter().enumerate() {
+        let value = match c.to_digit(10) {
+            Some(d) => d,
+            // X stands for 10 and only in the check position
+            None if *c == 'X' && i == 9 => 10,
+            None => return false,
+        };
+        sum += (10 - i as u32) * value;
+    }
+    sum % 11 == 0
+}
+
+fn isbn13_check(first12: &[u32]) -> u32 {
+    let sum: u32 = first12
+        .iter()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { *d } else { d * 3 })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+fn is_isbn13(chars: &[char]) -> bool {
+    let Some(values) = chars
+        .iter()
+        .map(|c| c.to_digit(10))
+        .collect::<Option<Vec<u32>>>()
+    else {
+        return false;
+    };
+    values.len() == 13 && isbn13_check(&values[..12]) == values[12]
+}
+
+// The canonical ISBN-13 for a valid ISBN-10 or ISBN-13, hyphens and spaces allowed
+pub fn normalize_isbn(input: &str) -> Option<String> {
+    let chars = digits(input.trim());
+    if is_isbn13(&chars) {
+        return Some(chars.into_iter().collect());
+    }
+    if !is_isbn10(&chars) {
+        return None;
+    }
+    let mut values: Vec<u32> = [9, 7, 8].into();
+    values.extend(chars[..9].iter().filter_map(|c| c.to_digit(10)));
+    values.push(isbn13_check(&values));
+    Some(values.iter().map(|d| d.to_string()).collect())
+}
+
+// The ISBN-10 form of a 978-prefixed ISBN-13, for display next to the canonical one
+pub fn isbn10(isbn13: &str) -> Option<String> {
+    let body = isbn13.strip_prefix("978").filter(|b| b.len() == 10)?;
+    let values = body[..9]
+        .chars()
+        .map(|c| c.to_digit(10))
+        .collect::<Option<Vec<u32>>>()?;
+    let sum: u32 = values
+        .iter()
+        .enumerate()
+        .map(|(i, d)| (10 - i as u32) * d)
+        .sum();
+    let check = match (11 - sum % 11) % 11 {
+        10 => 'X',
+        d => char::from_digit(d, 10)?,
+    };
+    Some(format!("{}{check}", &body[..9]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_both_lengths_with_hyphens() {
+        assert_eq!(
+            normalize_isbn("978-4-15-209870-2").as_deref(),
+            Some("9784152098702")
+        );
+        assert_eq!(
+            normalize_isbn("0-451-52493-4").as_deref(),
+            Some("9780451524935")
+        );
+        assert_eq!(
+            normalize_isbn("0 8044 2957 x").as_deref(),
+            Some("9780804429573")
+        );
+    }
+
+    #[test]
+    fn rejects_bad_checksums_and_shapes() {
+        assert_eq!(normalize_isbn("9784152098703"), None);
+        assert_eq!(normalize_isbn("0451524935"), None);
+        assert_eq!(normalize_isbn("X451524934"), None);
+        assert_eq!(normalize_isbn("1984"), None);
+        assert_eq!(normalize_isbn(""), None);
+    }
+
+    #[test]
+    fn converts_back_to_isbn10() {
+        assert_eq!(isbn10("9780451524935").as_deref(), Some("0451524934"));
+        assert_eq!(isbn10("9780804429573").as_deref(), Some("080442957X"));
+        assert_eq!(isbn10("9791032305690"), None);
+    }
+}
@@ -1,4 +1,18 @@
+pub mod analytics;
 pub mod book;
+pub mod collection;
+pub mod date;
+pub mod health;
+pub mod isbn;
 pub mod movie;
+pub mod person;
+pub mod query;
+pub mod reading;
+pub mod review;
+pub mod saved_search;
 pub mod search;
+pub mod search_settings;
+pub mod series;
+pub mod tag;
 pub mod web_result;
+pub mod user;
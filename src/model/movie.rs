@@ -1,17 +1,218 @@
 use serde::{Deserialize, Serialize};
 
+use crate::model::date::is_date;
+use crate::model::reading::Readings;
+
+// Eirin ratings, the only certification scheme the catalogue uses
+pub const CERTIFICATIONS: &[&str] = &["G", "PG12", "R15+", "R18+"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ExternalIds {
+    #[serde(default)]
+    pub imdb: Option<String>,
+    #[serde(default)]
+    pub tmdb: Option<u64>,
+    #[serde(default)]
+    pub wikidata: Option<String>,
+}
+
+impl ExternalIds {
+    pub fn imdb_url(&self) -> Option<String> {
+        self.imdb
+            .as_ref()
+            .map(|id| format!("https://www.imdb.com/title/{id}/"))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Movie {
     pub id: i64,
     pub title: String,
     #[serde(default)]
     pub title_en: Option<String>,
+    #[serde(default)]
+    pub title_reading: Option<Readings>,
     pub description: String,
     pub director: String,
+    #[serde(default)]
+    pub director_reading: Option<Readings>,
+    // The `people` document this name was linked to
+    #[serde(default)]
+    pub director_id: Option<i64>,
     pub year: i32,
     pub genres: Vec<String>,
     pub rating: f64,
     #[serde(default)]
     pub poster_url: Option<String>,
+    // Language of the catalogue entry, not of the film itself
     pub language: String,
+    #[serde(default)]
+    pub runtime_minutes: Option<u32>,
+    // Billing order
+    #[serde(default)]
+    pub cast: Vec<String>,
+    // ISO 3166-1 alpha-2 codes of the producing countries
+    #[serde(default)]
+    pub countries: Vec<String>,
+    #[serde(default)]
+    pub certification: Option<String>,
+    // YYYY-MM-DD, first theatrical release
+    #[serde(default)]
+    pub release_date: Option<String>,
+    #[serde(default)]
+    pub original_language: Option<String>,
+    #[serde(default)]
+    pub spoken_languages: Vec<String>,
+    #[serde(default)]
+    pub external_ids: ExternalIds,
+    // Average of visible user reviews, written back by `server::reviews`
+    #[serde(default)]
+    pub user_rating: Option<f64>,
+    #[serde(default)]
+    pub user_rating_count: u32,
+    // Tags enough users agree on, written back by `server::tags`
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// Display names for the producing countries used in the catalogue
+pub fn country_label(code: &str) -> &str {
+    match code {
+        "JP" => "日本",
+        "US" => "アメリカ",
+        "GB" => "イギリス",
+        "FR" => "フランス",
+        "DE" => "ドイツ",
+        "IT" => "イタリア",
+        "KR" => "韓国",
+        "CN" => "中国",
+        "HK" => "香港",
+        "TW" => "台湾",
+        "CA" => "カナダ",
+        "AU" => "オーストラリア",
+        "NZ" => "ニュージーランド",
+        _ => code,
+    }
+}
+
+impl Movie {
+    // Tidies hand-written seed and admin input before it is indexed
+    pub fn normalize(&mut self) {
+        self.certification = self
+            .certification
+            .take()
+            .map(|c| c.trim().to_uppercase().replace(['-', ' '], ""))
+            .filter(|c| !c.is_empty());
+        for code in self.countries.iter_mut() {
+            *code = code.trim().to_uppercase();
+        }
+        self.countries.retain(|c| !c.is_empty());
+        // `language` is the catalogue entry's, so it says nothing about the film
+        if self.spoken_languages.is_empty() {
+            self.spoken_languages
+                .extend(self.original_language.iter().cloned());
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.runtime_minutes == Some(0) {
+            return Err("Runtime must be at least one minute".to_string());
+        }
+        if let Some(c) = self
+            .certification
+            .as_ref()
+            .filter(|c| !CERTIFICATIONS.contains(&c.as_str()))
+        {
+            return Err(format!("Unknown certification: {c}"));
+        }
+        if let Some(d) = self.release_date.as_ref().filter(|d| !is_date(d)) {
+            return Err(format!("Release date must be YYYY-MM-DD: {d}"));
+        }
+        if let Some(c) = self
+            .countries
+            .iter()
+            .find(|c| c.len() != 2 || !c.bytes().all(|b| b.is_ascii_uppercase()))
+        {
+            return Err(format!("Country must be an ISO 3166 code: {c}"));
+        }
+        if let Some(id) = self.external_ids.imdb.as_ref().filter(|id| {
+            let digits = id.strip_prefix("tt").unwrap_or_default();
+            !(7..=8).contains(&digits.len()) || !digits.bytes().all(|b| b.is_ascii_digit())
+        }) {
+            return Err(format!("Invalid IMDb id: {id}"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn movie() -> Movie {
+        Movie {
+            id: 1,
+            title: "千と千尋の神隠し".into(),
+            title_en: None,
+            title_reading: None,
+            description: String::new(),
+            director: "宮崎駿".into(),
+            director_reading: None,
+            director_id: None,
+            year: 2001,
+            genres: vec![],
+            rating: 8.6,
+            poster_url: None,
+            language: "ja".into(),
+            runtime_minutes: Some(125),
+            cast: vec![],
+            countries: vec![],
+            certification: None,
+            release_date: None,
+            original_language: None,
+            spoken_languages: vec![],
+            external_ids: ExternalIds::default(),
+            user_rating: None,
+            user_rating_count: 0,
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn normalize_fills_languages_and_tidies_codes() {
+        let mut m = movie();
+        m.certification = Some("pg-12".into());
+        m.countries = vec![" jp".into(), "".into()];
+        m.normalize();
+        assert_eq!(m.certification.as_deref(), Some("PG12"));
+        assert_eq!(m.countries, vec!["JP"]);
+        assert_eq!(m.original_language, None);
+        assert!(m.spoken_languages.is_empty());
+        assert!(m.validate().is_ok());
+
+        m.original_language = Some("en".into());
+        m.normalize();
+        assert_eq!(m.spoken_languages, vec!["en"]);
+    }
+
+    #[test]
+    fn validate_rejects_malformed_details() {
+        let mut m = movie();
+        m.release_date = Some("2001-7-20".into());
+        assert!(m.validate().unwrap_err().starts_with("Release date"));
+
+        let mut m = movie();
+        m.certification = Some("PG13".into());
+        assert_eq!(m.validate().unwrap_err(), "Unknown certification: PG13");
+
+        let mut m = movie();
+        m.external_ids.imdb = Some("0245429".into());
+        assert!(m.validate().is_err());
+        m.external_ids.imdb = Some("tt0245429".into());
+        assert!(m.validate().is_ok());
+
+        let mut m = movie();
+        m.runtime_minutes = Some(0);
+        assert!(m.validate().is_err());
+    }
 }
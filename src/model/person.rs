@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::reading::Readings;
+use crate::model::search::SearchHit;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Director,
+    Author,
+}
+
+impl Role {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::Director => "監督",
+            Role::Author => "著者",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Person {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub name_reading: Option<Readings>,
+    // Other spellings of the name, e.g. the romanised form
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub roles: Vec<Role>,
+    #[serde(default)]
+    pub bio: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonDetail {
+    pub person: Person,
+    pub works: Vec<SearchHit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationResult {
+    pub people_count: usize,
+    pub movies_linked: usize,
+    pub books_linked: usize,
+}
@@ -0,0 +1,588 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::isbn::normalize_isbn;
+
+// Structured query syntax typed into the search bar, e.g.
+// `director:宮崎 year:1990..2000 -アクション "千と千尋"`
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ParsedQuery {
+    // Free text and quoted phrases, passed to Meilisearch as the main query
+    pub text: String,
+    pub creators: Vec<String>,
+    pub excluded_creators: Vec<String>,
+    pub genres: Vec<String>,
+    pub excluded_genres: Vec<String>,
+    pub languages: Vec<String>,
+    pub year_min: Option<i32>,
+    pub year_max: Option<i32>,
+    pub rating_min: Option<f64>,
+    pub rating_max: Option<f64>,
+    // Movies only; ignored by indexes without these attributes
+    pub cast: Vec<String>,
+    pub runtime_min: Option<i32>,
+    pub runtime_max: Option<i32>,
+    // Books only; a bare ISBN in the free text also lands here as ISBN-13
+    pub isbn: Option<String>,
+    // Average of user reviews, see `server::reviews`
+    pub user_rating_min: Option<f64>,
+    pub user_rating_max: Option<f64>,
+    // User tags, see `server::tags`
+    pub tags: Vec<String>,
+    pub excluded_tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueryParseError {
+    pub message: String,
+    // Character offset of the offending token
+    pub position: usize,
+}
+
+impl std::fmt::Display for QueryParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}文字目)", self.message, self.position + 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Creator,
+    Genre,
+    Year,
+    Rating,
+    UserRating,
+    Tag,
+    Language,
+    Cast,
+    Runtime,
+    Isbn,
+}
+
+fn field_of(name: &str) -> Option<Field> {
+    match name.to_lowercase().as_str() {
+        "director" | "author" | "creator" | "studio" | "監督" | "著者" | "制作" => {
+            Some(Field::Creator)
+        }
+        "genre" | "genres" | "ジャンル" => Some(Field::Genre),
+        "year" | "年" => Some(Field::Year),
+        "rating" | "評価" => Some(Field::Rating),
+        "user_rating" | "みんなの評価" => Some(Field::UserRating),
+        "tag" | "tags" | "タグ" => Some(Field::Tag),
+        "lang" | "language" | "言語" => Some(Field::Language),
+        "cast" | "出演" => Some(Field::Cast),
+        "runtime" | "上映時間" => Some(Field::Runtime),
+        "isbn" => Some(Field::Isbn),
+        _ => None,
+    }
+}
+
+struct Token {
+    negated: bool,
+    field: Option<String>,
+    value: String,
+    quoted: bool,
+    position: usize,
+    // One past the last char, so the token's source text can be recovered
+    end: usize,
+}
+
+fn error(message: impl Into<String>, position: usize) -> QueryParseError {
+    QueryParseError {
+        message: message.into(),
+        position,
+    }
+}
+
+fn is_space(c: char) -> bool {
+    c.is_whitespace()
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if is_space(chars[i]) {
+            i += 1;
+            continue;
+        }
+
+        let position = i;
+        let negated = chars[i] == '-' && chars.get(i + 1).is_some_and(|c| !is_space(*c));
+        if negated {
+            i += 1;
+        }
+
+        // A leading `name:` is only treated as a field when the name is known,
+        // so titles like "Re:ゼロ" still search as plain text
+        let mut field = None;
+        let word_end = (i..chars.len())
+            .find(|&j| is_space(chars[j]) || chars[j] == '"')
+            .unwrap_or(chars.len());
+        if let Some(colon) = (i..word_end).find(|&j| chars[j] == ':') {
+            let name: String = chars[i..colon].iter().collect();
+            if field_of(&name).is_some() {
+                field = Some(name);
+                i = colon + 1;
+            }
+        }
+
+        let (value, quoted) = if chars.get(i) == Some(&'"') {
+            let start = i + 1;
+            let end = (start..chars.len())
+                .find(|&j| chars[j] == '"')
+                .ok_or_else(|| error("引用符が閉じられていません", i))?;
+            i = end + 1;
+            (chars[start..end].iter().collect::<String>(), true)
+        } else {
+            let start = i;
+            while i < chars.len() && !is_space(chars[i]) {
+                i += 1;
+            }
+            (chars[start..i].iter().collect::<String>(), false)
+        };
+
+        if field.is_some() && value.trim().is_empty() {
+            return Err(error("フィールドの値が空です", position));
+        }
+
+        tokens.push(Token {
+            negated,
+            field,
+            value,
+            quoted,
+            position,
+            end: i,
+        });
+    }
+
+    Ok(tokens)
+}
+
+// One word of a raw query as `parse_query` splits it. `free` marks plain
+// text; field values, exclusions and phrases are structure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryWord {
+    pub text: String,
+    pub free: bool,
+}
+
+// The query split into words, for rewriting parts of it in place
+pub fn query_words(input: &str) -> Result<Vec<QueryWord>, QueryParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    Ok(tokenize(input)?
+        .into_iter()
+        .map(|t| QueryWord {
+            text: chars[t.position..t.end].iter().collect(),
+            free: t.field.is_none() && !t.negated && !t.quoted,
+        })
+        .collect())
+}
+
+// A value as it has to be typed to stay one word
+pub fn quoted(value: &str) -> String {
+    if value.chars().any(char::is_whitespace) {
+        format!("\"{value}\"")
+    } else {
+        value.to_string()
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str, position: usize) -> Result<T, QueryParseError> {
+    text.trim()
+        .parse()
+        .map_err(|_| error(format!("数値ではありません: {text}"), position))
+}
+
+// `>` and `<` move the bound by one step, which integers may overflow
+trait Step: Sized {
+    fn step_up(self, step: Self) -> Option<Self>;
+    fn step_down(self, step: Self) -> Option<Self>;
+}
+
+impl Step for i32 {
+    fn step_up(self, step: Self) -> Option<Self> {
+        self.checked_add(step)
+    }
+
+    fn step_down(self, step: Self) -> Option<Self> {
+        self.checked_sub(step)
+    }
+}
+
+impl Step for f64 {
+    fn step_up(self, step: Self) -> Option<Self> {
+        Some(self + step)
+    }
+
+    fn step_down(self, step: Self) -> Option<Self> {
+        Some(self - step)
+    }
+}
+
+// Accepts `1990`, `1990..2000`, `1990..`, `..2000`, `>=1990`, `>1990`, `<=2000` and `<2000`
+fn parse_range<T>(
+    text: &str,
+    position: usize,
+    step: T,
+) -> Result<(Option<T>, Option<T>), QueryParseError>
+where
+    T: std::str::FromStr + PartialOrd + Copy + Step,
+{
+    let out_of_range = || error(format!("範囲外の値です: {text}"), position);
+    let (min, max) = if let Some((lo, hi)) = text.split_once("..") {
+        let lo = (!lo.is_empty())
+            .then(|| parse_number(lo, position))
+            .transpose()?;
+        let hi = (!hi.is_empty())
+            .then(|| parse_number(hi, position))
+            .transpose()?;
+        if lo.is_none() && hi.is_none() {
+            return Err(error("範囲が空です", position));
+        }
+        (lo, hi)
+    } else if let Some(v) = text.strip_prefix(">=") {
+        (Some(parse_number(v, position)?), None)
+    } else if let Some(v) = text.strip_prefix("<=") {
+        (None, Some(parse_number(v, position)?))
+    } else if let Some(v) = text.strip_prefix('>') {
+        let v = parse_number::<T>(v, position)?;
+        (Some(v.step_up(step).ok_or_else(out_of_range)?), None)
+    } else if let Some(v) = text.strip_prefix('<') {
+        let v = parse_number::<T>(v, position)?;
+        (None, Some(v.step_down(step).ok_or_else(out_of_range)?))
+    } else {
+        let v = parse_number(text, position)?;
+        (Some(v), Some(v))
+    };
+
+    if let (Some(lo), Some(hi)) = (min, max) {
+        if lo > hi {
+            return Err(error("範囲の下限が上限より大きくなっています", position));
+        }
+    }
+
+    Ok((min, max))
+}
+
+// Repeated fields narrow each other; reported at the token that emptied the range
+fn check_bounds<T: PartialOrd>(
+    min: Option<T>,
+    max: Option<T>,
+    what: &str,
+    position: usize,
+) -> Result<(), QueryParseError> {
+    match (min, max) {
+        (Some(lo), Some(hi)) if lo > hi => Err(error(format!("{what}の条件が矛盾しています"), position)),
+        _ => Ok(()),
+    }
+}
+
+fn tighten_min<T: PartialOrd + Copy>(current: Option<T>, new: Option<T>) -> Option<T> {
+    match (current, new) {
+        (Some(a), Some(b)) => Some(if b > a { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
+
+fn tighten_max<T: PartialOrd + Copy>(current: Option<T>, new: Option<T>) -> Option<T> {
+    match (current, new) {
+        (Some(a), Some(b)) => Some(if b < a { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
+
+pub fn parse_query(input: &str) -> Result<ParsedQuery, QueryParseError> {
+    let mut parsed = ParsedQuery::default();
+    let mut text = Vec::new();
+
+    for token in tokenize(input)? {
+        let field = token.field.as_deref().and_then(field_of);
+
+        match (field, token.negated) {
+            (None, false) if token.quoted => text.push(format!("\"{}\"", token.value)),
+            (None, false) if normalize_isbn(&token.value).is_some() => {
+                parsed.isbn = normalize_isbn(&token.value);
+            }
+            (None, false) => text.push(token.value),
+            // A bare negated term excludes a genre
+            (None, true) | (Some(Field::Genre), true) => parsed.excluded_genres.push(token.value),
+            (Some(Field::Genre), false) => parsed.genres.push(token.value),
+            (Some(Field::Creator), false) => parsed.creators.push(token.value),
+            (Some(Field::Creator), true) => parsed.excluded_creators.push(token.value),
+            (Some(Field::Tag), false) => parsed.tags.push(token.value),
+            (Some(Field::Tag), true) => parsed.excluded_tags.push(token.value),
+            (Some(Field::Language), false) => parsed.languages.push(token.value),
+            (Some(Field::Cast), false) => parsed.cast.push(token.value),
+            (Some(Field::Isbn), false) => {
+                let isbn = normalize_isbn(&token.value).ok_or_else(|| {
+                    error(format!("ISBN が正しくありません: {}", token.value), token.position)
+                })?;
+                parsed.isbn = Some(isbn);
+            }
+            (Some(Field::Year), false) => {
+                let (min, max) = parse_range(&token.value, token.position, 1)?;
+                parsed.year_min = tighten_min(parsed.year_min, min);
+                parsed.year_max = tighten_max(parsed.year_max, max);
+                check_bounds(parsed.year_min, parsed.year_max, "年", token.position)?;
+            }
+            (Some(Field::Rating), false) => {
+                let (min, max) = parse_range(&token.value, token.position, 0.1)?;
+                parsed.rating_min = tighten_min(parsed.rating_min, min);
+                parsed.rating_max = tighten_max(parsed.rating_max, max);
+                check_bounds(parsed.rating_min, parsed.rating_max, "評価", token.position)?;
+            }
+            (Some(Field::UserRating), false) => {
+                let (min, max) = parse_range(&token.value, token.position, 0.1)?;
+                parsed.user_rating_min = tighten_min(parsed.user_rating_min, min);
+                parsed.user_rating_max = tighten_max(parsed.user_rating_max, max);
+                check_bounds(
+                    parsed.user_rating_min,
+                    parsed.user_rating_max,
+                    "みんなの評価",
+                    token.position,
+                )?;
+            }
+            (Some(Field::Runtime), false) => {
+                let (min, max) = parse_range(&token.value, token.position, 1)?;
+                parsed.runtime_min = tighten_min(parsed.runtime_min, min);
+                parsed.runtime_max = tighten_max(parsed.runtime_max, max);
+                check_bounds(parsed.runtime_min, parsed.runtime_max, "上映時間", token.position)?;
+            }
+            (Some(_), true) => {
+                return Err(error(
+                    format!(
+                        "{}: の指定は除外 (-) できません",
+                        token.field.as_deref().unwrap_or_default()
+                    ),
+                    token.position,
+                ))
+            }
+        }
+    }
+
+    parsed.text = text.join(" ");
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_passes_through() {
+        let parsed = parse_query("千と千尋 spirited away").unwrap();
+        assert_eq!(parsed.text, "千と千尋 spirited away");
+        assert_eq!(
+            parsed,
+            ParsedQuery {
+                text: parsed.text.clone(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(parse_query("").unwrap(), ParsedQuery::default());
+        assert_eq!(parse_query("   ").unwrap(), ParsedQuery::default());
+    }
+
+    #[test]
+    fn parses_the_full_example() {
+        let parsed =
+            parse_query(r#"director:宮崎 year:1990..2000 -アクション "千と千尋""#).unwrap();
+        assert_eq!(parsed.creators, vec!["宮崎"]);
+        assert_eq!(parsed.year_min, Some(1990));
+        assert_eq!(parsed.year_max, Some(2000));
+        assert_eq!(parsed.excluded_genres, vec!["アクション"]);
+        assert_eq!(parsed.text, "\"千と千尋\"");
+    }
+
+    #[test]
+    fn field_aliases() {
+        for q in ["author:村上", "creator:村上", "監督:村上", "AUTHOR:村上", "制作:村上"] {
+            assert_eq!(parse_query(q).unwrap().creators, vec!["村上"], "{q}");
+        }
+        assert_eq!(parse_query("genre:SF").unwrap().genres, vec!["SF"]);
+        assert_eq!(parse_query("lang:ja").unwrap().languages, vec!["ja"]);
+    }
+
+    #[test]
+    fn quoted_field_values() {
+        let parsed = parse_query(r#"director:"Christopher Nolan" batman"#).unwrap();
+        assert_eq!(parsed.creators, vec!["Christopher Nolan"]);
+        assert_eq!(parsed.text, "batman");
+    }
+
+    #[test]
+    fn negations() {
+        let parsed = parse_query("-genre:ホラー -director:Nolan -ドラマ").unwrap();
+        assert_eq!(parsed.excluded_genres, vec!["ホラー", "ドラマ"]);
+        assert_eq!(parsed.excluded_creators, vec!["Nolan"]);
+        assert!(parsed.text.is_empty());
+    }
+
+    #[test]
+    fn lone_dash_is_text() {
+        assert_eq!(parse_query("spider - man").unwrap().text, "spider - man");
+    }
+
+    #[test]
+    fn year_ranges() {
+        let cases = [
+            ("year:1997", (Some(1997), Some(1997))),
+            ("year:1990..", (Some(1990), None)),
+            ("year:..2000", (None, Some(2000))),
+            ("year:>=2001", (Some(2001), None)),
+            ("year:>2001", (Some(2002), None)),
+            ("year:<=1980", (None, Some(1980))),
+            ("year:<1980", (None, Some(1979))),
+        ];
+        for (q, (min, max)) in cases {
+            let parsed = parse_query(q).unwrap();
+            assert_eq!((parsed.year_min, parsed.year_max), (min, max), "{q}");
+        }
+    }
+
+    #[test]
+    fn repeated_ranges_intersect() {
+        let parsed = parse_query("year:1980.. year:..2000 year:>=1990").unwrap();
+        assert_eq!((parsed.year_min, parsed.year_max), (Some(1990), Some(2000)));
+    }
+
+    #[test]
+    fn rating_ranges() {
+        let parsed = parse_query("rating:8..9.5").unwrap();
+        assert_eq!(
+            (parsed.rating_min, parsed.rating_max),
+            (Some(8.0), Some(9.5))
+        );
+        let parsed = parse_query("rating:>=8.5").unwrap();
+        assert_eq!(parsed.rating_min, Some(8.5));
+    }
+
+    #[test]
+    fn user_rating_is_separate_from_rating() {
+        let parsed = parse_query("みんなの評価:>=8 rating:..9").unwrap();
+        assert_eq!(parsed.user_rating_min, Some(8.0));
+        assert_eq!(parsed.user_rating_max, None);
+        assert_eq!(parsed.rating_max, Some(9.0));
+    }
+
+    #[test]
+    fn tags_can_be_required_or_excluded() {
+        let parsed = parse_query("tag:泣ける -タグ:ホラー 週末").unwrap();
+        assert_eq!(parsed.tags, vec!["泣ける"]);
+        assert_eq!(parsed.excluded_tags, vec!["ホラー"]);
+        assert_eq!(parsed.text, "週末");
+    }
+
+    #[test]
+    fn cast_and_runtime() {
+        let parsed = parse_query(r#"出演:"Keanu Reeves" runtime:90..120 runtime:>100"#).unwrap();
+        assert_eq!(parsed.cast, vec!["Keanu Reeves"]);
+        assert_eq!(
+            (parsed.runtime_min, parsed.runtime_max),
+            (Some(101), Some(120))
+        );
+        assert!(parse_query("-cast:誰か").is_err());
+        assert!(parse_query("上映時間:長い").is_err());
+    }
+
+    #[test]
+    fn isbn_lookup() {
+        let parsed = parse_query("978-4-15-209870-2").unwrap();
+        assert_eq!(parsed.isbn.as_deref(), Some("9784152098702"));
+        assert!(parsed.text.is_empty());
+        let parsed = parse_query("isbn:0451524934").unwrap();
+        assert_eq!(parsed.isbn.as_deref(), Some("9780451524935"));
+        assert_eq!(parse_query("1984").unwrap().isbn, None);
+        assert_eq!(parse_query("isbn:12345").unwrap_err().position, 0);
+    }
+
+    #[test]
+    fn unknown_fields_are_text() {
+        let parsed = parse_query("Re:ゼロ The Lord of the Rings: Fellowship").unwrap();
+        assert_eq!(parsed.text, "Re:ゼロ The Lord of the Rings: Fellowship");
+    }
+
+    #[test]
+    fn unterminated_quote() {
+        let err = parse_query(r#"foo "bar baz"#).unwrap_err();
+        assert_eq!(err.position, 4);
+    }
+
+    #[test]
+    fn empty_field_value() {
+        let err = parse_query("director: 宮崎").unwrap_err();
+        assert_eq!(err.position, 0);
+        assert!(parse_query(r#"genre:"""#).is_err());
+    }
+
+    #[test]
+    fn invalid_numbers_and_ranges() {
+        assert!(parse_query("year:abc").is_err());
+        assert!(parse_query("year:2000..1990").is_err());
+        assert!(parse_query("year:..").is_err());
+        assert!(parse_query("rating:high").is_err());
+        assert_eq!(parse_query("foo year:x").unwrap_err().position, 4);
+    }
+
+    #[test]
+    fn contradictory_ranges() {
+        assert!(parse_query("year:..1990 year:2000..").is_err());
+        assert!(parse_query("rating:..5 rating:8..").is_err());
+        assert!(parse_query("rating:8..5").is_err());
+        assert!(parse_query("user_rating:..3 user_rating:4..").is_err());
+        assert!(parse_query("rating:5..8").is_ok());
+        let e = parse_query("runtime:>=120 dune runtime:<=90").unwrap_err();
+        assert_eq!(e.message, "上映時間の条件が矛盾しています");
+        assert_eq!(e.position, 19);
+    }
+
+    #[test]
+    fn words_keep_their_source_text() {
+        let words = query_words("director:\"宮崎 駿\" -horror \"spirited away\" Re:ゼロ　toto").unwrap();
+        let pairs: Vec<(&str, bool)> = words.iter().map(|w| (w.text.as_str(), w.free)).collect();
+        assert_eq!(
+            pairs,
+            [
+                ("director:\"宮崎 駿\"", false),
+                ("-horror", false),
+                ("\"spirited away\"", false),
+                ("Re:ゼロ", true),
+                ("toto", true),
+            ]
+        );
+        assert!(query_words("\"open").is_err());
+    }
+
+    #[test]
+    fn values_with_spaces_are_quoted() {
+        assert_eq!(quoted("千と千尋の神隠し"), "千と千尋の神隠し");
+        assert_eq!(quoted("Star Wars"), "\"Star Wars\"");
+        assert_eq!(quoted("ルパン三世　カリオストロの城"), "\"ルパン三世　カリオストロの城\"");
+    }
+
+    #[test]
+    fn steps_past_the_integer_range_are_errors() {
+        let e = parse_query("year:>2147483647").unwrap_err();
+        assert_eq!(e.message, "範囲外の値です: >2147483647");
+        assert!(parse_query("runtime:<-2147483648").is_err());
+        assert_eq!(parse_query("year:>2000").unwrap().year_min, Some(2001));
+    }
+
+    #[test]
+    fn negated_ranges_are_rejected() {
+        assert!(parse_query("-year:2000").is_err());
+        assert!(parse_query("-lang:en").is_err());
+    }
+
+    #[test]
+    fn multibyte_positions_are_character_offsets() {
+        let err = parse_query("宮崎 年:abc").unwrap_err();
+        assert_eq!(err.position, 3);
+    }
+}
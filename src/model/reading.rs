@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Readings {
+    #[serde(default)]
+    pub hiragana: Option<String>,
+    #[serde(default)]
+    pub katakana: Option<String>,
+    #[serde(default)]
+    pub romaji: Option<String>,
+    // The text the readings were made for, so they can be told apart from
+    // readings of an earlier title or name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
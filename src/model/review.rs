@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+
+pub const SCORE_MIN: u8 = 1;
+pub const SCORE_MAX: u8 = 10;
+const BODY_MAX: usize = 4000;
+// A review flagged by this many different users is hidden until a moderator
+// looks at it
+pub const AUTO_HIDE_FLAGS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewStatus {
+    #[default]
+    Visible,
+    // Hidden by a moderator or by enough flags; excluded from the aggregate
+    Hidden,
+}
+
+impl ReviewStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewStatus::Visible => "visible",
+            ReviewStatus::Hidden => "hidden",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReviewStatus::Visible => "公開",
+            ReviewStatus::Hidden => "非表示",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagReason {
+    Spam,
+    Offensive,
+    Spoiler,
+    OffTopic,
+}
+
+impl FlagReason {
+    pub const ALL: [FlagReason; 4] = [
+        FlagReason::Spam,
+        FlagReason::Offensive,
+        FlagReason::Spoiler,
+        FlagReason::OffTopic,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlagReason::Spam => "spam",
+            FlagReason::Offensive => "offensive",
+            FlagReason::Spoiler => "spoiler",
+            FlagReason::OffTopic => "off_topic",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FlagReason::Spam => "スパム",
+            FlagReason::Offensive => "不適切な表現",
+            FlagReason::Spoiler => "ネタバレ",
+            FlagReason::OffTopic => "作品と無関係",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReviewFlag {
+    pub user_id: i64,
+    pub reason: FlagReason,
+    pub created_at: u64,
+}
+
+// One user's score and text for one item, stored in the `reviews` index
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Review {
+    // `{user_id}-{index}-{item_id}`: a user has at most one review per item
+    pub id: String,
+    pub user_id: i64,
+    pub username: String,
+    pub index: String,
+    pub item_id: i64,
+    // Copied from the item so review search can match on it
+    pub item_title: String,
+    pub score: u8,
+    #[serde(default)]
+    pub body: String,
+    // Unix seconds
+    pub created_at: u64,
+    pub updated_at: u64,
+    #[serde(default)]
+    pub status: ReviewStatus,
+    #[serde(default)]
+    pub flags: Vec<ReviewFlag>,
+    // `flags.len()`, kept on the document so the moderation queue can sort on it
+    #[serde(default)]
+    pub flag_count: u32,
+}
+
+impl Review {
+    pub fn document_id(user_id: i64, index: &str, item_id: i64) -> String {
+        format!("{user_id}-{index}-{item_id}")
+    }
+
+    pub fn detail_url(&self) -> String {
+        match self.index.as_str() {
+            "movies" => format!("/movie/{}", self.item_id),
+            "series" => format!("/series/{}", self.item_id),
+            _ => format!("/book/{}", self.item_id),
+        }
+    }
+
+    pub fn normalize(&mut self) {
+        self.body = self.body.trim().to_string();
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(SCORE_MIN..=SCORE_MAX).contains(&self.score) {
+            return Err(format!(
+                "Score must be between {SCORE_MIN} and {SCORE_MAX}"
+            ));
+        }
+        if self.body.chars().count() > BODY_MAX {
+            return Err(format!("Review must be at most {BODY_MAX} characters"));
+        }
+        Ok(())
+    }
+
+    // Records a flag unless this user already flagged the review. Returns
+    // whether the review was hidden as a result.
+    pub fn add_flag(&mut self, user_id: i64, reason: FlagReason, now: u64) -> bool {
+        if self.flags.iter().any(|f| f.user_id == user_id) {
+            return false;
+        }
+        self.flags.push(ReviewFlag {
+            user_id,
+            reason,
+            created_at: now,
+        });
+        self.flag_count = self.flags.len() as u32;
+        if self.status == ReviewStatus::Visible && self.flag_count >= AUTO_HIDE_FLAGS {
+            self.status = ReviewStatus::Hidden;
+            return true;
+        }
+        false
+    }
+
+    // A moderator's decision; approving a review also clears its flags
+    pub fn moderate(&mut self, status: ReviewStatus) {
+        self.status = status;
+        if status == ReviewStatus::Visible {
+            self.flags.clear();
+            self.flag_count = 0;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct RatingSummary {
+    // Rounded to two decimals; `None` until someone has rated the item
+    pub average: Option<f64>,
+    pub count: u32,
+    // `histogram[0]` counts scores of 1, `histogram[9]` scores of 10
+    pub histogram: Vec<u32>,
+}
+
+impl RatingSummary {
+    // From a facet distribution over `score`, whose keys are the scores as text
+    pub fn from_distribution<'a>(distribution: impl IntoIterator<Item = (&'a str, usize)>) -> Self {
+        let mut histogram = vec![0u32; usize::from(SCORE_MAX)];
+        for (score, count) in distribution {
+            if let Some(slot) = score
+                .parse::<usize>()
+                .ok()
+                .filter(|s| (usize::from(SCORE_MIN)..=usize::from(SCORE_MAX)).contains(s))
+            {
+                histogram[slot - 1] += count as u32;
+            }
+        }
+        let count: u32 = histogram.iter().sum();
+        let total: u32 = histogram
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (i as u32 + 1) * n)
+            .sum();
+        let average =
+            (count > 0).then(|| (f64::from(total) / f64::from(count) * 100.0).round() / 100.0);
+        RatingSummary {
+            average,
+            count,
+            histogram,
+        }
+    }
+
+    // Share of the most common score, for scaling histogram bars
+    pub fn bar_width(&self, score: u8) -> f64 {
+        let max = self.histogram.iter().copied().max().unwrap_or(0);
+        let n = self
+            .histogram
+            .get(usize::from(score).wrapping_sub(1))
+            .copied()
+            .unwrap_or(0);
+        if max == 0 {
+            0.0
+        } else {
+            f64::from(n) / f64::from(max)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReviewPage {
+    pub reviews: Vec<Review>,
+    pub page: usize,
+    pub total_pages: usize,
+    pub summary: RatingSummary,
+    // The signed-in user's own review, even when it is hidden
+    pub own: Option<Review>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(score: u8) -> Review {
+        Review {
+            id: Review::document_id(1, "movies", 1),
+            user_id: 1,
+            username: "hanako".into(),
+            index: "movies".into(),
+            item_id: 1,
+            item_title: "千と千尋の神隠し".into(),
+            score,
+            body: " 何度見てもいい ".into(),
+            created_at: 0,
+            updated_at: 0,
+            status: ReviewStatus::Visible,
+            flags: vec![],
+            flag_count: 0,
+        }
+    }
+
+    #[test]
+    fn validate_checks_score_range() {
+        let mut r = review(10);
+        r.normalize();
+        assert_eq!(r.body, "何度見てもいい");
+        assert!(r.validate().is_ok());
+        assert!(review(0).validate().is_err());
+        assert!(review(11).validate().is_err());
+    }
+
+    #[test]
+    fn flags_hide_a_review_once_per_user() {
+        let mut r = review(1);
+        assert!(!r.add_flag(2, FlagReason::Spam, 0));
+        assert!(!r.add_flag(2, FlagReason::Offensive, 0));
+        assert_eq!(r.flag_count, 1);
+        assert!(!r.add_flag(3, FlagReason::Spam, 0));
+        assert!(r.add_flag(4, FlagReason::Spoiler, 0));
+        assert_eq!(r.status, ReviewStatus::Hidden);
+
+        r.moderate(ReviewStatus::Visible);
+        assert_eq!(r.status, ReviewStatus::Visible);
+        assert_eq!(r.flag_count, 0);
+        assert!(r.flags.is_empty());
+    }
+
+    #[test]
+    fn summary_from_score_distribution() {
+        let summary = RatingSummary::from_distribution([("8", 2), ("10", 1), ("x", 5), ("0", 1)]);
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.average, Some(8.67));
+        assert_eq!(summary.histogram[7], 2);
+        assert_eq!(summary.histogram[9], 1);
+        assert_eq!(summary.bar_width(8), 1.0);
+        assert_eq!(summary.bar_width(10), 0.5);
+        assert_eq!(summary.bar_width(1), 0.0);
+
+        let empty = RatingSummary::from_distribution([]);
+        assert_eq!(empty.average, None);
+        assert_eq!(empty.histogram.len(), 10);
+    }
+}
@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::date::rfc3339;
+use crate::model::search::SearchRequest;
+
+const NAME_MAX_CHARS: usize = 50;
+// New items listed in one notification; the count still covers all of them
+pub const NOTIFIED_ITEMS_MAX: usize = 20;
+
+// A search a user keeps under a name, re-run on a schedule by
+// `server::saved_searches`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SavedSearch {
+    // `{user_id}-{random hex}`
+    pub id: String,
+    pub user_id: i64,
+    pub name: String,
+    pub request: SearchRequest,
+    pub created_at: u64,
+    // `None` until the first scheduled run, which only records a baseline
+    #[serde(default)]
+    pub last_run_at: Option<u64>,
+    // Every document id that has matched so far
+    #[serde(default)]
+    pub seen_ids: Vec<i64>,
+    // Receives a JSON POST for each notification
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    // Secret in the Atom feed URL, since feed readers cannot send the session cookie
+    pub feed_token: String,
+}
+
+impl SavedSearch {
+    pub fn normalize(&mut self) {
+        self.name = self.name.trim().to_string();
+        self.webhook_url = self
+            .webhook_url
+            .take()
+            .map(|u| u.trim().to_string())
+            .filter(|u| !u.is_empty());
+        // A saved search is re-run from its first page
+        self.request.page = None;
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("Name is required".to_string());
+        }
+        if self.name.chars().count() > NAME_MAX_CHARS {
+            return Err(format!("Name must be at most {NAME_MAX_CHARS} characters"));
+        }
+        if self.request.index == "web" {
+            return Err("Web results cannot be saved".to_string());
+        }
+        if let Some(ref url) = self.webhook_url {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(format!("Webhook URL must be http(s): {url}"));
+            }
+        }
+        Ok(())
+    }
+
+    // Records the ids matching now and returns those never seen before. The
+    // first run only sets the baseline, so saving a search does not announce
+    // everything that already matches.
+    pub fn record_run(&mut self, matching: &[i64], now: u64) -> Vec<i64> {
+        let first_run = self.last_run_at.is_none();
+        self.last_run_at = Some(now);
+        let new: Vec<i64> = matching
+            .iter()
+            .copied()
+            .filter(|id| !self.seen_ids.contains(id))
+            .collect();
+        self.seen_ids.extend(&new);
+        self.seen_ids.sort_unstable();
+        if first_run {
+            vec![]
+        } else {
+            new
+        }
+    }
+
+    pub fn feed_path(&self) -> String {
+        format!("/feeds/saved/{}.atom?token={}", self.id, self.feed_token)
+    }
+
+    // "書籍・SF・★8以上" style summary of what the search filters on
+    pub fn summary(&self) -> String {
+        let r = &self.request;
+        let mut parts = Vec::new();
+        parts.push(
+            match r.index.as_str() {
+                "movies" => "映画",
+                "books" => "書籍",
+                "series" => "シリーズ",
+                other => other,
+            }
+            .to_string(),
+        );
+        if !r.query.is_empty() {
+            parts.push(format!("「{}」", r.query));
+        }
+        parts.extend(r.genres.iter().cloned());
+        parts.extend(r.tags.iter().map(|t| format!("#{t}")));
+        match (r.year_min, r.year_max) {
+            (Some(min), Some(max)) => parts.push(format!("{min}〜{max}年")),
+            (Some(min), None) => parts.push(format!("{min}年以降")),
+            (None, Some(max)) => parts.push(format!("{max}年以前")),
+            (None, None) => {}
+        }
+        if let Some(rating) = r.rating_min {
+            parts.push(format!("★{rating}以上"));
+        }
+        parts.join("・")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NotifiedItem {
+    pub id: i64,
+    pub title: String,
+}
+
+// New matches for one saved search, stored in the `notifications` index
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Notification {
+    // `{saved_search_id}-{created_at}`
+    pub id: String,
+    pub user_id: i64,
+    pub saved_search_id: String,
+    pub saved_search_name: String,
+    pub index: String,
+    pub new_count: usize,
+    // The first `NOTIFIED_ITEMS_MAX` of the new items
+    pub items: Vec<NotifiedItem>,
+    pub created_at: u64,
+    #[serde(default)]
+    pub read: bool,
+}
+
+impl Notification {
+    pub fn message(&self) -> String {
+        format!("保存した検索に{}件の新着", self.new_count)
+    }
+
+    pub fn item_url(&self, id: i64) -> String {
+        match self.index.as_str() {
+            "movies" => format!("/movie/{id}"),
+            "series" => format!("/series/{id}"),
+            _ => format!("/book/{id}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct NotificationFeed {
+    pub notifications: Vec<Notification>,
+    pub unread: usize,
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// An Atom document with one entry per notification; `base_url` has no trailing slash
+pub fn atom_feed(search: &SavedSearch, notifications: &[Notification], base_url: &str) -> String {
+    let updated = notifications
+        .iter()
+        .map(|n| n.created_at)
+        .max()
+        .unwrap_or(search.created_at);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <id>{base_url}/saved/{}</id>\n", search.id));
+    xml.push_str(&format!("  <title>{}</title>\n", escape_xml(&search.name)));
+    xml.push_str(&format!("  <subtitle>{}</subtitle>\n", escape_xml(&search.summary())));
+    xml.push_str(&format!("  <updated>{}</updated>\n", rfc3339(updated)));
+    for n in notifications {
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <id>{base_url}/notifications/{}</id>\n", n.id));
+        xml.push_str(&format!("    <title>{}</title>\n", escape_xml(&n.message())));
+        xml.push_str(&format!("    <updated>{}</updated>\n", rfc3339(n.created_at)));
+        if let Some(first) = n.items.first() {
+            xml.push_str(&format!(
+                "    <link href=\"{base_url}{}\"/>\n",
+                n.item_url(first.id)
+            ));
+        }
+        let titles: Vec<&str> = n.items.iter().map(|i| i.title.as_str()).collect();
+        xml.push_str(&format!(
+            "    <summary>{}</summary>\n",
+            escape_xml(&titles.join("、"))
+        ));
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved() -> SavedSearch {
+        SavedSearch {
+            id: "1-abc".into(),
+            user_id: 1,
+            name: " 新着SF ".into(),
+            request: SearchRequest {
+                index: "books".into(),
+                genres: vec!["SF".into()],
+                rating_min: Some(8.0),
+                page: Some(3),
+                ..Default::default()
+            },
+            created_at: 0,
+            last_run_at: None,
+            seen_ids: vec![],
+            webhook_url: Some("  ".into()),
+            feed_token: "f00d".into(),
+        }
+    }
+
+    #[test]
+    fn normalize_and_validate() {
+        let mut s = saved();
+        s.normalize();
+        assert_eq!(s.name, "新着SF");
+        assert_eq!(s.webhook_url, None);
+        assert_eq!(s.request.page, None);
+        assert!(s.validate().is_ok());
+        assert_eq!(s.summary(), "書籍・SF・★8以上");
+
+        s.webhook_url = Some("ftp://example.com".into());
+        assert!(s.validate().is_err());
+        s.webhook_url = None;
+        s.request.index = "web".into();
+        assert!(s.validate().is_err());
+    }
+
+    #[test]
+    fn first_run_is_a_baseline() {
+        let mut s = saved();
+        assert!(s.record_run(&[1, 2], 10).is_empty());
+        assert_eq!(s.last_run_at, Some(10));
+        assert_eq!(s.record_run(&[2, 3, 4], 20), vec![3, 4]);
+        // Items that drop out and match again are not new
+        assert!(s.record_run(&[1], 30).is_empty());
+        assert_eq!(s.seen_ids, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn atom_feed_escapes_and_dates() {
+        let s = SavedSearch {
+            name: "SF & ファンタジー".into(),
+            ..saved()
+        };
+        let n = Notification {
+            id: "1-abc-86400".into(),
+            user_id: 1,
+            saved_search_id: s.id.clone(),
+            saved_search_name: s.name.clone(),
+            index: "books".into(),
+            new_count: 3,
+            items: vec![NotifiedItem {
+                id: 7,
+                title: "<三体>".into(),
+            }],
+            created_at: 86_400 + 3661,
+            read: false,
+        };
+        let xml = atom_feed(&s, std::slice::from_ref(&n), "http://localhost:3000");
+        assert!(xml.contains("<title>SF &amp; ファンタジー</title>"));
+        assert!(xml.contains("<title>保存した検索に3件の新着</title>"));
+        assert!(xml.contains("<updated>1970-01-02T01:01:01Z</updated>"));
+        assert!(xml.contains("<link href=\"http://localhost:3000/book/7\"/>"));
+        assert!(xml.contains("<summary>&lt;三体&gt;</summary>"));
+    }
+}
@@ -1,16 +1,49 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+use crate::model::book::Book;
+use crate::model::collection::{Collection, SeriesPosition};
+use crate::model::movie::Movie;
+use crate::model::series::Series;
+
+// Everything that makes up a search on the home page, also what a saved
+// search stores
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct SearchRequest {
     pub query: String,
     pub index: String,
     #[serde(default)]
     pub genres: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub year_min: Option<i32>,
     pub year_max: Option<i32>,
     pub rating_min: Option<f64>,
     pub page: Option<usize>,
     pub sort: Option<String>,
+    // 0.0 is pure keyword search, 1.0 pure semantic search
+    #[serde(default)]
+    pub semantic_ratio: Option<f32>,
+    #[serde(default)]
+    pub movie_filters: MovieFilters,
+    #[serde(default)]
+    pub book_filters: BookFilters,
+    #[serde(default)]
+    pub series_filters: SeriesFilters,
+}
+
+impl SearchRequest {
+    // Neither a query nor any filter, i.e. nothing worth running or saving
+    pub fn is_blank(&self) -> bool {
+        self.query.trim().is_empty()
+            && self.genres.is_empty()
+            && self.tags.is_empty()
+            && self.year_min.is_none()
+            && self.year_max.is_none()
+            && self.rating_min.is_none()
+            && self.movie_filters == MovieFilters::default()
+            && self.book_filters == BookFilters::default()
+            && self.series_filters == SeriesFilters::default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -20,6 +53,17 @@ pub struct SearchResponse {
     pub page: usize,
     pub total_pages: usize,
     pub processing_time_ms: usize,
+    #[serde(default)]
+    pub web_hits: Vec<SearchHit>,
+    // Alternative queries ("もしかして") offered when there are few hits
+    #[serde(default)]
+    pub suggestions: Vec<String>,
+    // Collections matching the query, shown above the first page
+    #[serde(default)]
+    pub collections: Vec<Collection>,
+    // Analytics event id, passed on to the detail page to log clicks
+    #[serde(default)]
+    pub search_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,12 +73,94 @@ pub struct SearchHit {
     pub title_en: Option<String>,
     pub description: String,
     pub creator: String,
+    #[serde(default)]
+    pub creator_id: Option<i64>,
     pub year: i32,
     pub genres: Vec<String>,
     pub rating: f64,
     pub image_url: Option<String>,
     pub language: String,
     pub index: String,
+    #[serde(default)]
+    pub series: Option<SeriesPosition>,
+    #[serde(default)]
+    pub user_rating: Option<f64>,
+    #[serde(default)]
+    pub user_rating_count: u32,
+}
+
+impl From<Movie> for SearchHit {
+    fn from(m: Movie) -> Self {
+        SearchHit {
+            id: m.id,
+            title: m.title,
+            title_en: m.title_en,
+            description: m.description,
+            creator: m.director,
+            creator_id: m.director_id,
+            year: m.year,
+            genres: m.genres,
+            rating: m.rating,
+            image_url: m.poster_url,
+            language: m.language,
+            index: "movies".to_string(),
+            series: None,
+            user_rating: m.user_rating,
+            user_rating_count: m.user_rating_count,
+        }
+    }
+}
+
+impl From<Book> for SearchHit {
+    fn from(b: Book) -> Self {
+        SearchHit {
+            id: b.id,
+            title: b.title,
+            title_en: b.title_en,
+            description: b.description,
+            creator: b.author,
+            creator_id: b.author_id,
+            year: b.year,
+            genres: b.genres,
+            rating: b.rating,
+            image_url: b.cover_url,
+            language: b.language,
+            index: "books".to_string(),
+            series: None,
+            user_rating: b.user_rating,
+            user_rating_count: b.user_rating_count,
+        }
+    }
+}
+
+impl From<Series> for SearchHit {
+    fn from(s: Series) -> Self {
+        SearchHit {
+            id: s.id,
+            title: s.title,
+            title_en: s.title_en,
+            description: s.description,
+            creator: s.studio.or(s.network).unwrap_or_default(),
+            creator_id: None,
+            year: s.year,
+            genres: s.genres,
+            rating: s.rating,
+            image_url: s.poster_url,
+            language: s.language,
+            index: "series".to_string(),
+            series: None,
+            user_rating: s.user_rating,
+            user_rating_count: s.user_rating_count,
+        }
+    }
+}
+
+// A related item with the score it was ranked by and the reasons behind it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarItem {
+    pub hit: SearchHit,
+    pub score: f64,
+    pub reasons: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -42,6 +168,40 @@ pub struct FacetInfo {
     pub genres: Vec<FacetValue>,
     pub years: Vec<FacetValue>,
     pub languages: Vec<FacetValue>,
+    #[serde(default)]
+    pub countries: Vec<FacetValue>,
+    #[serde(default)]
+    pub certifications: Vec<FacetValue>,
+    #[serde(default)]
+    pub publishers: Vec<FacetValue>,
+    #[serde(default)]
+    pub formats: Vec<FacetValue>,
+    #[serde(default)]
+    pub statuses: Vec<FacetValue>,
+    #[serde(default)]
+    pub tags: Vec<FacetValue>,
+}
+
+// Filters on movie-only attributes, set from the facet panel
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct MovieFilters {
+    pub runtime_min: Option<u32>,
+    pub runtime_max: Option<u32>,
+    pub countries: Vec<String>,
+    pub certifications: Vec<String>,
+}
+
+// Filters on book-only attributes, set from the facet panel
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct BookFilters {
+    pub publishers: Vec<String>,
+    pub formats: Vec<String>,
+}
+
+// Filters on series-only attributes, set from the facet panel
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SeriesFilters {
+    pub statuses: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,3 +209,33 @@ pub struct FacetValue {
     pub value: String,
     pub count: usize,
 }
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SuggestionKind {
+    Title,
+    TitleEn,
+    Creator,
+    Genre,
+    // A query other users ran successfully, from the search analytics
+    Popular,
+}
+
+impl SuggestionKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SuggestionKind::Title => "タイトル",
+            SuggestionKind::TitleEn => "英題",
+            SuggestionKind::Creator => "人物",
+            SuggestionKind::Genre => "ジャンル",
+            SuggestionKind::Popular => "人気の検索",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    pub value: String,
+    #[serde(default)]
+    pub count: Option<usize>,
+}
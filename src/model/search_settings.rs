@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchSettings {
+    pub id: String,
+    pub index: String,
+    pub version: u32,
+    // Each group is a set of interchangeable terms, e.g. ["SF", "サイエンスフィクション"]
+    #[serde(default)]
+    pub synonyms: Vec<Vec<String>>,
+    #[serde(default)]
+    pub stop_words: Vec<String>,
+    #[serde(default)]
+    pub typo_tolerance: TypoTolerance,
+    #[serde(default)]
+    pub note: String,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TypoTolerance {
+    pub enabled: bool,
+    pub one_typo_min: u8,
+    pub two_typos_min: u8,
+    #[serde(default)]
+    pub disabled_attributes: Vec<String>,
+    #[serde(default)]
+    pub disabled_words: Vec<String>,
+}
+
+impl Default for TypoTolerance {
+    // Meilisearch's own defaults
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            one_typo_min: 5,
+            two_typos_min: 9,
+            disabled_attributes: vec![],
+            disabled_words: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct QueryAnalysis {
+    pub stop_words: Vec<String>,
+    pub expansions: Vec<(String, Vec<String>)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SettingsPreview {
+    pub analysis: QueryAnalysis,
+    pub titles: Vec<String>,
+    pub total_hits: usize,
+}
+
+impl SearchSettings {
+    pub fn new(index: &str) -> Self {
+        Self {
+            id: String::new(),
+            index: index.to_string(),
+            version: 0,
+            synonyms: vec![],
+            stop_words: vec![],
+            typo_tolerance: TypoTolerance::default(),
+            note: String::new(),
+            updated_at: 0,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !matches!(self.index.as_str(), "movies" | "books" | "series" | "web") {
+            return Err(format!("Unknown index: {}", self.index));
+        }
+        let typo = &self.typo_tolerance;
+        if typo.one_typo_min > typo.two_typos_min {
+            return Err("one_typo_min must not exceed two_typos_min".to_string());
+        }
+        Ok(())
+    }
+
+    // Expands synonym groups into Meilisearch's multi-way synonym map
+    pub fn synonym_map(&self) -> HashMap<String, Vec<String>> {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for group in &self.synonyms {
+            for term in group {
+                let others = group.iter().filter(|t| *t != term).cloned();
+                let entry = map.entry(term.clone()).or_default();
+                for other in others {
+                    if !entry.contains(&other) {
+                        entry.push(other);
+                    }
+                }
+            }
+        }
+        map.retain(|_, v| !v.is_empty());
+        map
+    }
+
+    // The query plus one copy per synonym it contains, with that term swapped
+    // for each of its alternatives
+    pub fn variants(&self, query: &str) -> Vec<String> {
+        let map = self.synonym_map();
+        let lowered = query.to_lowercase();
+        let mut variants = vec![query.to_string()];
+        let mut terms: Vec<&String> = map.keys().collect();
+        terms.sort();
+        for term in terms {
+            let term_lowered = term.to_lowercase();
+            if !lowered.contains(&term_lowered) {
+                continue;
+            }
+            for other in &map[term] {
+                let variant = lowered.replace(&term_lowered, other);
+                if !variants.contains(&variant) {
+                    variants.push(variant);
+                }
+            }
+        }
+        variants
+    }
+
+    pub fn analyze(&self, query: &str) -> QueryAnalysis {
+        let map = self.synonym_map();
+        let lowered = query.to_lowercase();
+        let mut analysis = QueryAnalysis::default();
+
+        for word in query.split_whitespace() {
+            if self.stop_words.iter().any(|s| s.eq_ignore_ascii_case(word)) {
+                analysis.stop_words.push(word.to_string());
+            }
+        }
+
+        let mut terms: Vec<&String> = map.keys().collect();
+        terms.sort();
+        for term in terms {
+            if lowered.contains(&term.to_lowercase()) {
+                analysis.expansions.push((term.clone(), map[term].clone()));
+            }
+        }
+
+        analysis
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(synonyms: &[&[&str]], stop_words: &[&str]) -> SearchSettings {
+        SearchSettings {
+            synonyms: synonyms
+                .iter()
+                .map(|group| group.iter().map(|t| t.to_string()).collect())
+                .collect(),
+            stop_words: stop_words.iter().map(|w| w.to_string()).collect(),
+            ..SearchSettings::new("movies")
+        }
+    }
+
+    #[test]
+    fn synonym_groups_expand_both_ways() {
+        let map = settings(&[&["SF", "サイエンスフィクション", "SF"], &["solo"]], &[]).synonym_map();
+        assert_eq!(map["SF"], vec!["サイエンスフィクション"]);
+        assert_eq!(map["サイエンスフィクション"], vec!["SF"]);
+        // A group of one has nothing to swap with
+        assert!(!map.contains_key("solo"));
+    }
+
+    #[test]
+    fn validation_checks_index_and_typo_sizes() {
+        assert!(SearchSettings::new("movies").validate().is_ok());
+        assert!(SearchSettings::new("users").validate().is_err());
+
+        let mut settings = SearchSettings::new("books");
+        settings.typo_tolerance.one_typo_min = 10;
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn analysis_lists_stop_words_and_expansions() {
+        let analysis = settings(&[&["SF", "サイエンスフィクション"]], &["the", "の"])
+            .analyze("The sf の 映画");
+        assert_eq!(analysis.stop_words, vec!["The", "の"]);
+        assert_eq!(
+            analysis.expansions,
+            vec![("SF".to_string(), vec!["サイエンスフィクション".to_string()])]
+        );
+    }
+
+    #[test]
+    fn variants_swap_in_each_synonym() {
+        let settings = settings(&[&["sf", "space opera"]], &[]);
+        assert_eq!(settings.variants("SF 映画"), vec!["SF 映画", "space opera 映画"]);
+        assert_eq!(settings.variants("ホラー"), vec!["ホラー"]);
+    }
+}
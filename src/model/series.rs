@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::reading::Readings;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SeriesStatus {
+    Ongoing,
+    Finished,
+}
+
+impl SeriesStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SeriesStatus::Ongoing => "放送中",
+            SeriesStatus::Finished => "完結",
+        }
+    }
+
+    // Facet values arrive as the serialised name
+    pub fn label_of(value: &str) -> &str {
+        match value {
+            "ongoing" => SeriesStatus::Ongoing.label(),
+            "finished" => SeriesStatus::Finished.label(),
+            _ => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Season {
+    pub number: u32,
+    #[serde(default)]
+    pub title: Option<String>,
+    pub episodes: u32,
+    pub year: i32,
+}
+
+// A TV series or anime, broadcast over one or more seasons
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Series {
+    pub id: i64,
+    pub title: String,
+    #[serde(default)]
+    pub title_en: Option<String>,
+    #[serde(default)]
+    pub title_reading: Option<Readings>,
+    pub description: String,
+    // Animation studio or production company
+    #[serde(default)]
+    pub studio: Option<String>,
+    // Original broadcaster or streaming service
+    #[serde(default)]
+    pub network: Option<String>,
+    // First broadcast year; `end_year` stays empty while the series is ongoing
+    pub year: i32,
+    #[serde(default)]
+    pub end_year: Option<i32>,
+    pub status: SeriesStatus,
+    #[serde(default)]
+    pub seasons: Vec<Season>,
+    // Sum over `seasons`, kept on the document so it can be sorted on
+    #[serde(default)]
+    pub episode_count: u32,
+    pub genres: Vec<String>,
+    pub rating: f64,
+    #[serde(default)]
+    pub poster_url: Option<String>,
+    pub language: String,
+    // Average of visible user reviews, written back by `server::reviews`
+    #[serde(default)]
+    pub user_rating: Option<f64>,
+    #[serde(default)]
+    pub user_rating_count: u32,
+    // Tags enough users agree on, written back by `server::tags`
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl Series {
+    pub fn normalize(&mut self) {
+        self.seasons.sort_by_key(|s| s.number);
+        if !self.seasons.is_empty() {
+            self.episode_count = self.seasons.iter().map(|s| s.episodes).sum();
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match (self.status, self.end_year) {
+            (SeriesStatus::Finished, None) => {
+                return Err("A finished series needs an end year".to_string())
+            }
+            (SeriesStatus::Ongoing, Some(_)) => {
+                return Err("An ongoing series has no end year".to_string())
+            }
+            (_, Some(end)) if end < self.year => {
+                return Err(format!("End year {end} is before {}", self.year))
+            }
+            _ => {}
+        }
+        if let Some(s) = self
+            .seasons
+            .windows(2)
+            .find(|pair| pair[0].number == pair[1].number)
+        {
+            return Err(format!("Duplicate season {}", s[0].number));
+        }
+        if let Some(s) = self.seasons.iter().find(|s| s.episodes == 0) {
+            return Err(format!("Season {} has no episodes", s.number));
+        }
+        Ok(())
+    }
+
+    pub fn broadcast_years(&self) -> String {
+        match self.end_year {
+            Some(end) if end == self.year => self.year.to_string(),
+            Some(end) => format!("{}–{end}", self.year),
+            None => format!("{}–", self.year),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(year: i32, end_year: Option<i32>) -> Series {
+        Series {
+            id: 1,
+            title: "進撃の巨人".into(),
+            title_en: None,
+            title_reading: None,
+            description: String::new(),
+            studio: None,
+            network: None,
+            year,
+            end_year,
+            status: if end_year.is_some() {
+                SeriesStatus::Finished
+            } else {
+                SeriesStatus::Ongoing
+            },
+            seasons: vec![
+                Season {
+                    number: 2,
+                    title: None,
+                    episodes: 12,
+                    year: 2017,
+                },
+                Season {
+                    number: 1,
+                    title: None,
+                    episodes: 25,
+                    year: 2013,
+                },
+            ],
+            episode_count: 0,
+            genres: vec![],
+            rating: 9.0,
+            poster_url: None,
+            language: "ja".into(),
+            user_rating: None,
+            user_rating_count: 0,
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn normalize_orders_seasons_and_counts_episodes() {
+        let mut s = series(2013, Some(2023));
+        s.normalize();
+        assert_eq!(s.seasons[0].number, 1);
+        assert_eq!(s.episode_count, 37);
+        assert!(s.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_checks_status_against_end_year() {
+        let mut s = series(2013, Some(2023));
+        s.status = SeriesStatus::Ongoing;
+        assert!(s.validate().is_err());
+        let s = series(2013, Some(2010));
+        assert!(s.validate().unwrap_err().starts_with("End year"));
+        let mut s = series(2013, None);
+        s.seasons[0].number = 1;
+        s.normalize();
+        assert_eq!(s.validate().unwrap_err(), "Duplicate season 1");
+    }
+
+    #[test]
+    fn broadcast_years_show_open_periods() {
+        let finished = series(2013, Some(2023));
+        let ongoing = series(2019, None);
+        assert_eq!(ongoing.broadcast_years(), "2019–");
+        assert_eq!(finished.broadcast_years(), "2013–2023");
+    }
+}
@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::search::FacetValue;
+
+const TAG_MAX_CHARS: usize = 30;
+pub const TAGS_PER_ITEM: usize = 10;
+// A tag is copied onto the catalogue document once this many users agree on it
+pub const POPULAR_TAG_MIN_USERS: usize = 2;
+pub const POPULAR_TAGS_PER_ITEM: usize = 10;
+pub const CLOUD_WEIGHTS: u8 = 5;
+
+// Case and whitespace folding. Width folding (full-width ASCII, half-width
+// kana) needs NFKC and is applied on the server before this.
+pub fn fold_tag(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+// Splits "泣ける, 週末向け、原作あり" as typed into the tag editor
+pub fn split_tags(text: &str) -> Vec<String> {
+    text.split([',', '、', '，'])
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TagAlias {
+    pub alias: String,
+    pub tag: String,
+}
+
+// Admin-maintained synonyms and bans, stored as a single document
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TagRules {
+    pub id: String,
+    #[serde(default)]
+    pub aliases: Vec<TagAlias>,
+    #[serde(default)]
+    pub banned: Vec<String>,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+impl Default for TagRules {
+    fn default() -> Self {
+        TagRules {
+            id: "rules".to_string(),
+            aliases: vec![],
+            banned: vec![],
+            updated_at: 0,
+        }
+    }
+}
+
+impl TagRules {
+    // Follows aliases to the tag they were merged into
+    pub fn resolve(&self, tag: &str) -> String {
+        let mut current = tag.to_string();
+        // Bounded so a hand-edited cycle cannot loop forever
+        for _ in 0..=self.aliases.len() {
+            match self.aliases.iter().find(|a| a.alias == current) {
+                Some(a) => current = a.tag.clone(),
+                None => break,
+            }
+        }
+        current
+    }
+
+    pub fn is_banned(&self, tag: &str) -> bool {
+        self.banned.iter().any(|b| b == tag)
+    }
+
+    // The stored form of a width-folded tag, or `None` for empty and banned tags
+    pub fn canonical(&self, text: &str) -> Option<String> {
+        let folded = fold_tag(text);
+        if folded.is_empty() {
+            return None;
+        }
+        let tag = self.resolve(&folded);
+        (!self.is_banned(&tag)).then_some(tag)
+    }
+
+    // Canonical tags for one user's list, deduplicated in input order
+    pub fn canonical_list(&self, texts: &[String]) -> Result<Vec<String>, String> {
+        let mut tags: Vec<String> = Vec::new();
+        for text in texts {
+            if text.trim().chars().count() > TAG_MAX_CHARS {
+                return Err(format!("Tags must be at most {TAG_MAX_CHARS} characters"));
+            }
+            if let Some(tag) = self.canonical(text) {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        }
+        if tags.len() > TAGS_PER_ITEM {
+            return Err(format!("At most {TAGS_PER_ITEM} tags per item"));
+        }
+        Ok(tags)
+    }
+
+    // Makes `from` a synonym of `to`; earlier aliases of `from` follow it
+    pub fn merge(&mut self, from: &str, to: &str) -> Result<(String, String), String> {
+        let from = fold_tag(from);
+        let to = self.resolve(&fold_tag(to));
+        if from.is_empty() || to.is_empty() {
+            return Err("Tag is empty".to_string());
+        }
+        if from == to {
+            return Err(format!("Cannot merge a tag into itself: {from}"));
+        }
+        self.aliases.retain(|a| a.alias != from);
+        for alias in self.aliases.iter_mut().filter(|a| a.tag == from) {
+            alias.tag = to.clone();
+        }
+        self.aliases.push(TagAlias {
+            alias: from.clone(),
+            tag: to.clone(),
+        });
+        Ok((from, to))
+    }
+
+    pub fn ban(&mut self, tag: &str) -> String {
+        let tag = fold_tag(tag);
+        if !tag.is_empty() && !self.is_banned(&tag) {
+            self.banned.push(tag.clone());
+        }
+        tag
+    }
+
+    pub fn unban(&mut self, tag: &str) {
+        let tag = fold_tag(tag);
+        self.banned.retain(|b| *b != tag);
+    }
+}
+
+// One user's tags on one item, stored in the `user_tags` index
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UserTags {
+    // `{user_id}-{index}-{item_id}`, like watchlist entries and reviews
+    pub id: String,
+    pub user_id: i64,
+    pub index: String,
+    pub item_id: i64,
+    pub tags: Vec<String>,
+    pub updated_at: u64,
+}
+
+impl UserTags {
+    pub fn document_id(user_id: i64, index: &str, item_id: i64) -> String {
+        format!("{user_id}-{index}-{item_id}")
+    }
+}
+
+// Tags shared by enough users, most agreed on first
+pub fn popular_tags(counts: &[FacetValue]) -> Vec<String> {
+    let mut popular: Vec<&FacetValue> = counts
+        .iter()
+        .filter(|fv| fv.count >= POPULAR_TAG_MIN_USERS)
+        .collect();
+    popular.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    popular
+        .into_iter()
+        .take(POPULAR_TAGS_PER_ITEM)
+        .map(|fv| fv.value.clone())
+        .collect()
+}
+
+// Size class 1..=CLOUD_WEIGHTS for a tag cloud entry
+pub fn cloud_weight(count: usize, max: usize) -> u8 {
+    if max == 0 {
+        return 1;
+    }
+    let steps = usize::from(CLOUD_WEIGHTS - 1);
+    1 + (count.min(max) * steps).div_ceil(max) as u8
+}
+
+// Every tag on an item with the number of users who added it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemTags {
+    pub tags: Vec<FacetValue>,
+    // The signed-in user's own tags
+    pub own: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagOverview {
+    // Usage across all items, by number of user lists
+    pub tags: Vec<FacetValue>,
+    pub rules: TagRules,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fv(value: &str, count: usize) -> FacetValue {
+        FacetValue {
+            value: value.to_string(),
+            count,
+        }
+    }
+
+    #[test]
+    fn canonical_folds_case_and_synonyms() {
+        let mut rules = TagRules::default();
+        rules.merge("泣ける映画", "泣ける").unwrap();
+        rules.merge("Tearjerker", "泣ける映画").unwrap();
+        assert_eq!(rules.canonical("  泣ける映画 ").as_deref(), Some("泣ける"));
+        assert_eq!(rules.canonical("TEARJERKER").as_deref(), Some("泣ける"));
+        assert_eq!(rules.canonical("Feel  Good").as_deref(), Some("feel good"));
+        assert_eq!(rules.canonical("  "), None);
+
+        rules.ban("ネタバレ");
+        assert_eq!(rules.canonical("ネタバレ"), None);
+        rules.unban("ネタバレ");
+        assert_eq!(rules.canonical("ネタバレ").as_deref(), Some("ネタバレ"));
+    }
+
+    #[test]
+    fn merge_retargets_earlier_aliases() {
+        let mut rules = TagRules::default();
+        rules.merge("a", "b").unwrap();
+        rules.merge("b", "c").unwrap();
+        assert_eq!(rules.resolve("a"), "c");
+        assert!(rules.aliases.iter().all(|a| a.tag == "c"));
+        // `c` now absorbs `b`, so merging back would create a cycle
+        assert!(rules.merge("c", "b").is_err());
+        assert!(rules.merge("x", "X").is_err());
+    }
+
+    #[test]
+    fn split_accepts_japanese_commas() {
+        assert_eq!(split_tags("泣ける, 週末向け、原作あり，"), vec!["泣ける", "週末向け", "原作あり"]);
+        assert!(split_tags(" , ").is_empty());
+    }
+
+    #[test]
+    fn canonical_list_dedups_and_limits() {
+        let rules = TagRules::default();
+        let tags = rules
+            .canonical_list(&["週末向け".into(), "SF".into(), "sf".into(), "".into()])
+            .unwrap();
+        assert_eq!(tags, vec!["週末向け", "sf"]);
+
+        let too_many: Vec<String> = (0..=TAGS_PER_ITEM).map(|i| format!("tag{i}")).collect();
+        assert!(rules.canonical_list(&too_many).is_err());
+        assert!(rules.canonical_list(&["長".repeat(31)]).is_err());
+    }
+
+    #[test]
+    fn popular_tags_need_agreement() {
+        let counts = [fv("原作あり", 2), fv("泣ける", 5), fv("一人だけ", 1), fv("週末向け", 2)];
+        assert_eq!(popular_tags(&counts), vec!["泣ける", "原作あり", "週末向け"]);
+    }
+
+    #[test]
+    fn cloud_weights_scale_to_the_largest_tag() {
+        assert_eq!(cloud_weight(10, 10), CLOUD_WEIGHTS);
+        assert_eq!(cloud_weight(1, 10), 2);
+        assert_eq!(cloud_weight(0, 10), 1);
+        assert_eq!(cloud_weight(3, 0), 1);
+    }
+}
@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::search::{FacetValue, SearchHit};
+
+pub const EXPORT_VERSION: u32 = 1;
+// As many as a list shows, and a bound on the lookups one import makes
+pub const IMPORT_MAX_ENTRIES: usize = 1000;
+const USERNAME_MAX: usize = 32;
+const PASSWORD_MIN: usize = 8;
+
+// The public side of an account; the password hash never leaves the server
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    // From `users.admins` in the server config, never stored with the account
+    #[serde(default)]
+    pub is_admin: bool,
+}
+
+pub fn validate_username(username: &str) -> Result<(), String> {
+    if username.is_empty() || username.chars().count() > USERNAME_MAX {
+        return Err(format!("Username must be 1 to {USERNAME_MAX} characters"));
+    }
+    if !username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err("Username may only contain letters, digits, '_' and '-'".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < PASSWORD_MIN {
+        return Err(format!(
+            "Password must be at least {PASSWORD_MIN} characters"
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ListStatus {
+    Want,
+    Done,
+}
+
+impl ListStatus {
+    // The serialised name, as stored in the filterable `status` attribute
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListStatus::Want => "want",
+            ListStatus::Done => "done",
+        }
+    }
+
+    // Books are read, everything else is watched
+    pub fn label(&self, index: &str) -> &'static str {
+        match (self, index) {
+            (ListStatus::Want, "books") => "読みたい",
+            (ListStatus::Done, "books") => "読んだ",
+            (ListStatus::Want, _) => "見たい",
+            (ListStatus::Done, _) => "見た",
+        }
+    }
+}
+
+pub fn is_listable(index: &str) -> bool {
+    matches!(index, "movies" | "books" | "series")
+}
+
+// One item on a user's list, stored in the `watchlist` index
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WatchlistEntry {
+    // `{user_id}-{index}-{item_id}`, so re-adding an item replaces it
+    pub id: String,
+    pub user_id: i64,
+    pub index: String,
+    pub item_id: i64,
+    pub status: ListStatus,
+    // Unix seconds
+    pub added_at: u64,
+    // Copied from the item so the list can be searched and filtered on its own
+    pub title: String,
+    #[serde(default)]
+    pub title_en: Option<String>,
+    pub creator: String,
+    pub year: i32,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    pub rating: f64,
+}
+
+impl WatchlistEntry {
+    pub fn document_id(user_id: i64, index: &str, item_id: i64) -> String {
+        format!("{user_id}-{index}-{item_id}")
+    }
+
+    pub fn new(user_id: i64, hit: SearchHit, status: ListStatus, added_at: u64) -> Self {
+        WatchlistEntry {
+            id: Self::document_id(user_id, &hit.index, hit.id),
+            user_id,
+            index: hit.index,
+            item_id: hit.id,
+            status,
+            added_at,
+            title: hit.title,
+            title_en: hit.title_en,
+            creator: hit.creator,
+            year: hit.year,
+            genres: hit.genres,
+            rating: hit.rating,
+        }
+    }
+
+    // `movies:12`, the same form collections use for their members
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.index, self.item_id)
+    }
+
+    pub fn detail_url(&self) -> String {
+        match self.index.as_str() {
+            "movies" => format!("/movie/{}", self.item_id),
+            "series" => format!("/series/{}", self.item_id),
+            _ => format!("/book/{}", self.item_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchlistResponse {
+    pub entries: Vec<WatchlistEntry>,
+    // Genre counts over the user's whole list, not just the filtered page
+    pub genres: Vec<FacetValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExportedEntry {
+    pub index: String,
+    pub id: i64,
+    pub status: ListStatus,
+    #[serde(default)]
+    pub added_at: u64,
+}
+
+// The JSON file users download and import. Only references are kept; titles
+// and the rest are looked up again on import.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WatchlistExport {
+    pub version: u32,
+    #[serde(default)]
+    pub entries: Vec<ExportedEntry>,
+}
+
+impl WatchlistExport {
+    pub fn from_entries(entries: &[WatchlistEntry]) -> Self {
+        WatchlistExport {
+            version: EXPORT_VERSION,
+            entries: entries
+                .iter()
+                .map(|e| ExportedEntry {
+                    index: e.index.clone(),
+                    id: e.item_id,
+                    status: e.status,
+                    added_at: e.added_at,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != EXPORT_VERSION {
+            return Err(format!("Unsupported export version: {}", self.version));
+        }
+        if self.entries.len() > IMPORT_MAX_ENTRIES {
+            return Err(format!(
+                "An import may have at most {IMPORT_MAX_ENTRIES} entries, got {}",
+                self.entries.len()
+            ));
+        }
+        if let Some(e) = self.entries.iter().find(|e| !is_listable(&e.index)) {
+            return Err(format!("Unknown index: {}", e.index));
+        }
+        if let Some((i, e)) = self.entries.iter().enumerate().find(|(i, e)| {
+            self.entries[..*i]
+                .iter()
+                .any(|p| p.index == e.index && p.id == e.id)
+        }) {
+            return Err(format!(
+                "Duplicate entry at position {}: {}:{}",
+                i + 1,
+                e.index,
+                e.id
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportResult {
+    pub imported: usize,
+    // Entries whose item no longer exists in the catalogue
+    pub skipped: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exported(index: &str, id: i64) -> ExportedEntry {
+        ExportedEntry {
+            index: index.into(),
+            id,
+            status: ListStatus::Want,
+            added_at: 0,
+        }
+    }
+
+    #[test]
+    fn labels_depend_on_the_kind_of_item() {
+        assert_eq!(ListStatus::Want.label("movies"), "見たい");
+        assert_eq!(ListStatus::Done.label("series"), "見た");
+        assert_eq!(ListStatus::Want.label("books"), "読みたい");
+        assert_eq!(ListStatus::Done.label("books"), "読んだ");
+    }
+
+    #[test]
+    fn usernames_and_passwords_are_checked() {
+        assert!(validate_username("hanako_01").is_ok());
+        assert!(validate_username("").is_err());
+        assert!(validate_username("花子").is_err());
+        assert!(validate_username(&"a".repeat(33)).is_err());
+        assert!(validate_password("short").is_err());
+        assert!(validate_password("long enough").is_ok());
+    }
+
+    #[test]
+    fn export_rejects_unknown_indexes_and_duplicates() {
+        let mut export = WatchlistExport {
+            version: EXPORT_VERSION,
+            entries: vec![exported("movies", 1), exported("books", 1)],
+        };
+        assert!(export.validate().is_ok());
+
+        export.entries.push(exported("movies", 1));
+        assert_eq!(
+            export.validate().unwrap_err(),
+            "Duplicate entry at position 3: movies:1"
+        );
+
+        export.entries = vec![exported("web", 4)];
+        assert_eq!(export.validate().unwrap_err(), "Unknown index: web");
+
+        export.entries = (0..=IMPORT_MAX_ENTRIES as i64)
+            .map(|id| exported("movies", id))
+            .collect();
+        assert!(export.validate().unwrap_err().starts_with("An import may have at most"));
+
+        export.entries.clear();
+        export.version = 2;
+        assert!(export.validate().is_err());
+    }
+}
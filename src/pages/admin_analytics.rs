@@ -0,0 +1,169 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::api::{get_analytics_report, get_search_settings, save_search_settings};
+use crate::model::analytics::Reformulation;
+use crate::model::search::FacetValue;
+
+fn query_list(values: Vec<FacetValue>, empty: &'static str) -> impl IntoView {
+    view! {
+        {values.is_empty().then(|| view! { <p class="watchlist-empty">{empty}</p> })}
+        <ul class="admin-list">
+            {values.into_iter().map(|fv| view! {
+                <li>
+                    <span>{fv.value}</span>
+                    <span>{format!("{} 回", fv.count)}</span>
+                </li>
+            }).collect_view()}
+        </ul>
+    }
+}
+
+#[component]
+pub fn AnalyticsAdminPage() -> impl IntoView {
+    let (days, set_days) = signal(7u64);
+    let (message, set_message) = signal(Option::<String>::None);
+
+    let report = Resource::new(move || days.get(), |days| async move {
+        get_analytics_report(days).await
+    });
+
+    // Adds a rewrite users made as a synonym group of the index's search settings
+    let add_synonym = move |candidate: Reformulation| {
+        spawn_local(async move {
+            let result = async {
+                let mut settings = get_search_settings(candidate.index.clone()).await?;
+                let pair = [candidate.from.clone(), candidate.to.clone()];
+                if settings
+                    .synonyms
+                    .iter()
+                    .any(|group| pair.iter().all(|w| group.contains(w)))
+                {
+                    return Ok(false);
+                }
+                settings.synonyms.push(pair.to_vec());
+                save_search_settings(settings).await.map(|_| true)
+            }
+            .await;
+            set_message.set(Some(match result {
+                Ok(true) => format!("「{}」と「{}」を同義語に追加しました", candidate.from, candidate.to),
+                Ok(false) => format!("「{}」と「{}」は既に同義語です", candidate.from, candidate.to),
+                Err(e) => format!("エラー: {e}"),
+            }));
+        });
+    };
+
+    view! {
+        <div class="admin-page">
+            <h1 class="admin-title">"検索分析"</h1>
+
+            {move || message.get().map(|msg| view! {
+                <div class="seed-message">{msg}</div>
+            })}
+
+            <div class="index-toggle">
+                {[(1u64, "24時間"), (7, "7日"), (30, "30日"), (90, "90日")].into_iter().map(|(value, label)| view! {
+                    <button
+                        class=move || if days.get() == value { "toggle-btn active" } else { "toggle-btn" }
+                        on:click=move |_| set_days.set(value)
+                    >
+                        {label}
+                    </button>
+                }).collect_view()}
+            </div>
+
+            <Suspense fallback=move || view! { <div class="loading">"読み込み中..."</div> }>
+                {move || report.get().map(|result| match result {
+                    Err(e) => view! {
+                        <div class="error">"エラー: "{e.to_string()}</div>
+                    }.into_any(),
+                    Ok(report) => view! {
+                        <div class="admin-card">
+                            <h2>"概要"</h2>
+                            <ul class="admin-list">
+                                <li><span>"検索数 (1ページ目)"</span><span>{report.searches}</span></li>
+                                <li><span>"結果のクリック数"</span><span>{report.clicks}</span></li>
+                            </ul>
+                            <p class="admin-hint">
+                                {if report.retention_days == 0 {
+                                    "記録は無効です (ANALYTICS_RETENTION_DAYS=0)".to_string()
+                                } else {
+                                    format!("IP アドレスは末尾を伏せて記録し、{} 日後に削除します", report.retention_days)
+                                }}
+                            </p>
+                        </div>
+
+                        <div class="analytics-grid">
+                            <div class="admin-card">
+                                <h2>"よく検索されるキーワード"</h2>
+                                {query_list(report.top_queries, "まだ検索はありません")}
+                            </div>
+                            <div class="admin-card">
+                                <h2>"結果が0件のキーワード"</h2>
+                                {query_list(report.zero_result_queries, "0件の検索はありません")}
+                            </div>
+                        </div>
+
+                        <div class="admin-card">
+                            <h2>"表示位置ごとのクリック率"</h2>
+                            <ul class="admin-list">
+                                {report.ctr.into_iter().map(|row| {
+                                    let rate = row.rate() * 100.0;
+                                    view! {
+                                        <li class="ctr-row">
+                                            <span>{format!("{} 位", row.position)}</span>
+                                            <span class="ctr-bar">
+                                                <span class="ctr-bar-fill" style=format!("width: {rate:.1}%")></span>
+                                            </span>
+                                            <span>{format!("{rate:.1}% ({}/{})", row.clicks, row.impressions)}</span>
+                                        </li>
+                                    }
+                                }).collect_view()}
+                            </ul>
+                        </div>
+
+                        <div class="admin-card">
+                            <h2>"遅い検索"</h2>
+                            {report.slow_searches.is_empty().then(|| view! {
+                                <p class="watchlist-empty">"まだ検索はありません"</p>
+                            })}
+                            <ul class="admin-list">
+                                {report.slow_searches.into_iter().map(|event| view! {
+                                    <li>
+                                        <span>
+                                            {format!("[{}] {}", event.index, if event.query.is_empty() { "(キーワードなし)" } else { &event.query })}
+                                            {event.filter.map(|f| view! { <code class="analytics-filter">{f}</code> })}
+                                        </span>
+                                        <span>{format!("{} ms (Meilisearch {} ms)", event.latency_ms, event.processing_ms)}</span>
+                                    </li>
+                                }).collect_view()}
+                            </ul>
+                        </div>
+
+                        <div class="admin-card">
+                            <h2>"同義語の候補"</h2>
+                            <p class="admin-hint">"0件だった検索の直後に、同じ利用者が別のキーワードで結果を得た組み合わせです"</p>
+                            {report.reformulations.is_empty().then(|| view! {
+                                <p class="watchlist-empty">"候補はありません"</p>
+                            })}
+                            <ul class="admin-list">
+                                {report.reformulations.into_iter().map(|candidate| {
+                                    let label = format!("[{}] {} → {} ({} 回)", candidate.index, candidate.from, candidate.to, candidate.count);
+                                    view! {
+                                        <li>
+                                            <span>{label}</span>
+                                            <button
+                                                class="page-btn"
+                                                on:click=move |_| add_synonym(candidate.clone())
+                                            >"同義語に追加"</button>
+                                        </li>
+                                    }
+                                }).collect_view()}
+                            </ul>
+                        </div>
+                    }.into_any(),
+                })}
+            </Suspense>
+        </div>
+    }
+}
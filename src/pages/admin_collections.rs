@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::api::{
+    delete_collection, get_collection, list_collections, save_collection, search_items,
+};
+use crate::model::collection::{Collection, CollectionKind, CollectionMember};
+use crate::model::search::SearchHit;
+use crate::pages::admin_settings::{input_value, textarea_value};
+
+#[component]
+pub fn CollectionsAdminPage() -> impl IntoView {
+    let (collections, set_collections) = signal(Vec::<Collection>::new());
+    let (draft, set_draft) = signal(Collection::default());
+    // Member key → title, so the editor can show names without refetching
+    let (titles, set_titles) = signal(HashMap::<String, String>::new());
+    let (message, set_message) = signal(Option::<String>::None);
+    let (saving, set_saving) = signal(false);
+    let (add_index, set_add_index) = signal("movies".to_string());
+    let (add_query, set_add_query) = signal(String::new());
+    let (candidates, set_candidates) = signal(Vec::<SearchHit>::new());
+
+    let refresh = move || {
+        spawn_local(async move {
+            match list_collections().await {
+                Ok(list) => set_collections.set(list),
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+    refresh();
+
+    let remember = move |hits: &[SearchHit]| {
+        set_titles.update(|t| {
+            for hit in hits {
+                t.insert(format!("{}:{}", hit.index, hit.id), hit.title.clone());
+            }
+        });
+    };
+
+    let open = move |id: i64| {
+        set_message.set(None);
+        spawn_local(async move {
+            match get_collection(id).await {
+                Ok(detail) => {
+                    remember(&detail.members);
+                    set_draft.set(detail.collection);
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    let on_search = move |_| {
+        let q = add_query.get_untracked();
+        let idx = add_index.get_untracked();
+        spawn_local(async move {
+            match search_items(q, idx, None, None, None, None, None, Some(1), None, None, None, None, None).await {
+                Ok(response) => {
+                    remember(&response.hits);
+                    set_candidates.set(response.hits);
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    let on_save = move |_| {
+        let collection = draft.get_untracked();
+        set_saving.set(true);
+        set_message.set(None);
+        spawn_local(async move {
+            match save_collection(collection).await {
+                Ok(saved) => {
+                    set_message.set(Some(format!("「{}」を保存しました", saved.name)));
+                    set_draft.set(saved);
+                    refresh();
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+            set_saving.set(false);
+        });
+    };
+
+    let on_delete = move |_| {
+        let id = draft.get_untracked().id;
+        if id == 0 {
+            return;
+        }
+        spawn_local(async move {
+            match delete_collection(id).await {
+                Ok(()) => {
+                    set_message.set(Some("削除しました".to_string()));
+                    set_draft.set(Collection::default());
+                    refresh();
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    view! {
+        <div class="admin-page">
+            <h1 class="admin-title">"コレクション管理"</h1>
+
+            {move || message.get().map(|msg| view! {
+                <div class="seed-message">{msg}</div>
+            })}
+
+            <div class="admin-layout">
+                <section class="admin-card">
+                    <h2>{move || {
+                        let id = draft.get().id;
+                        if id == 0 { "新しいコレクション".to_string() } else { format!("コレクション #{id}") }
+                    }}</h2>
+
+                    <div class="admin-field">
+                        <label>"名前"</label>
+                        <input
+                            type="text"
+                            class="admin-input"
+                            prop:value=move || draft.get().name
+                            on:input=move |ev| set_draft.update(|c| c.name = input_value(&ev))
+                        />
+                    </div>
+
+                    <div class="admin-field">
+                        <label>"説明"</label>
+                        <textarea
+                            class="admin-textarea"
+                            rows="3"
+                            prop:value=move || draft.get().description
+                            on:input=move |ev| set_draft.update(|c| c.description = textarea_value(&ev))
+                        ></textarea>
+                    </div>
+
+                    <div class="admin-field">
+                        <label>"種類"</label>
+                        <div class="index-toggle">
+                            {[CollectionKind::Series, CollectionKind::List].into_iter().map(|kind| view! {
+                                <button
+                                    class=move || if draft.get().kind == kind { "toggle-btn active" } else { "toggle-btn" }
+                                    on:click=move |_| set_draft.update(|c| c.kind = kind)
+                                >
+                                    {kind.label()}
+                                </button>
+                            }).collect_view()}
+                        </div>
+                    </div>
+
+                    <div class="admin-field">
+                        <label>"メンバー (上から順に表示)"</label>
+                        <ol class="admin-list collection-members">
+                            {move || {
+                                let members = draft.get().members;
+                                let count = members.len();
+                                members.into_iter().enumerate().map(|(i, member)| {
+                                    let key = member.key();
+                                    let title = titles.with(|t| t.get(&key).cloned()).unwrap_or(key);
+                                    view! {
+                                        <li>
+                                            <span>{format!("{}. {title}", i + 1)}</span>
+                                            <span class="collection-member-actions">
+                                                <button
+                                                    class="page-btn"
+                                                    disabled=i == 0
+                                                    on:click=move |_| set_draft.update(|c| c.move_member(i, i - 1))
+                                                >"↑"</button>
+                                                <button
+                                                    class="page-btn"
+                                                    disabled=i + 1 == count
+                                                    on:click=move |_| set_draft.update(|c| c.move_member(i, i + 1))
+                                                >"↓"</button>
+                                                <button
+                                                    class="page-btn"
+                                                    on:click=move |_| set_draft.update(|c| { c.members.remove(i); })
+                                                >"削除"</button>
+                                            </span>
+                                        </li>
+                                    }
+                                }).collect_view()
+                            }}
+                        </ol>
+                    </div>
+
+                    <div class="admin-field">
+                        <label>"メンバーを追加"</label>
+                        <div class="index-toggle">
+                            {["movies", "books"].into_iter().map(|idx| view! {
+                                <button
+                                    class=move || if add_index.get() == idx { "toggle-btn active" } else { "toggle-btn" }
+                                    on:click=move |_| set_add_index.set(idx.to_string())
+                                >
+                                    {if idx == "movies" { "映画" } else { "書籍" }}
+                                </button>
+                            }).collect_view()}
+                        </div>
+                        <input
+                            type="text"
+                            class="admin-input"
+                            placeholder="タイトルで検索"
+                            prop:value=add_query
+                            on:input=move |ev| set_add_query.set(input_value(&ev))
+                        />
+                        <button class="page-btn" on:click=on_search>"検索"</button>
+                        <ul class="admin-list">
+                            {move || candidates.get().into_iter().map(|hit| {
+                                let member = CollectionMember { index: hit.index.clone(), id: hit.id };
+                                let already = draft.with(|c| c.members.contains(&member));
+                                view! {
+                                    <li>
+                                        <span>{format!("{} ({})", hit.title, hit.year)}</span>
+                                        <button
+                                            class="page-btn"
+                                            disabled=already
+                                            on:click=move |_| set_draft.update(|c| c.members.push(member.clone()))
+                                        >"追加"</button>
+                                    </li>
+                                }
+                            }).collect_view()}
+                        </ul>
+                    </div>
+
+                    <div class="collection-actions">
+                        <button class="web-import-btn" on:click=on_save disabled=saving>
+                            {move || if saving.get() { "保存中..." } else { "保存" }}
+                        </button>
+                        <button class="page-btn" on:click=on_delete disabled=move || draft.get().id == 0>
+                            "削除"
+                        </button>
+                    </div>
+                </section>
+
+                <aside class="admin-card">
+                    <h2>"コレクション一覧"</h2>
+                    <button class="page-btn" on:click=move |_| set_draft.set(Collection::default())>
+                        "新規作成"
+                    </button>
+                    <ul class="admin-list">
+                        {move || collections.get().into_iter().map(|c| view! {
+                            <li>
+                                <span>{format!("{} ({} 件)", c.name, c.members.len())}</span>
+                                <button class="page-btn" on:click=move |_| open(c.id)>"編集"</button>
+                            </li>
+                        }).collect_view()}
+                    </ul>
+                </aside>
+            </div>
+        </div>
+    }
+}
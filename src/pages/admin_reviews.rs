@@ -0,0 +1,113 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::api::{moderate_review, search_reviews};
+use crate::model::review::ReviewStatus;
+use crate::pages::admin_settings::input_value;
+
+#[component]
+pub fn ReviewsAdminPage() -> impl IntoView {
+    let (query, set_query) = signal(String::new());
+    // The moderation queue by default; off searches every visible review
+    let (flagged_only, set_flagged_only) = signal(true);
+    let (version, set_version) = signal(0u32);
+    let (message, set_message) = signal(Option::<String>::None);
+
+    let reviews = Resource::new(
+        move || (query.get(), flagged_only.get(), version.get()),
+        |(query, flagged_only, _)| async move { search_reviews(query, flagged_only).await },
+    );
+
+    let moderate = move |review_id: String, status: ReviewStatus| {
+        spawn_local(async move {
+            match moderate_review(review_id, status).await {
+                Ok(review) => {
+                    set_message.set(Some(format!(
+                        "{} さんのレビューを「{}」にしました",
+                        review.username,
+                        review.status.label()
+                    )));
+                    set_version.update(|v| *v += 1);
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    view! {
+        <div class="admin-page">
+            <h1 class="admin-title">"レビュー管理"</h1>
+
+            {move || message.get().map(|msg| view! {
+                <div class="seed-message">{msg}</div>
+            })}
+
+            <div class="watchlist-controls">
+                <input
+                    type="text"
+                    class="admin-input"
+                    placeholder="本文・作品名・ユーザー名で検索"
+                    prop:value=query
+                    on:change=move |ev| set_query.set(input_value(&ev))
+                />
+                <div class="index-toggle">
+                    {[(true, "報告済み・非表示"), (false, "公開中のすべて")].into_iter().map(|(value, label)| view! {
+                        <button
+                            class=move || if flagged_only.get() == value { "toggle-btn active" } else { "toggle-btn" }
+                            on:click=move |_| set_flagged_only.set(value)
+                        >
+                            {label}
+                        </button>
+                    }).collect_view()}
+                </div>
+            </div>
+
+            <Suspense fallback=move || view! { <div class="loading">"読み込み中..."</div> }>
+                {move || reviews.get().map(|result| match result {
+                    Ok(list) if list.is_empty() => view! {
+                        <p class="watchlist-empty">"該当するレビューはありません"</p>
+                    }.into_any(),
+                    Ok(list) => view! {
+                        <ul class="review-list">
+                            {list.into_iter().map(|review| {
+                                let hide_id = review.id.clone();
+                                let show_id = review.id.clone();
+                                let reasons: Vec<&str> = review.flags.iter().map(|f| f.reason.label()).collect();
+                                view! {
+                                    <li class="review-item">
+                                        <div class="review-header">
+                                            <span class="review-score">{format!("★ {}", review.score)}</span>
+                                            <span class="review-author">{review.username.clone()}</span>
+                                            <a href=review.detail_url()>{review.item_title.clone()}</a>
+                                            <span class="watchlist-status">{review.status.label()}</span>
+                                        </div>
+                                        <p class="review-body">{review.body.clone()}</p>
+                                        {(!reasons.is_empty()).then(|| view! {
+                                            <p class="review-flags">
+                                                {format!("報告 {} 件: {}", reasons.len(), reasons.join("、"))}
+                                            </p>
+                                        })}
+                                        <div class="collection-member-actions">
+                                            <button
+                                                class="page-btn"
+                                                on:click=move |_| moderate(show_id.clone(), ReviewStatus::Visible)
+                                            >"公開 (報告を解除)"</button>
+                                            <button
+                                                class="page-btn"
+                                                disabled=review.status == ReviewStatus::Hidden
+                                                on:click=move |_| moderate(hide_id.clone(), ReviewStatus::Hidden)
+                                            >"非表示"</button>
+                                        </div>
+                                    </li>
+                                }
+                            }).collect_view()}
+                        </ul>
+                    }.into_any(),
+                    Err(e) => view! {
+                        <div class="error">"エラー: "{e.to_string()}</div>
+                    }.into_any(),
+                })}
+            </Suspense>
+        </div>
+    }
+}
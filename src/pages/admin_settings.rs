@@ -0,0 +1,334 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+
+use crate::api::{
+    get_search_settings, get_search_settings_history, get_searchable_attributes,
+    preview_search_settings, save_search_settings,
+};
+use crate::model::search_settings::{SearchSettings, SettingsPreview, TypoTolerance};
+
+fn parse_list(text: &str) -> Vec<String> {
+    text.split([',', '、', '\n'])
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+fn parse_synonyms(text: &str) -> Vec<Vec<String>> {
+    text.lines()
+        .map(parse_list)
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+fn format_synonyms(groups: &[Vec<String>]) -> String {
+    groups
+        .iter()
+        .map(|g| g.join(", "))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub(crate) fn input_value(ev: &leptos::ev::Event) -> String {
+    let target = ev.target().unwrap();
+    target.unchecked_ref::<web_sys::HtmlInputElement>().value()
+}
+
+pub(crate) fn textarea_value(ev: &leptos::ev::Event) -> String {
+    let target = ev.target().unwrap();
+    target
+        .unchecked_ref::<web_sys::HtmlTextAreaElement>()
+        .value()
+}
+
+#[component]
+pub fn SearchSettingsPage() -> impl IntoView {
+    let (index, set_index) = signal("movies".to_string());
+    let (synonyms, set_synonyms) = signal(String::new());
+    let (stop_words, set_stop_words) = signal(String::new());
+    let (typo_enabled, set_typo_enabled) = signal(true);
+    let (one_typo_min, set_one_typo_min) = signal(5u8);
+    let (two_typos_min, set_two_typos_min) = signal(9u8);
+    let (searchable, set_searchable) = signal(Vec::<String>::new());
+    let (disabled_attributes, set_disabled_attributes) = signal(Vec::<String>::new());
+    let (disabled_words, set_disabled_words) = signal(String::new());
+    let (note, set_note) = signal(String::new());
+    let (version, set_version) = signal(0u32);
+    let (history, set_history) = signal(Vec::<SearchSettings>::new());
+    let (message, set_message) = signal(Option::<String>::None);
+    let (saving, set_saving) = signal(false);
+    let (test_query, set_test_query) = signal(String::new());
+    let (preview, set_preview) = signal(Option::<Result<SettingsPreview, ServerFnError>>::None);
+
+    let fill = move |settings: SearchSettings| {
+        set_synonyms.set(format_synonyms(&settings.synonyms));
+        set_stop_words.set(settings.stop_words.join(", "));
+        set_typo_enabled.set(settings.typo_tolerance.enabled);
+        set_one_typo_min.set(settings.typo_tolerance.one_typo_min);
+        set_two_typos_min.set(settings.typo_tolerance.two_typos_min);
+        set_disabled_attributes.set(settings.typo_tolerance.disabled_attributes);
+        set_disabled_words.set(settings.typo_tolerance.disabled_words.join(", "));
+        set_version.set(settings.version);
+        set_note.set(String::new());
+    };
+
+    let draft = move || SearchSettings {
+        synonyms: parse_synonyms(&synonyms.get_untracked()),
+        stop_words: parse_list(&stop_words.get_untracked()),
+        typo_tolerance: TypoTolerance {
+            enabled: typo_enabled.get_untracked(),
+            one_typo_min: one_typo_min.get_untracked(),
+            two_typos_min: two_typos_min.get_untracked(),
+            disabled_attributes: disabled_attributes.get_untracked(),
+            disabled_words: parse_list(&disabled_words.get_untracked()),
+        },
+        note: note.get_untracked(),
+        ..SearchSettings::new(&index.get_untracked())
+    };
+
+    // Load the current version and its history whenever the index changes
+    Effect::new(move |_| {
+        let idx = index.get();
+        set_preview.set(None);
+        spawn_local(async move {
+            match get_search_settings(idx.clone()).await {
+                Ok(settings) => fill(settings),
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+            set_searchable.set(get_searchable_attributes(idx.clone()).await.unwrap_or_default());
+            set_history.set(get_search_settings_history(idx).await.unwrap_or_default());
+        });
+    });
+
+    let on_save = move |_| {
+        let settings = draft();
+        set_saving.set(true);
+        set_message.set(None);
+        spawn_local(async move {
+            match save_search_settings(settings).await {
+                Ok(saved) => {
+                    set_message.set(Some(format!("バージョン {} を保存しました", saved.version)));
+                    let idx = saved.index.clone();
+                    fill(saved);
+                    set_history.set(get_search_settings_history(idx).await.unwrap_or_default());
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+            set_saving.set(false);
+        });
+    };
+
+    let on_preview = move |_| {
+        let settings = draft();
+        let q = test_query.get_untracked();
+        spawn_local(async move {
+            set_preview.set(Some(preview_search_settings(settings, q).await));
+        });
+    };
+
+    view! {
+        <div class="admin-page">
+            <h1 class="admin-title">"検索設定"</h1>
+            <div class="index-toggle">
+                {["movies", "books", "series", "web"].into_iter().map(|idx| {
+                    let label = match idx {
+                        "movies" => "映画",
+                        "books" => "書籍",
+                        "series" => "シリーズ",
+                        _ => "Web",
+                    };
+                    view! {
+                        <button
+                            class=move || if index.get() == idx { "toggle-btn active" } else { "toggle-btn" }
+                            on:click=move |_| set_index.set(idx.to_string())
+                        >
+                            {label}
+                        </button>
+                    }
+                }).collect_view()}
+            </div>
+
+            {move || message.get().map(|msg| view! {
+                <div class="seed-message">{msg}</div>
+            })}
+
+            <div class="admin-layout">
+                <section class="admin-card">
+                    <h2>{move || format!("現在のバージョン: v{}", version.get())}</h2>
+
+                    <div class="admin-field">
+                        <label>"同義語 (1 行に 1 グループ, カンマ区切り)"</label>
+                        <textarea
+                            class="admin-textarea"
+                            rows="8"
+                            placeholder="SF, サイエンスフィクション"
+                            prop:value=synonyms
+                            on:input=move |ev| set_synonyms.set(textarea_value(&ev))
+                        ></textarea>
+                    </div>
+
+                    <div class="admin-field">
+                        <label>"ストップワード (カンマ区切り)"</label>
+                        <textarea
+                            class="admin-textarea"
+                            rows="3"
+                            prop:value=stop_words
+                            on:input=move |ev| set_stop_words.set(textarea_value(&ev))
+                        ></textarea>
+                    </div>
+
+                    <div class="admin-field">
+                        <label class="genre-checkbox">
+                            <input
+                                type="checkbox"
+                                prop:checked=typo_enabled
+                                on:change=move |_| set_typo_enabled.update(|v| *v = !*v)
+                            />
+                            <span>"誤字許容を有効にする"</span>
+                        </label>
+                        <div class="range-inputs">
+                            <span>"1 文字誤字の最小語長"</span>
+                            <input
+                                type="number"
+                                class="range-input"
+                                min="1"
+                                prop:value=move || one_typo_min.get().to_string()
+                                on:change=move |ev| {
+                                    if let Ok(v) = input_value(&ev).parse() {
+                                        set_one_typo_min.set(v);
+                                    }
+                                }
+                            />
+                            <span>"2 文字誤字の最小語長"</span>
+                            <input
+                                type="number"
+                                class="range-input"
+                                min="1"
+                                prop:value=move || two_typos_min.get().to_string()
+                                on:change=move |ev| {
+                                    if let Ok(v) = input_value(&ev).parse() {
+                                        set_two_typos_min.set(v);
+                                    }
+                                }
+                            />
+                        </div>
+                    </div>
+
+                    <div class="admin-field">
+                        <label>"誤字許容を無効にする属性"</label>
+                        <div class="genre-checkboxes">
+                            {move || searchable.get().into_iter().map(|attr| {
+                                let attr_for_check = attr.clone();
+                                let attr_for_change = attr.clone();
+                                view! {
+                                    <label class="genre-checkbox">
+                                        <input
+                                            type="checkbox"
+                                            prop:checked=move || disabled_attributes.get().contains(&attr_for_check)
+                                            on:change=move |_| {
+                                                set_disabled_attributes.update(|attrs| {
+                                                    if attrs.contains(&attr_for_change) {
+                                                        attrs.retain(|a| a != &attr_for_change);
+                                                    } else {
+                                                        attrs.push(attr_for_change.clone());
+                                                    }
+                                                });
+                                            }
+                                        />
+                                        <span>{attr}</span>
+                                    </label>
+                                }
+                            }).collect_view()}
+                        </div>
+                    </div>
+
+                    <div class="admin-field">
+                        <label>"誤字許容を無効にする単語 (カンマ区切り)"</label>
+                        <input
+                            type="text"
+                            class="admin-input"
+                            prop:value=disabled_words
+                            on:input=move |ev| set_disabled_words.set(input_value(&ev))
+                        />
+                    </div>
+
+                    <div class="admin-field">
+                        <label>"変更メモ"</label>
+                        <input
+                            type="text"
+                            class="admin-input"
+                            prop:value=note
+                            on:input=move |ev| set_note.set(input_value(&ev))
+                        />
+                    </div>
+
+                    <button class="web-import-btn" on:click=on_save disabled=saving>
+                        {move || if saving.get() { "保存中..." } else { "新しいバージョンとして保存" }}
+                    </button>
+                </section>
+
+                <aside class="admin-card">
+                    <h2>"テストクエリ"</h2>
+                    <div class="admin-field">
+                        <input
+                            type="text"
+                            class="admin-input"
+                            placeholder="例: スターウォーズ"
+                            prop:value=test_query
+                            on:input=move |ev| set_test_query.set(input_value(&ev))
+                        />
+                        <button class="page-btn" on:click=on_preview>"プレビュー"</button>
+                    </div>
+                    {move || preview.get().map(|result| match result {
+                        Ok(p) => view! {
+                            <div class="admin-preview">
+                                {p.analysis.expansions.into_iter().map(|(term, others)| view! {
+                                    <p class="admin-hint">{format!("同義語: {term} → {}", others.join(", "))}</p>
+                                }).collect_view()}
+                                {(!p.analysis.stop_words.is_empty()).then(|| view! {
+                                    <p class="admin-hint">{format!("無視される語: {}", p.analysis.stop_words.join(", "))}</p>
+                                })}
+                                <p class="results-meta">{format!("保存済み設定での結果: {} 件", p.total_hits)}</p>
+                                <ul class="admin-list">
+                                    {p.titles.into_iter().map(|t| view! { <li>{t}</li> }).collect_view()}
+                                </ul>
+                            </div>
+                        }.into_any(),
+                        Err(e) => view! {
+                            <div class="error">"エラー: "{e.to_string()}</div>
+                        }.into_any(),
+                    })}
+
+                    <h2>"履歴"</h2>
+                    <ul class="admin-list">
+                        {move || history.get().into_iter().map(|s| {
+                            let label = if s.note.is_empty() {
+                                format!("v{}", s.version)
+                            } else {
+                                format!("v{} — {}", s.version, s.note)
+                            };
+                            view! {
+                                <li>
+                                    <span>{label}</span>
+                                    <button
+                                        class="page-btn"
+                                        on:click=move |_| {
+                                            let mut restored = s.clone();
+                                            restored.note = format!("v{} から復元", s.version);
+                                            fill(restored.clone());
+                                            set_note.set(restored.note);
+                                        }
+                                    >
+                                        "読み込む"
+                                    </button>
+                                </li>
+                            }
+                        }).collect_view()}
+                    </ul>
+                </aside>
+            </div>
+        </div>
+    }
+}
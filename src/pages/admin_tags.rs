@@ -0,0 +1,149 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::api::{ban_tag, get_tag_overview, merge_tags, unban_tag};
+use crate::model::tag::TagOverview;
+use crate::pages::admin_settings::input_value;
+
+#[component]
+pub fn TagsAdminPage() -> impl IntoView {
+    let (overview, set_overview) = signal(Option::<Result<TagOverview, ServerFnError>>::None);
+    let (merge_from, set_merge_from) = signal(String::new());
+    let (merge_to, set_merge_to) = signal(String::new());
+    let (message, set_message) = signal(Option::<String>::None);
+
+    Effect::new(move |_| {
+        spawn_local(async move {
+            set_overview.set(Some(get_tag_overview().await));
+        });
+    });
+
+    // Every action returns the refreshed overview
+    let apply = move |result: Result<TagOverview, ServerFnError>, done: String| match result {
+        Ok(updated) => {
+            set_overview.set(Some(Ok(updated)));
+            set_message.set(Some(done));
+        }
+        Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+    };
+
+    let on_merge = move |_| {
+        let from = merge_from.get_untracked();
+        let to = merge_to.get_untracked();
+        spawn_local(async move {
+            let done = format!("「{from}」を「{to}」に統合しました");
+            apply(merge_tags(from, to).await, done);
+            set_merge_from.set(String::new());
+        });
+    };
+
+    let on_ban = move |tag: String| {
+        spawn_local(async move {
+            let done = format!("「{tag}」を禁止しました");
+            apply(ban_tag(tag).await, done);
+        });
+    };
+
+    let on_unban = move |tag: String| {
+        spawn_local(async move {
+            let done = format!("「{tag}」の禁止を解除しました");
+            apply(unban_tag(tag).await, done);
+        });
+    };
+
+    view! {
+        <div class="admin-page">
+            <h1 class="admin-title">"タグ管理"</h1>
+
+            {move || message.get().map(|msg| view! {
+                <div class="seed-message">{msg}</div>
+            })}
+
+            <div class="admin-card">
+                <h2>"タグの統合"</h2>
+                <p class="admin-hint">"統合元のタグはすべて統合先に置き換わり、以後は同義語として扱われます"</p>
+                <div class="tag-merge">
+                    <input
+                        type="text"
+                        class="admin-input"
+                        placeholder="統合元 (例: 泣ける映画)"
+                        prop:value=merge_from
+                        on:input=move |ev| set_merge_from.set(input_value(&ev))
+                    />
+                    <span class="range-sep">"→"</span>
+                    <input
+                        type="text"
+                        class="admin-input"
+                        placeholder="統合先 (例: 泣ける)"
+                        prop:value=merge_to
+                        on:input=move |ev| set_merge_to.set(input_value(&ev))
+                    />
+                    <button class="web-import-btn" on:click=on_merge>"統合"</button>
+                </div>
+            </div>
+
+            {move || match overview.get() {
+                None => view! { <div class="loading">"読み込み中..."</div> }.into_any(),
+                Some(Err(e)) => view! {
+                    <div class="error">"エラー: "{e.to_string()}</div>
+                }.into_any(),
+                Some(Ok(overview)) => view! {
+                    <div class="admin-card">
+                        <h2>"使用中のタグ"</h2>
+                        {overview.tags.is_empty().then(|| view! {
+                            <p class="watchlist-empty">"まだタグはありません"</p>
+                        })}
+                        <ul class="admin-list">
+                            {overview.tags.into_iter().map(|fv| {
+                                let tag = fv.value.clone();
+                                let tag_for_merge = fv.value.clone();
+                                view! {
+                                    <li>
+                                        <span>{format!("{} ({})", fv.value, fv.count)}</span>
+                                        <div class="collection-member-actions">
+                                            <button
+                                                class="page-btn"
+                                                on:click=move |_| set_merge_from.set(tag_for_merge.clone())
+                                            >"統合元に"</button>
+                                            <button
+                                                class="page-btn"
+                                                on:click=move |_| on_ban(tag.clone())
+                                            >"禁止"</button>
+                                        </div>
+                                    </li>
+                                }
+                            }).collect_view()}
+                        </ul>
+                    </div>
+
+                    <div class="admin-card">
+                        <h2>"同義語"</h2>
+                        <ul class="admin-list">
+                            {overview.rules.aliases.into_iter().map(|alias| view! {
+                                <li>{format!("{} → {}", alias.alias, alias.tag)}</li>
+                            }).collect_view()}
+                        </ul>
+                    </div>
+
+                    <div class="admin-card">
+                        <h2>"禁止タグ"</h2>
+                        <ul class="admin-list">
+                            {overview.rules.banned.into_iter().map(|tag| {
+                                let tag_for_unban = tag.clone();
+                                view! {
+                                    <li>
+                                        <span>{tag}</span>
+                                        <button
+                                            class="page-btn"
+                                            on:click=move |_| on_unban(tag_for_unban.clone())
+                                        >"解除"</button>
+                                    </li>
+                                }
+                            }).collect_view()}
+                        </ul>
+                    </div>
+                }.into_any(),
+            }}
+        </div>
+    }
+}
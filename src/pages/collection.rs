@@ -0,0 +1,67 @@
+use leptos::prelude::*;
+use leptos_router::hooks::use_params_map;
+
+use crate::api::get_collection;
+use crate::components::result_card::ResultCard;
+use crate::model::collection::CollectionDetail;
+
+#[component]
+pub fn CollectionPage() -> impl IntoView {
+    let params = use_params_map();
+
+    let id = move || {
+        params
+            .read()
+            .get("id")
+            .and_then(|id| id.parse::<i64>().ok())
+            .unwrap_or(0)
+    };
+
+    let collection_resource = Resource::new(id, |id| async move { get_collection(id).await });
+
+    view! {
+        <div class="detail-page">
+            <a href="/" class="back-link">"← 検索に戻る"</a>
+            <Suspense fallback=move || view! { <div class="loading">"読み込み中..."</div> }>
+                {move || {
+                    collection_resource.get().map(|result| {
+                        match result {
+                            Ok(detail) => view! { <CollectionView detail=detail/> }.into_any(),
+                            Err(e) => view! {
+                                <div class="error">"エラー: "{e.to_string()}</div>
+                            }.into_any(),
+                        }
+                    })
+                }}
+            </Suspense>
+        </div>
+    }
+}
+
+#[component]
+fn CollectionView(detail: CollectionDetail) -> impl IntoView {
+    let collection = detail.collection;
+    let count = detail.members.len();
+
+    view! {
+        <article class="detail-card">
+            <div class="detail-header">
+                <h1>{collection.name.clone()}</h1>
+            </div>
+            <div class="detail-meta">
+                <span class="detail-type">{collection.kind.label()}</span>
+                <span class="detail-year">{format!("{count} 作品")}</span>
+            </div>
+            {(!collection.description.is_empty()).then(|| view! {
+                <div class="detail-description">
+                    <p>{collection.description.clone()}</p>
+                </div>
+            })}
+        </article>
+        <section class="person-works">
+            <div class="results-grid">
+                {detail.members.into_iter().map(|hit| view! { <ResultCard hit=hit/> }).collect_view()}
+            </div>
+        </section>
+    }
+}
@@ -1,9 +1,16 @@
 use leptos::prelude::*;
-use leptos_router::hooks::use_params_map;
+use leptos::task::spawn_local;
+use leptos_router::hooks::{use_params_map, use_query_map};
 
-use crate::api::{get_book, get_movie, get_web_result};
+use crate::api::{get_book, get_movie, get_series, get_web_result, log_click};
+use crate::components::related_items::RelatedItems;
+use crate::components::reviews::Reviews;
+use crate::components::tags::Tags;
+use crate::components::watchlist_buttons::WatchlistButtons;
 use crate::model::book::Book;
-use crate::model::movie::Movie;
+use crate::model::isbn::isbn10;
+use crate::model::movie::{country_label, Movie};
+use crate::model::series::Series;
 use crate::model::web_result::WebResult;
 
 #[component]
@@ -18,8 +25,24 @@ pub fn DetailPage(index: String) -> impl IntoView {
             .unwrap_or(0)
     };
 
+    // Opened from a result card: `?sid=…&pos=…` identify the search and position
+    let query = use_query_map();
+    let click_index = index.clone();
+    Effect::new(move |_| {
+        let (search_id, position) = query.with(|q| {
+            (q.get("sid"), q.get("pos").and_then(|p| p.parse::<usize>().ok()))
+        });
+        if let (Some(search_id), Some(position)) = (search_id, position) {
+            let index = click_index.clone();
+            let id = id();
+            spawn_local(async move {
+                let _ = log_click(search_id, index, id, position).await;
+            });
+        }
+    });
+
     if index == "movies" {
-        let movie_resource = Resource::new(move || id(), |id| async move { get_movie(id).await });
+        let movie_resource = Resource::new(id, |id| async move { get_movie(id).await });
 
         view! {
             <div class="detail-page">
@@ -36,11 +59,37 @@ pub fn DetailPage(index: String) -> impl IntoView {
                         })
                     }}
                 </Suspense>
+                <Tags index="movies" id=Signal::derive(id)/>
+                <Reviews index="movies" id=Signal::derive(id)/>
+                <RelatedItems index="movies" id=Signal::derive(id)/>
+            </div>
+        }
+        .into_any()
+    } else if index == "series" {
+        let series_resource = Resource::new(id, |id| async move { get_series(id).await });
+
+        view! {
+            <div class="detail-page">
+                <a href="/" class="back-link">"← 検索に戻る"</a>
+                <Suspense fallback=move || view! { <div class="loading">"読み込み中..."</div> }>
+                    {move || {
+                        series_resource.get().map(|result| {
+                            match result {
+                                Ok(series) => view! { <SeriesDetail series=series/> }.into_any(),
+                                Err(e) => view! {
+                                    <div class="error">"エラー: "{e.to_string()}</div>
+                                }.into_any(),
+                            }
+                        })
+                    }}
+                </Suspense>
+                <Tags index="series" id=Signal::derive(id)/>
+                <Reviews index="series" id=Signal::derive(id)/>
             </div>
         }
         .into_any()
     } else if index == "web" {
-        let web_resource = Resource::new(move || id(), |id| async move { get_web_result(id).await });
+        let web_resource = Resource::new(id, |id| async move { get_web_result(id).await });
 
         view! {
             <div class="detail-page">
@@ -61,7 +110,7 @@ pub fn DetailPage(index: String) -> impl IntoView {
         }
         .into_any()
     } else {
-        let book_resource = Resource::new(move || id(), |id| async move { get_book(id).await });
+        let book_resource = Resource::new(id, |id| async move { get_book(id).await });
 
         view! {
             <div class="detail-page">
@@ -78,6 +127,9 @@ pub fn DetailPage(index: String) -> impl IntoView {
                         })
                     }}
                 </Suspense>
+                <Tags index="books" id=Signal::derive(id)/>
+                <Reviews index="books" id=Signal::derive(id)/>
+                <RelatedItems index="books" id=Signal::derive(id)/>
             </div>
         }
         .into_any()
@@ -88,6 +140,16 @@ pub fn DetailPage(index: String) -> impl IntoView {
 fn MovieDetail(movie: Movie) -> impl IntoView {
     let rating = format!("{:.1}", movie.rating);
     let title_en = movie.title_en.clone();
+    let imdb_url = movie.external_ids.imdb_url();
+    let countries = (!movie.countries.is_empty()).then(|| {
+        movie
+            .countries
+            .iter()
+            .map(|c| country_label(c))
+            .collect::<Vec<_>>()
+            .join("・")
+    });
+    let spoken = (!movie.spoken_languages.is_empty()).then(|| movie.spoken_languages.join(", "));
 
     view! {
         <article class="detail-card">
@@ -100,14 +162,75 @@ fn MovieDetail(movie: Movie) -> impl IntoView {
             <div class="detail-meta">
                 <span class="detail-type">"映画"</span>
                 <span class="detail-rating">{format!("★ {rating}")}</span>
+                {movie.user_rating.filter(|_| movie.user_rating_count > 0).map(|r| view! {
+                    <span class="detail-user-rating">
+                        {format!("みんなの評価 {r:.1} ({} 件)", movie.user_rating_count)}
+                    </span>
+                })}
                 <span class="detail-year">{movie.year.to_string()}</span>
                 <span class="detail-lang">{movie.language.clone()}</span>
+                {movie.certification.clone().map(|c| view! {
+                    <span class="detail-certification">{c}</span>
+                })}
             </div>
+            <WatchlistButtons index="movies".to_string() id=movie.id/>
             <div class="detail-info">
                 <div class="info-row">
                     <span class="info-label">"監督"</span>
-                    <span class="info-value">{movie.director.clone()}</span>
+                    <span class="info-value">
+                        <PersonLink id=movie.director_id name=movie.director.clone()/>
+                    </span>
                 </div>
+                {(!movie.cast.is_empty()).then(|| view! {
+                    <div class="info-row">
+                        <span class="info-label">"出演"</span>
+                        <span class="info-value">
+                            <ol class="detail-cast">
+                                {movie.cast.clone().into_iter().map(|name| view! {
+                                    <li>{name}</li>
+                                }).collect_view()}
+                            </ol>
+                        </span>
+                    </div>
+                })}
+                {movie.runtime_minutes.map(|m| view! {
+                    <div class="info-row">
+                        <span class="info-label">"上映時間"</span>
+                        <span class="info-value">{format!("{m} 分")}</span>
+                    </div>
+                })}
+                {movie.release_date.clone().map(|d| view! {
+                    <div class="info-row">
+                        <span class="info-label">"公開日"</span>
+                        <span class="info-value">{d}</span>
+                    </div>
+                })}
+                {countries.map(|c| view! {
+                    <div class="info-row">
+                        <span class="info-label">"製作国"</span>
+                        <span class="info-value">{c}</span>
+                    </div>
+                })}
+                {movie.original_language.clone().map(|l| view! {
+                    <div class="info-row">
+                        <span class="info-label">"原語"</span>
+                        <span class="info-value">{l}</span>
+                    </div>
+                })}
+                {spoken.map(|l| view! {
+                    <div class="info-row">
+                        <span class="info-label">"使用言語"</span>
+                        <span class="info-value">{l}</span>
+                    </div>
+                })}
+                {imdb_url.map(|url| view! {
+                    <div class="info-row">
+                        <span class="info-label">"外部リンク"</span>
+                        <span class="info-value">
+                            <a href=url target="_blank" rel="noopener noreferrer">"IMDb"</a>
+                        </span>
+                    </div>
+                })}
             </div>
             <div class="detail-genres">
                 {movie.genres.into_iter().map(|g| view! {
@@ -127,6 +250,27 @@ fn BookDetail(book: Book) -> impl IntoView {
     let rating = format!("{:.1}", book.rating);
     let title_en = book.title_en.clone();
     let pages = book.pages;
+    let isbn = book.isbn.clone().map(|i| match isbn10(&i) {
+        Some(short) => format!("{i} (ISBN-10: {short})"),
+        None => i,
+    });
+    // Only worth showing when the edition is a translation or retitling
+    let translated = book.original_title.as_ref().is_some_and(|t| *t != book.title)
+        || book
+            .original_language
+            .as_ref()
+            .is_some_and(|l| *l != book.language);
+    let original = translated.then(|| {
+        let title = book
+            .original_title
+            .clone()
+            .unwrap_or_else(|| book.title.clone());
+        match &book.original_language {
+            Some(lang) => format!("{title} ({lang})"),
+            None => title,
+        }
+    });
+    let translators = (!book.translators.is_empty()).then(|| book.translators.join("、"));
 
     view! {
         <article class="detail-card">
@@ -139,14 +283,61 @@ fn BookDetail(book: Book) -> impl IntoView {
             <div class="detail-meta">
                 <span class="detail-type">"書籍"</span>
                 <span class="detail-rating">{format!("★ {rating}")}</span>
+                {book.user_rating.filter(|_| book.user_rating_count > 0).map(|r| view! {
+                    <span class="detail-user-rating">
+                        {format!("みんなの評価 {r:.1} ({} 件)", book.user_rating_count)}
+                    </span>
+                })}
                 <span class="detail-year">{book.year.to_string()}</span>
                 <span class="detail-lang">{book.language.clone()}</span>
+                {book.format.map(|f| view! {
+                    <span class="detail-format">{f.label()}</span>
+                })}
             </div>
+            <WatchlistButtons index="books".to_string() id=book.id/>
             <div class="detail-info">
                 <div class="info-row">
                     <span class="info-label">"著者"</span>
-                    <span class="info-value">{book.author.clone()}</span>
+                    <span class="info-value">
+                        <PersonLink id=book.author_id name=book.author.clone()/>
+                    </span>
                 </div>
+                {translators.map(|t| view! {
+                    <div class="info-row">
+                        <span class="info-label">"翻訳"</span>
+                        <span class="info-value">{t}</span>
+                    </div>
+                })}
+                {original.map(|o| view! {
+                    <div class="info-row">
+                        <span class="info-label">"原題"</span>
+                        <span class="info-value">{o}</span>
+                    </div>
+                })}
+                {book.series_number.map(|n| view! {
+                    <div class="info-row">
+                        <span class="info-label">"巻数"</span>
+                        <span class="info-value">{format!("第 {n} 巻")}</span>
+                    </div>
+                })}
+                {book.publisher.clone().map(|p| view! {
+                    <div class="info-row">
+                        <span class="info-label">"出版社"</span>
+                        <span class="info-value">{p}</span>
+                    </div>
+                })}
+                {book.publication_date.clone().map(|d| view! {
+                    <div class="info-row">
+                        <span class="info-label">"刊行日"</span>
+                        <span class="info-value">{d}</span>
+                    </div>
+                })}
+                {isbn.map(|i| view! {
+                    <div class="info-row">
+                        <span class="info-label">"ISBN"</span>
+                        <span class="info-value">{i}</span>
+                    </div>
+                })}
                 {pages.map(|p| view! {
                     <div class="info-row">
                         <span class="info-label">"ページ数"</span>
@@ -167,6 +358,99 @@ fn BookDetail(book: Book) -> impl IntoView {
     }
 }
 
+#[component]
+fn SeriesDetail(series: Series) -> impl IntoView {
+    let rating = format!("{:.1}", series.rating);
+    let title_en = series.title_en.clone();
+    let years = series.broadcast_years();
+
+    view! {
+        <article class="detail-card">
+            <div class="detail-header">
+                <h1>{series.title.clone()}</h1>
+                {title_en.map(|en| view! {
+                    <p class="detail-title-en">{en}</p>
+                })}
+            </div>
+            <div class="detail-meta">
+                <span class="detail-type">"シリーズ"</span>
+                <span class="detail-rating">{format!("★ {rating}")}</span>
+                {series.user_rating.filter(|_| series.user_rating_count > 0).map(|r| view! {
+                    <span class="detail-user-rating">
+                        {format!("みんなの評価 {r:.1} ({} 件)", series.user_rating_count)}
+                    </span>
+                })}
+                <span class="detail-year">{years}</span>
+                <span class="detail-lang">{series.language.clone()}</span>
+                <span class="detail-status">{series.status.label()}</span>
+            </div>
+            <WatchlistButtons index="series".to_string() id=series.id/>
+            <div class="detail-info">
+                {series.studio.clone().map(|s| view! {
+                    <div class="info-row">
+                        <span class="info-label">"制作"</span>
+                        <span class="info-value">{s}</span>
+                    </div>
+                })}
+                {series.network.clone().map(|n| view! {
+                    <div class="info-row">
+                        <span class="info-label">"放送・配信"</span>
+                        <span class="info-value">{n}</span>
+                    </div>
+                })}
+                <div class="info-row">
+                    <span class="info-label">"話数"</span>
+                    <span class="info-value">
+                        {format!("全 {} 話 ({} シーズン)", series.episode_count, series.seasons.len())}
+                    </span>
+                </div>
+            </div>
+            {(!series.seasons.is_empty()).then(|| view! {
+                <table class="detail-seasons">
+                    <thead>
+                        <tr>
+                            <th>"シーズン"</th>
+                            <th>"放送年"</th>
+                            <th>"話数"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {series.seasons.clone().into_iter().map(|s| view! {
+                            <tr>
+                                <td>
+                                    {match s.title {
+                                        Some(title) => format!("第 {} 期「{title}」", s.number),
+                                        None => format!("第 {} 期", s.number),
+                                    }}
+                                </td>
+                                <td>{s.year.to_string()}</td>
+                                <td>{s.episodes.to_string()}</td>
+                            </tr>
+                        }).collect_view()}
+                    </tbody>
+                </table>
+            })}
+            <div class="detail-genres">
+                {series.genres.into_iter().map(|g| view! {
+                    <span class="genre-tag">{g}</span>
+                }).collect_view()}
+            </div>
+            <div class="detail-description">
+                <h2>"あらすじ"</h2>
+                <p>{series.description.clone()}</p>
+            </div>
+        </article>
+    }
+}
+
+#[component]
+fn PersonLink(id: Option<i64>, name: String) -> impl IntoView {
+    match id {
+        Some(id) => view! { <a href=format!("/person/{id}")>{name}</a> }.into_any(),
+        None => view! { <span>{name}</span> }.into_any(),
+    }
+}
+
 #[component]
 fn WebResultDetail(result: WebResult) -> impl IntoView {
     let url = result.url.clone();
@@ -1,28 +1,44 @@
 use leptos::prelude::*;
 use leptos::task::spawn_local;
+use leptos_router::hooks::use_query_map;
 use leptos_use::signal_debounced;
 
-use crate::api::{get_facets, search_items, search_web_and_import, seed_data};
+use crate::api::{
+    get_facets, get_saved_search, search_items, search_web_and_import, seed_data,
+    semantic_search_available,
+};
 use crate::components::facet_panel::FacetPanel;
 use crate::components::pagination::Pagination;
+use crate::components::save_search::SaveSearch;
 use crate::components::search_bar::SearchBar;
 use crate::components::search_results::SearchResults;
-use crate::model::search::{FacetInfo, SearchResponse};
+use crate::model::query::parse_query;
+use crate::model::search::{
+    BookFilters, FacetInfo, MovieFilters, SearchRequest, SearchResponse, SeriesFilters,
+};
 
 #[component]
 pub fn HomePage() -> impl IntoView {
     let (query, set_query) = signal(String::new());
     let (index, set_index) = signal("movies".to_string());
     let (selected_genres, set_selected_genres) = signal(Vec::<String>::new());
+    let (selected_tags, set_selected_tags) = signal(Vec::<String>::new());
     let (year_min, set_year_min) = signal(Option::<i32>::None);
     let (year_max, set_year_max) = signal(Option::<i32>::None);
     let (rating_min, set_rating_min) = signal(Option::<f64>::None);
     let (page, set_page) = signal(1usize);
     let (sort, set_sort) = signal(Option::<String>::None);
+    let (semantic_ratio, set_semantic_ratio) = signal(0.0f32);
+    let (semantic_available, set_semantic_available) = signal(false);
+    let (movie_filters, set_movie_filters) = signal(MovieFilters::default());
+    let (book_filters, set_book_filters) = signal(BookFilters::default());
+    let (series_filters, set_series_filters) = signal(SeriesFilters::default());
     let (seeding, set_seeding) = signal(false);
     let (seed_message, set_seed_message) = signal(Option::<String>::None);
     let (loading, set_loading) = signal(false);
     let (web_importing, set_web_importing) = signal(false);
+    // A saved search being opened, applied once its index is selected
+    let pending = RwSignal::new(Option::<SearchRequest>::None);
 
     let debounced_query: Signal<String> = signal_debounced(query, 300.0);
 
@@ -41,6 +57,89 @@ pub fn HomePage() -> impl IntoView {
         });
     });
 
+    // Index-specific filters and sorts would be rejected by the other indexes
+    Effect::new(move |_| {
+        let idx = index.get();
+        // Tags are shared, but one index's tags rarely match the other's items
+        set_selected_tags.set(vec![]);
+        if idx != "movies" {
+            set_movie_filters.set(MovieFilters::default());
+        }
+        if idx != "books" {
+            set_book_filters.set(BookFilters::default());
+        }
+        if idx != "series" {
+            set_series_filters.set(SeriesFilters::default());
+        }
+        let shared_sort = sort.get_untracked().is_none_or(|s| {
+            ["year:", "rating:", "title:"]
+                .iter()
+                .any(|prefix| s.starts_with(prefix))
+                // User ratings exist on every catalogue index, just not on web results
+                || (idx != "web" && s.starts_with("user_rating"))
+        });
+        if !shared_sort {
+            set_sort.set(None);
+        }
+
+        if let Some(request) = pending.get_untracked().filter(|r| r.index == idx) {
+            pending.set(None);
+            set_query.set(request.query);
+            set_selected_genres.set(request.genres);
+            set_selected_tags.set(request.tags);
+            set_year_min.set(request.year_min);
+            set_year_max.set(request.year_max);
+            set_rating_min.set(request.rating_min);
+            set_sort.set(request.sort);
+            set_semantic_ratio.set(request.semantic_ratio.unwrap_or(0.0));
+            set_movie_filters.set(request.movie_filters);
+            set_book_filters.set(request.book_filters);
+            set_series_filters.set(request.series_filters);
+        }
+    });
+
+    // `/?saved={id}` opens a saved search
+    let query_map = use_query_map();
+    Effect::new(move |_| {
+        let Some(id) = query_map.with(|q| q.get("saved")) else {
+            return;
+        };
+        spawn_local(async move {
+            match get_saved_search(id).await {
+                Ok(saved) => {
+                    let index = saved.request.index.clone();
+                    pending.set(Some(saved.request));
+                    set_index.set(index);
+                }
+                Err(e) => set_seed_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    });
+
+    // What "この検索を保存" stores
+    let current_request = Signal::derive(move || SearchRequest {
+        query: query.get(),
+        index: index.get(),
+        genres: selected_genres.get(),
+        tags: selected_tags.get(),
+        year_min: year_min.get(),
+        year_max: year_max.get(),
+        rating_min: rating_min.get(),
+        page: None,
+        sort: sort.get(),
+        semantic_ratio: Some(semantic_ratio.get()).filter(|r| *r > 0.0),
+        movie_filters: movie_filters.get(),
+        book_filters: book_filters.get(),
+        series_filters: series_filters.get(),
+    });
+
+    // The keyword/semantic slider only appears when the server has an embedder
+    Effect::new(move |_| {
+        spawn_local(async move {
+            set_semantic_available.set(semantic_search_available().await.unwrap_or(false));
+        });
+    });
+
     // Track previous filter key to detect filter changes vs page changes
     let (prev_filter_key, set_prev_filter_key) = signal(String::new());
 
@@ -49,14 +148,20 @@ pub fn HomePage() -> impl IntoView {
         let q = debounced_query.get();
         let idx = index.get();
         let genres = selected_genres.get();
+        let tags = selected_tags.get();
         let y_min = year_min.get();
         let y_max = year_max.get();
         let r_min = rating_min.get();
         let p = page.get();
         let s = sort.get();
+        let ratio = semantic_ratio.get();
+        let movie = movie_filters.get();
+        let book = book_filters.get();
+        let series = series_filters.get();
 
         // Build a key from filter params (excluding page)
-        let filter_key = format!("{q}|{idx}|{genres:?}|{y_min:?}|{y_max:?}|{r_min:?}|{s:?}");
+        let filter_key =
+            format!("{q}|{idx}|{genres:?}|{tags:?}|{y_min:?}|{y_max:?}|{r_min:?}|{s:?}|{ratio}|{movie:?}|{book:?}|{series:?}");
         let prev = prev_filter_key.get_untracked();
         let filters_changed = !prev.is_empty() && prev != filter_key;
         set_prev_filter_key.set(filter_key);
@@ -68,14 +173,44 @@ pub fn HomePage() -> impl IntoView {
             return;
         }
 
-        if q.is_empty() && genres.is_empty() && y_min.is_none() && y_max.is_none() && r_min.is_none() {
+        if q.is_empty()
+            && genres.is_empty()
+            && tags.is_empty()
+            && y_min.is_none()
+            && y_max.is_none()
+            && r_min.is_none()
+            && movie == MovieFilters::default()
+            && book == BookFilters::default()
+            && series == SeriesFilters::default()
+        {
             set_results.set(None);
             return;
         }
 
+        // Syntax errors are shown inline by the search bar
+        if parse_query(&q).is_err() {
+            return;
+        }
+
         set_loading.set(true);
         spawn_local(async move {
-            let result = search_items(q, idx, Some(genres), y_min, y_max, r_min, Some(p), s).await;
+            let ratio = (ratio > 0.0).then_some(ratio);
+            let result = search_items(
+                q,
+                idx,
+                Some(genres),
+                Some(tags),
+                y_min,
+                y_max,
+                r_min,
+                Some(p),
+                s,
+                ratio,
+                Some(movie),
+                Some(book),
+                Some(series),
+            )
+            .await;
             set_results.set(Some(result));
             set_loading.set(false);
         });
@@ -88,8 +223,8 @@ pub fn HomePage() -> impl IntoView {
             match seed_data().await {
                 Ok(result) => {
                     set_seed_message.set(Some(format!(
-                        "投入完了: 映画 {} 件, 書籍 {} 件",
-                        result.movies_count, result.books_count
+                        "投入完了: 映画 {} 件, 書籍 {} 件, シリーズ {} 件",
+                        result.movies_count, result.books_count, result.series_count
                     )));
                     // Refresh facets
                     let idx = index.get_untracked();
@@ -149,6 +284,7 @@ pub fn HomePage() -> impl IntoView {
                 set_query=set_query
                 index=index
                 set_index=set_index
+                set_selected_genres=set_selected_genres
                 on_seed=on_seed
                 seeding=seeding
             />
@@ -162,6 +298,8 @@ pub fn HomePage() -> impl IntoView {
                     facets=facets.into()
                     selected_genres=selected_genres
                     set_selected_genres=set_selected_genres
+                    selected_tags=selected_tags
+                    set_selected_tags=set_selected_tags
                     year_min=year_min
                     set_year_min=set_year_min
                     year_max=year_max
@@ -169,14 +307,26 @@ pub fn HomePage() -> impl IntoView {
                     rating_min=rating_min
                     set_rating_min=set_rating_min
                     set_sort=set_sort
+                    semantic_available=semantic_available.into()
+                    semantic_ratio=semantic_ratio
+                    set_semantic_ratio=set_semantic_ratio
+                    movie_filters=movie_filters
+                    set_movie_filters=set_movie_filters
+                    book_filters=book_filters
+                    set_book_filters=set_book_filters
+                    series_filters=series_filters
+                    set_series_filters=set_series_filters
+                    period_years=Signal::derive(move || index.get() == "series")
                 />
                 <div class="results-section">
+                    <SaveSearch request=current_request/>
                     <SearchResults
                         results=results.into()
                         loading=loading.into()
                         query=query_signal
                         on_web_import=on_web_import
                         web_importing=web_importing.into()
+                        on_suggestion=move |q| set_query.set(q)
                     />
                     <Pagination
                         current_page=current_page
@@ -0,0 +1,90 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_router::hooks::use_navigate;
+
+use crate::api::{login, register};
+use crate::components::account::use_account;
+use crate::pages::admin_settings::input_value;
+
+#[component]
+pub fn LoginPage() -> impl IntoView {
+    let account = use_account();
+    let navigate = use_navigate();
+    let (username, set_username) = signal(String::new());
+    let (password, set_password) = signal(String::new());
+    let (message, set_message) = signal(Option::<String>::None);
+    let (busy, set_busy) = signal(false);
+
+    let submit = move |new_account: bool| {
+        let name = username.get_untracked();
+        let pass = password.get_untracked();
+        let navigate = navigate.clone();
+        set_busy.set(true);
+        set_message.set(None);
+        spawn_local(async move {
+            let result = if new_account {
+                register(name, pass).await
+            } else {
+                login(name, pass).await
+            };
+            set_busy.set(false);
+            match result {
+                Ok(_) => {
+                    account.reload();
+                    navigate("/watchlist", Default::default());
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+    let submit = StoredValue::new(submit);
+
+    view! {
+        <div class="admin-page login-page">
+            <h1 class="admin-title">"ログイン"</h1>
+
+            {move || message.get().map(|msg| view! {
+                <div class="seed-message">{msg}</div>
+            })}
+
+            <section class="admin-card">
+                <div class="admin-field">
+                    <label>"ユーザー名"</label>
+                    <input
+                        type="text"
+                        class="admin-input"
+                        autocomplete="username"
+                        prop:value=username
+                        on:input=move |ev| set_username.set(input_value(&ev))
+                    />
+                </div>
+                <div class="admin-field">
+                    <label>"パスワード (8文字以上)"</label>
+                    <input
+                        type="password"
+                        class="admin-input"
+                        autocomplete="current-password"
+                        prop:value=password
+                        on:input=move |ev| set_password.set(input_value(&ev))
+                    />
+                </div>
+                <div class="collection-actions">
+                    <button
+                        class="web-import-btn"
+                        disabled=busy
+                        on:click=move |_| submit.with_value(|f| f(false))
+                    >
+                        "ログイン"
+                    </button>
+                    <button
+                        class="page-btn"
+                        disabled=busy
+                        on:click=move |_| submit.with_value(|f| f(true))
+                    >
+                        "新規登録"
+                    </button>
+                </div>
+            </section>
+        </div>
+    }
+}
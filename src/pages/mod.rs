@@ -1,3 +1,14 @@
+pub mod admin_analytics;
+pub mod admin_collections;
+pub mod admin_reviews;
+pub mod admin_settings;
+pub mod admin_tags;
+pub mod collection;
 pub mod detail;
 pub mod home;
+pub mod login;
 pub mod not_found;
+pub mod notifications;
+pub mod person;
+pub mod saved_searches;
+pub mod watchlist;
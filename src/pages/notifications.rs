@@ -0,0 +1,73 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::api::{get_notifications, mark_notifications_read};
+use crate::components::account::use_account;
+
+#[component]
+pub fn NotificationsPage() -> impl IntoView {
+    let account = use_account();
+
+    let feed = Resource::new(
+        move || account.user.get(),
+        |_| async move { get_notifications().await },
+    );
+
+    // Opening the page counts as reading everything on it
+    Effect::new(move |_| {
+        if let Some(Ok(feed)) = feed.get() {
+            if feed.unread > 0 {
+                spawn_local(async move {
+                    if mark_notifications_read().await.is_ok() {
+                        account.unread.set(0);
+                    }
+                });
+            }
+        }
+    });
+
+    view! {
+        <div class="admin-page">
+            <h1 class="admin-title">"通知"</h1>
+            <Suspense fallback=move || view! { <div class="loading">"読み込み中..."</div> }>
+                {move || feed.get().map(|result| match result {
+                    Ok(feed) if feed.notifications.is_empty() => view! {
+                        <p class="watchlist-empty">
+                            "通知はまだありません。"
+                            <a href="/saved">"保存した検索"</a>
+                            "に新着があるとここに届きます"
+                        </p>
+                    }.into_any(),
+                    Ok(feed) => view! {
+                        <ul class="notification-list">
+                            {feed.notifications.into_iter().map(|n| {
+                                let more = n.new_count.saturating_sub(n.items.len());
+                                view! {
+                                    <li class=if n.read { "notification" } else { "notification unread" }>
+                                        <div class="notification-header">
+                                            <span class="notification-message">{n.message()}</span>
+                                            <a href=format!("/?saved={}", n.saved_search_id)>
+                                                {n.saved_search_name.clone()}
+                                            </a>
+                                        </div>
+                                        <ul class="notification-items">
+                                            {n.items.iter().map(|item| view! {
+                                                <li><a href=n.item_url(item.id)>{item.title.clone()}</a></li>
+                                            }).collect_view()}
+                                        </ul>
+                                        {(more > 0).then(|| view! {
+                                            <p class="admin-hint">{format!("ほか {more} 件")}</p>
+                                        })}
+                                    </li>
+                                }
+                            }).collect_view()}
+                        </ul>
+                    }.into_any(),
+                    Err(e) => view! {
+                        <div class="error">"エラー: "{e.to_string()}</div>
+                    }.into_any(),
+                })}
+            </Suspense>
+        </div>
+    }
+}
@@ -0,0 +1,85 @@
+use leptos::prelude::*;
+use leptos_router::hooks::use_params_map;
+
+use crate::api::get_person;
+use crate::components::result_card::ResultCard;
+use crate::model::person::PersonDetail;
+
+#[component]
+pub fn PersonPage() -> impl IntoView {
+    let params = use_params_map();
+
+    let id = move || {
+        params
+            .read()
+            .get("id")
+            .and_then(|id| id.parse::<i64>().ok())
+            .unwrap_or(0)
+    };
+
+    let person_resource = Resource::new(id, |id| async move { get_person(id).await });
+
+    view! {
+        <div class="detail-page">
+            <a href="/" class="back-link">"← 検索に戻る"</a>
+            <Suspense fallback=move || view! { <div class="loading">"読み込み中..."</div> }>
+                {move || {
+                    person_resource.get().map(|result| {
+                        match result {
+                            Ok(detail) => view! { <PersonProfile detail=detail/> }.into_any(),
+                            Err(e) => view! {
+                                <div class="error">"エラー: "{e.to_string()}</div>
+                            }.into_any(),
+                        }
+                    })
+                }}
+            </Suspense>
+        </div>
+    }
+}
+
+#[component]
+fn PersonProfile(detail: PersonDetail) -> impl IntoView {
+    let person = detail.person;
+    let reading = person
+        .name_reading
+        .as_ref()
+        .and_then(|r| r.hiragana.clone());
+    let aliases = (!person.aliases.is_empty()).then(|| person.aliases.join(" / "));
+    let works_count = detail.works.len();
+
+    view! {
+        <article class="detail-card">
+            <div class="detail-header">
+                <h1>{person.name.clone()}</h1>
+                {reading.map(|r| view! { <p class="detail-title-en">{r}</p> })}
+            </div>
+            <div class="detail-meta">
+                {person.roles.iter().map(|r| view! {
+                    <span class="detail-type">{r.label()}</span>
+                }).collect_view()}
+                <span class="detail-year">{format!("{works_count} 作品")}</span>
+            </div>
+            {aliases.map(|a| view! {
+                <div class="detail-info">
+                    <div class="info-row">
+                        <span class="info-label">"別名"</span>
+                        <span class="info-value">{a}</span>
+                    </div>
+                </div>
+            })}
+            {person.bio.clone().map(|bio| view! {
+                <div class="detail-description">
+                    <h2>"プロフィール"</h2>
+                    <p>{bio}</p>
+                </div>
+            })}
+        </article>
+        <section class="person-works">
+            <h2 class="related-title">"作品一覧"</h2>
+            <div class="results-grid">
+                {detail.works.into_iter().map(|hit| view! { <ResultCard hit=hit/> }).collect_view()}
+            </div>
+        </section>
+    }
+}
@@ -0,0 +1,108 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::api::{check_saved_search, delete_saved_search, list_saved_searches};
+use crate::components::account::use_account;
+
+#[component]
+pub fn SavedSearchesPage() -> impl IntoView {
+    let account = use_account();
+
+    view! {
+        <div class="admin-page">
+            <h1 class="admin-title">"保存した検索"</h1>
+            {move || match account.user.get() {
+                Some(_) => view! { <SavedSearches/> }.into_any(),
+                None => view! {
+                    <p class="watchlist-empty">
+                        <a href="/login">"ログイン"</a>
+                        "すると検索を保存できます"
+                    </p>
+                }.into_any(),
+            }}
+        </div>
+    }
+}
+
+#[component]
+fn SavedSearches() -> impl IntoView {
+    let account = use_account();
+    let (version, set_version) = signal(0u32);
+    let (message, set_message) = signal(Option::<String>::None);
+
+    let searches = Resource::new(
+        move || version.get(),
+        |_| async move { list_saved_searches().await },
+    );
+
+    let on_check = move |id: String| {
+        spawn_local(async move {
+            match check_saved_search(id).await {
+                Ok(Some(notification)) => {
+                    set_message.set(Some(notification.message()));
+                    account.reload();
+                }
+                Ok(None) => set_message.set(Some("新着はありません".to_string())),
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+            set_version.update(|v| *v += 1);
+        });
+    };
+
+    let on_delete = move |id: String| {
+        spawn_local(async move {
+            match delete_saved_search(id).await {
+                Ok(()) => set_version.update(|v| *v += 1),
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    view! {
+        {move || message.get().map(|msg| view! {
+            <div class="seed-message">{msg}</div>
+        })}
+        <p class="admin-hint">"保存した検索は定期的に再実行され、新しく一致した作品が通知に届きます"</p>
+        <Suspense fallback=move || view! { <div class="loading">"読み込み中..."</div> }>
+            {move || searches.get().map(|result| match result {
+                Ok(list) if list.is_empty() => view! {
+                    <p class="watchlist-empty">"検索結果の上の「この検索を保存」から保存できます"</p>
+                }.into_any(),
+                Ok(list) => view! {
+                    <ul class="saved-search-list">
+                        {list.into_iter().map(|search| {
+                            let check_id = search.id.clone();
+                            let delete_id = search.id.clone();
+                            view! {
+                                <li class="admin-card saved-search">
+                                    <div class="saved-search-header">
+                                        <a href=format!("/?saved={}", search.id) class="saved-search-name">
+                                            {search.name.clone()}
+                                        </a>
+                                        <span class="saved-search-summary">{search.summary()}</span>
+                                    </div>
+                                    <p class="admin-hint">
+                                        {format!("これまでに一致: {} 件", search.seen_ids.len())}
+                                        {search.webhook_url.clone().map(|url| format!(" ・ Webhook: {url}"))}
+                                    </p>
+                                    <div class="collection-member-actions">
+                                        <button class="page-btn" on:click=move |_| on_check(check_id.clone())>
+                                            "今すぐ確認"
+                                        </button>
+                                        <a class="page-btn" href=search.feed_path() target="_blank">"Atom フィード"</a>
+                                        <button class="page-btn" on:click=move |_| on_delete(delete_id.clone())>
+                                            "削除"
+                                        </button>
+                                    </div>
+                                </li>
+                            }
+                        }).collect_view()}
+                    </ul>
+                }.into_any(),
+                Err(e) => view! {
+                    <div class="error">"エラー: "{e.to_string()}</div>
+                }.into_any(),
+            })}
+        </Suspense>
+    }
+}
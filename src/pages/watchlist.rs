@@ -0,0 +1,246 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::api::{export_watchlist, import_watchlist, search_watchlist, set_watchlist_status};
+use crate::components::account::use_account;
+use crate::model::user::{ListStatus, WatchlistEntry};
+use crate::pages::admin_settings::{input_value, textarea_value};
+
+// Percent-encodes everything outside RFC 3986's unreserved set so the export
+// can be offered as a `data:` download without a separate endpoint
+fn data_url(json: &str) -> String {
+    let encoded: String = json
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect();
+    format!("data:application/json;charset=utf-8,{encoded}")
+}
+
+#[component]
+pub fn WatchlistPage() -> impl IntoView {
+    let account = use_account();
+
+    view! {
+        <div class="admin-page watchlist-page">
+            <h1 class="admin-title">"マイリスト"</h1>
+            {move || match account.user.get() {
+                Some(_) => view! { <Watchlist/> }.into_any(),
+                None => view! {
+                    <p class="watchlist-empty">
+                        <a href="/login">"ログイン"</a>
+                        "するとリストを作れます"
+                    </p>
+                }.into_any(),
+            }}
+        </div>
+    }
+}
+
+#[component]
+fn Watchlist() -> impl IntoView {
+    let account = use_account();
+    let (query, set_query) = signal(String::new());
+    let (index, set_index) = signal(Option::<String>::None);
+    let (status, set_status) = signal(Option::<ListStatus>::None);
+    let (genre, set_genre) = signal(Option::<String>::None);
+    // Bumped after every change so the list is fetched again
+    let (version, set_version) = signal(0u32);
+    let (message, set_message) = signal(Option::<String>::None);
+    let (export_json, set_export_json) = signal(Option::<String>::None);
+    let (import_json, set_import_json) = signal(String::new());
+
+    let list = Resource::new(
+        move || (query.get(), index.get(), status.get(), genre.get(), version.get()),
+        |(query, index, status, genre, _)| async move {
+            search_watchlist(query, index, status, genre).await
+        },
+    );
+
+    let change = move |entry: WatchlistEntry, status: Option<ListStatus>| {
+        spawn_local(async move {
+            match set_watchlist_status(entry.index, entry.item_id, status).await {
+                Ok(()) => {
+                    set_version.update(|v| *v += 1);
+                    account.reload();
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    let on_export = move |_| {
+        spawn_local(async move {
+            match export_watchlist().await {
+                Ok(json) => set_export_json.set(Some(json)),
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    let on_import = move |_| {
+        let json = import_json.get_untracked();
+        spawn_local(async move {
+            match import_watchlist(json).await {
+                Ok(result) => {
+                    let skipped = if result.skipped > 0 {
+                        format!(" ({} 件は見つからずスキップ)", result.skipped)
+                    } else {
+                        String::new()
+                    };
+                    set_message.set(Some(format!("{} 件を読み込みました{skipped}", result.imported)));
+                    set_import_json.set(String::new());
+                    set_version.update(|v| *v += 1);
+                    account.reload();
+                }
+                Err(e) => set_message.set(Some(format!("エラー: {e}"))),
+            }
+        });
+    };
+
+    view! {
+        {move || message.get().map(|msg| view! {
+            <div class="seed-message">{msg}</div>
+        })}
+
+        <div class="watchlist-controls">
+            <input
+                type="text"
+                class="admin-input"
+                placeholder="リスト内を検索"
+                prop:value=query
+                on:input=move |ev| set_query.set(input_value(&ev))
+            />
+            <div class="index-toggle">
+                {[(None, "すべて"), (Some("movies"), "映画"), (Some("books"), "書籍"), (Some("series"), "シリーズ")]
+                    .into_iter()
+                    .map(|(idx, label)| {
+                        let idx = idx.map(str::to_string);
+                        let current = idx.clone();
+                        view! {
+                            <button
+                                class=move || if index.get() == current { "toggle-btn active" } else { "toggle-btn" }
+                                on:click=move |_| set_index.set(idx.clone())
+                            >
+                                {label}
+                            </button>
+                        }
+                    })
+                    .collect_view()}
+            </div>
+            <div class="index-toggle">
+                {[(None, "すべて"), (Some(ListStatus::Want), "見たい・読みたい"), (Some(ListStatus::Done), "見た・読んだ")]
+                    .into_iter()
+                    .map(|(s, label)| view! {
+                        <button
+                            class=move || if status.get() == s { "toggle-btn active" } else { "toggle-btn" }
+                            on:click=move |_| set_status.set(s)
+                        >
+                            {label}
+                        </button>
+                    })
+                    .collect_view()}
+            </div>
+        </div>
+
+        <Suspense fallback=move || view! { <div class="loading">"読み込み中..."</div> }>
+            {move || list.get().map(|result| match result {
+                Ok(response) => {
+                    let empty = response.entries.is_empty();
+                    view! {
+                        <div class="facet-options watchlist-genres">
+                            <button
+                                class=move || if genre.get().is_none() { "genre-tag active" } else { "genre-tag" }
+                                on:click=move |_| set_genre.set(None)
+                            >
+                                "すべてのジャンル"
+                            </button>
+                            {response.genres.into_iter().map(|g| {
+                                let value = g.value.clone();
+                                let selected = g.value.clone();
+                                view! {
+                                    <button
+                                        class=move || if genre.get().as_ref() == Some(&selected) { "genre-tag active" } else { "genre-tag" }
+                                        on:click=move |_| set_genre.set(Some(value.clone()))
+                                    >
+                                        {format!("{} ({})", g.value, g.count)}
+                                    </button>
+                                }
+                            }).collect_view()}
+                        </div>
+                        {empty.then(|| view! {
+                            <p class="watchlist-empty">"リストに作品がありません"</p>
+                        })}
+                        <ul class="admin-list watchlist-entries">
+                            {response.entries.into_iter().map(|entry| {
+                                let other = if entry.status == ListStatus::Want {
+                                    ListStatus::Done
+                                } else {
+                                    ListStatus::Want
+                                };
+                                let move_entry = entry.clone();
+                                let remove_entry = entry.clone();
+                                view! {
+                                    <li>
+                                        <span>
+                                            <span class="watchlist-status">{entry.status.label(&entry.index)}</span>
+                                            <a href=entry.detail_url()>{entry.title.clone()}</a>
+                                            {format!(" {} ({})", entry.creator, entry.year)}
+                                        </span>
+                                        <span class="collection-member-actions">
+                                            <button
+                                                class="page-btn"
+                                                on:click=move |_| change(move_entry.clone(), Some(other))
+                                            >
+                                                {format!("「{}」へ", other.label(&entry.index))}
+                                            </button>
+                                            <button
+                                                class="page-btn"
+                                                on:click=move |_| change(remove_entry.clone(), None)
+                                            >
+                                                "削除"
+                                            </button>
+                                        </span>
+                                    </li>
+                                }
+                            }).collect_view()}
+                        </ul>
+                    }.into_any()
+                }
+                Err(e) => view! {
+                    <div class="error">"エラー: "{e.to_string()}</div>
+                }.into_any(),
+            })}
+        </Suspense>
+
+        <section class="admin-card watchlist-transfer">
+            <h2>"エクスポート / インポート"</h2>
+            <button class="page-btn" on:click=on_export>"JSON を書き出す"</button>
+            {move || export_json.get().map(|json| view! {
+                <a class="page-btn" href=data_url(&json) download="watchlist.json">
+                    "watchlist.json をダウンロード"
+                </a>
+            })}
+            <div class="admin-field">
+                <label>"書き出した JSON を貼り付けて読み込み"</label>
+                <textarea
+                    class="admin-textarea"
+                    rows="6"
+                    prop:value=import_json
+                    on:input=move |ev| set_import_json.set(textarea_value(&ev))
+                ></textarea>
+            </div>
+            <button
+                class="web-import-btn"
+                disabled=move || import_json.get().trim().is_empty()
+                on:click=on_import
+            >
+                "読み込む"
+            </button>
+        </section>
+    }
+}
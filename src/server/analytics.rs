@@ -0,0 +1,428 @@
+// Search analytics. `search_items` logs every search and the detail page logs
+// clicks on results; both are written in the background so they never slow a
+// search down. Client addresses are stored anonymised and events older than
+// ANALYTICS_RETENTION_DAYS are purged by a scheduled job (see `main.rs`).
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use meilisearch_sdk::documents::DocumentDeletionQuery;
+use meilisearch_sdk::search::Selectors;
+use meilisearch_sdk::settings::{FacetSortValue, FacetingSettings};
+use serde::Serialize;
+
+use crate::model::analytics::{
+    anonymize_ip, ctr_by_position, reformulations, AnalyticsReport, ClickEvent, SearchEvent,
+    REPORT_ROWS,
+};
+use crate::model::search::{FacetValue, Suggestion, SuggestionKind};
+use crate::server::config::config;
+use crate::server::filter::{Filter, Value};
+use crate::server::meilisearch::{get_client, get_index};
+use crate::server::telemetry;
+use crate::server::users::{new_token, now};
+
+pub const SEARCH_EVENTS_INDEX: &str = "search_events";
+pub const CLICK_EVENTS_INDEX: &str = "click_events";
+const DAY_SECS: u64 = 24 * 60 * 60;
+// Recent searches scanned for reformulations
+const SESSION_SCAN: usize = 1000;
+const POPULAR_SUGGESTIONS: usize = 3;
+const POPULAR_CANDIDATES: usize = 10;
+const POPULAR_MIN_CLIENTS: usize = 3;
+const MAX_POSITION: usize = 1000;
+
+static CONFIGURED: AtomicBool = AtomicBool::new(false);
+
+// `analytics.retention_days`; 0 turns logging off
+pub fn retention_days() -> u64 {
+    config().analytics.retention_days
+}
+
+pub fn is_enabled() -> bool {
+    retention_days() > 0
+}
+
+fn since(days: u64) -> Filter {
+    Filter::Range {
+        field: "at",
+        min: Some(Value::Int(now().saturating_sub(days * DAY_SECS) as i64)),
+        max: None,
+    }
+}
+
+async fn configure_indexes() -> Result<(), String> {
+    // Facet counts feed the report, so they are ordered by count and not capped at 100
+    let faceting = FacetingSettings {
+        max_values_per_facet: 1000,
+        sort_facet_values_by: Some(BTreeMap::from([("*".to_string(), FacetSortValue::Count)])),
+    };
+
+    let searches = get_index(SEARCH_EVENTS_INDEX);
+    searches
+        .set_filterable_attributes(["at", "index", "query", "hits", "shown", "page", "client"])
+        .await
+        .map_err(|e| format!("Failed to set filterable attributes: {e}"))?;
+    searches
+        .set_sortable_attributes(["at", "latency_ms"])
+        .await
+        .map_err(|e| format!("Failed to set sortable attributes: {e}"))?;
+    searches
+        .set_faceting(&faceting)
+        .await
+        .map_err(|e| format!("Failed to set faceting: {e}"))?;
+
+    let clicks = get_index(CLICK_EVENTS_INDEX);
+    clicks
+        .set_filterable_attributes(["at", "index", "search_id", "position"])
+        .await
+        .map_err(|e| format!("Failed to set filterable attributes: {e}"))?;
+    clicks
+        .set_faceting(&faceting)
+        .await
+        .map_err(|e| format!("Failed to set faceting: {e}"))?;
+    Ok(())
+}
+
+// Queues the event without waiting for Meilisearch to index it
+fn log<T: Serialize + Send + Sync + 'static>(index_name: &'static str, event: T) {
+    actix_web::rt::spawn(telemetry::in_current_request(async move {
+        if !CONFIGURED.swap(true, Ordering::SeqCst) {
+            if let Err(e) = configure_indexes().await {
+                CONFIGURED.store(false, Ordering::SeqCst);
+                tracing::warn!(error = %e, "analytics indexes not configured");
+                return;
+            }
+        }
+        if let Err(e) = get_index(index_name)
+            .add_documents(&[event], Some("id"))
+            .await
+        {
+            tracing::warn!(error = %e, index = index_name, "analytics event not logged");
+        }
+    }));
+}
+
+pub fn new_event_id() -> String {
+    new_token()[..16].to_string()
+}
+
+// The anonymised address of the current server function call
+pub async fn client() -> String {
+    let Ok(request) = leptos_actix::extract::<actix_web::HttpRequest>().await else {
+        return anonymize_ip("");
+    };
+    let trusted: Vec<IpAddr> = config()
+        .analytics
+        .trusted_proxies
+        .iter()
+        .filter_map(|p| p.parse().ok())
+        .collect();
+    let forwarded_for = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok());
+    let addr = client_addr(request.peer_addr().map(|a| a.ip()), forwarded_for, &trusted);
+    anonymize_ip(&addr.map(|ip| ip.to_string()).unwrap_or_default())
+}
+
+// The peer, unless it is a trusted proxy: then the nearest X-Forwarded-For
+// entry that is not one. Entries further left were written by the client and
+// cannot be believed, or one visitor could pass for many.
+fn client_addr(
+    peer: Option<IpAddr>,
+    forwarded_for: Option<&str>,
+    trusted: &[IpAddr],
+) -> Option<IpAddr> {
+    let peer = peer?;
+    if !trusted.contains(&peer) {
+        return Some(peer);
+    }
+    let mut last = peer;
+    for entry in forwarded_for.unwrap_or_default().rsplit(',') {
+        match entry.trim().parse::<IpAddr>() {
+            Ok(ip) if trusted.contains(&ip) => last = ip,
+            Ok(ip) => return Some(ip),
+            Err(_) => break,
+        }
+    }
+    Some(last)
+}
+
+pub fn log_search(event: SearchEvent) {
+    if is_enabled() {
+        log(SEARCH_EVENTS_INDEX, event);
+    }
+}
+
+pub fn log_click(search_id: String, index: String, item_id: i64, position: usize) {
+    if !is_enabled() || search_id.is_empty() || !(1..=MAX_POSITION).contains(&position) {
+        return;
+    }
+    log(
+        CLICK_EVENTS_INDEX,
+        ClickEvent {
+            id: new_event_id(),
+            at: now(),
+            search_id,
+            index,
+            item_id,
+            position,
+        },
+    );
+}
+
+// Counts of each requested facet over the matching events. Totals are summed
+// from these too, since the hit estimate stops at Meilisearch's maxTotalHits.
+async fn facet_counts(
+    index_name: &str,
+    filter: Filter,
+    facets: &[&str],
+) -> HashMap<String, Vec<FacetValue>> {
+    let index = get_index(index_name);
+    let expression = filter.to_expression();
+    let mut search = index.search();
+    search.with_limit(0);
+    if let Some(ref f) = expression {
+        search.with_filter(f);
+    }
+    search.with_facets(meilisearch_sdk::search::Selectors::Some(facets));
+
+    let Ok(results) = search.execute::<serde_json::Value>().await else {
+        return HashMap::new();
+    };
+    results
+        .facet_distribution
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(facet, values)| {
+            let mut values: Vec<FacetValue> = values
+                .into_iter()
+                .filter(|(value, _)| !value.is_empty())
+                .map(|(value, count)| FacetValue { value, count })
+                .collect();
+            values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+            (facet, values)
+        })
+        .collect()
+}
+
+fn total(counts: &HashMap<String, Vec<FacetValue>>, facet: &str) -> usize {
+    counts.get(facet).into_iter().flatten().map(|fv| fv.count).sum()
+}
+
+async fn events(filter: Filter, sort: &str, limit: usize) -> Vec<SearchEvent> {
+    let index = get_index(SEARCH_EVENTS_INDEX);
+    let expression = filter.to_expression();
+    let sort = [sort];
+    let mut search = index.search();
+    search.with_limit(limit);
+    if let Some(ref f) = expression {
+        search.with_filter(f);
+    }
+    search.with_sort(&sort);
+    match search.execute::<SearchEvent>().await {
+        Ok(results) => results.hits.into_iter().map(|h| h.result).collect(),
+        Err(_) => vec![],
+    }
+}
+
+fn top(mut counts: HashMap<String, Vec<FacetValue>>, facet: &str) -> Vec<FacetValue> {
+    let mut values = counts.remove(facet).unwrap_or_default();
+    values.truncate(REPORT_ROWS);
+    values
+}
+
+pub async fn report(days: u64) -> AnalyticsReport {
+    let window = since(days);
+    // Query counts only look at first pages, so paging through results does
+    // not count the same query again
+    let first_pages = Filter::And(vec![window.clone(), Filter::eq("page", 1)]);
+
+    let shown = facet_counts(SEARCH_EVENTS_INDEX, window.clone(), &["shown"]).await;
+    let queries =
+        facet_counts(SEARCH_EVENTS_INDEX, first_pages.clone(), &["query", "page"]).await;
+    let zero = Filter::And(vec![first_pages, Filter::eq("hits", 0)]);
+    let zero_queries = facet_counts(SEARCH_EVENTS_INDEX, zero, &["query"]).await;
+    let positions = facet_counts(CLICK_EVENTS_INDEX, window.clone(), &["position"]).await;
+
+    let slow_searches = events(window.clone(), "latency_ms:desc", REPORT_ROWS).await;
+    let recent = events(window, "at:desc", SESSION_SCAN).await;
+
+    AnalyticsReport {
+        days,
+        searches: total(&queries, "page"),
+        clicks: total(&positions, "position"),
+        top_queries: top(queries, "query"),
+        zero_result_queries: top(zero_queries, "query"),
+        ctr: ctr_by_position(
+            shown.get("shown").map(Vec::as_slice).unwrap_or_default(),
+            positions.get("position").map(Vec::as_slice).unwrap_or_default(),
+        ),
+        slow_searches,
+        reformulations: reformulations(&recent),
+        retention_days: retention_days(),
+    }
+}
+
+// Queries other users ran successfully that start with `prefix`, for autocomplete.
+// Only queries searched from POPULAR_MIN_CLIENTS different (anonymised) clients
+// are offered, so one visitor's searches never show up for everyone else.
+pub async fn popular_queries(index_name: &str, prefix: &str) -> Vec<Suggestion> {
+    if !is_enabled() {
+        return vec![];
+    }
+    let successful = Filter::And(vec![
+        Filter::eq("index", index_name),
+        Filter::Range {
+            field: "hits",
+            min: Some(Value::Int(1)),
+            max: None,
+        },
+    ]);
+    let index = get_index(SEARCH_EVENTS_INDEX);
+    let expression = successful.to_expression().unwrap_or_default();
+    let mut query = index.facet_search("query");
+    query.with_facet_query(prefix);
+    query.with_filter(&expression);
+    let Ok(response) = query.execute().await else {
+        return vec![];
+    };
+    // Fewer searches than clients needed cannot pass, so they are not counted
+    let candidates: Vec<(String, usize)> = response
+        .facet_hits
+        .into_iter()
+        .filter(|hit| hit.value != prefix && hit.count >= POPULAR_MIN_CLIENTS)
+        .take(POPULAR_CANDIDATES)
+        .map(|hit| (hit.value, hit.count))
+        .collect();
+    if candidates.is_empty() {
+        return vec![];
+    }
+
+    let filters: Vec<String> = candidates
+        .iter()
+        .map(|(value, _)| {
+            Filter::And(vec![successful.clone(), Filter::eq("query", value.as_str())])
+                .to_expression()
+                .unwrap_or_default()
+        })
+        .collect();
+    let client = get_client();
+    let mut searches = client.multi_search();
+    for filter in &filters {
+        let mut search = index.search();
+        search
+            .with_filter(filter)
+            .with_limit(0)
+            .with_facets(Selectors::Some(&["client"]));
+        searches.with_search_query(search);
+    }
+    let clients: Vec<usize> = match searches.execute::<serde_json::Value>().await {
+        Ok(response) => response
+            .results
+            .into_iter()
+            .map(|results| {
+                results
+                    .facet_distribution
+                    .and_then(|facets| facets.get("client").map(|values| values.len()))
+                    .unwrap_or(0)
+            })
+            .collect(),
+        Err(_) => return vec![],
+    };
+    widely_searched(candidates, &clients)
+}
+
+fn widely_searched(candidates: Vec<(String, usize)>, clients: &[usize]) -> Vec<Suggestion> {
+    candidates
+        .into_iter()
+        .zip(clients)
+        .filter(|(_, clients)| **clients >= POPULAR_MIN_CLIENTS)
+        .take(POPULAR_SUGGESTIONS)
+        .map(|((value, count), _)| Suggestion {
+            kind: SuggestionKind::Popular,
+            value,
+            count: Some(count),
+        })
+        .collect()
+}
+
+// Deletes events older than the retention period. Returns the number of
+// indexes a deletion was queued for.
+pub async fn purge() -> Result<usize, String> {
+    let days = retention_days();
+    if days == 0 {
+        return Ok(0);
+    }
+    let expired = Filter::Range {
+        field: "at",
+        min: None,
+        max: Some(Value::Int(now().saturating_sub(days * DAY_SECS) as i64)),
+    }
+    .to_expression()
+    .unwrap_or_default();
+
+    let mut purged = 0;
+    for index_name in [SEARCH_EVENTS_INDEX, CLICK_EVENTS_INDEX] {
+        let index = get_index(index_name);
+        // Nothing to purge before the first event created the index
+        if index.get_stats().await.is_err() {
+            continue;
+        }
+        DocumentDeletionQuery::new(&index)
+            .with_filter(&expired)
+            .execute::<serde_json::Value>()
+            .await
+            .map_err(|e| format!("Failed to purge {index_name}: {e}"))?;
+        purged += 1;
+    }
+    Ok(purged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwarded_addresses_count_only_behind_trusted_proxies() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let proxy = [ip("10.0.0.2")];
+        // Straight from a visitor, the header is whatever they made up
+        assert_eq!(
+            client_addr(Some(ip("203.0.113.7")), Some("198.51.100.1"), &proxy),
+            Some(ip("203.0.113.7"))
+        );
+        // Behind the proxy, the entry it appended wins over a forged one
+        assert_eq!(
+            client_addr(Some(ip("10.0.0.2")), Some("198.51.100.1, 203.0.113.7"), &proxy),
+            Some(ip("203.0.113.7"))
+        );
+        assert_eq!(
+            client_addr(Some(ip("10.0.0.2")), None, &proxy),
+            Some(ip("10.0.0.2"))
+        );
+        assert_eq!(client_addr(None, Some("203.0.113.7"), &proxy), None);
+    }
+
+    #[test]
+    fn only_queries_from_enough_clients_are_suggested() {
+        let candidates = vec![
+            ("dune".to_string(), 40),
+            ("my private query".to_string(), 12),
+            ("dune part two".to_string(), 5),
+        ];
+        let values: Vec<(String, Option<usize>)> = widely_searched(candidates, &[9, 1, 3])
+            .into_iter()
+            .map(|s| (s.value, s.count))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("dune".to_string(), Some(40)),
+                ("dune part two".to_string(), Some(5))
+            ]
+        );
+    }
+}
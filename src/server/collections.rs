@@ -0,0 +1,198 @@
+// Collections group movies and books into series or curated lists. They live
+// in their own `collections` index so they can be searched like any item.
+
+use crate::model::book::Book;
+use crate::model::collection::{
+    Collection, CollectionDetail, CollectionKind, CollectionMember, SeriesPosition,
+};
+use crate::model::movie::Movie;
+use crate::model::search::SearchHit;
+use crate::server::filter::{Filter, Value};
+use crate::server::meilisearch::{get_client, get_index, is_index_not_found, new_document_id};
+
+pub const COLLECTIONS_INDEX: &str = "collections";
+const LIST_LIMIT: usize = 200;
+// Collections shown above search results
+const MATCH_LIMIT: usize = 3;
+
+fn bundled_collections() -> Vec<Collection> {
+    let data: &str = include_str!("../../seed/collections.json");
+    serde_json::from_str(data).unwrap_or_default()
+}
+
+async fn configure_collections_index() -> Result<(), String> {
+    let index = get_index(COLLECTIONS_INDEX);
+
+    index
+        .set_searchable_attributes(["name", "description", "member_titles"])
+        .await
+        .map_err(|e| format!("Failed to set searchable attributes: {e}"))?;
+
+    index
+        .set_filterable_attributes(["member_keys", "kind"])
+        .await
+        .map_err(|e| format!("Failed to set filterable attributes: {e}"))?;
+
+    index
+        .set_sortable_attributes(["id"])
+        .await
+        .map_err(|e| format!("Failed to set sortable attributes: {e}"))?;
+
+    Ok(())
+}
+
+async fn member_hit(member: &CollectionMember) -> Option<SearchHit> {
+    let index = get_index(&member.index);
+    let id = member.id.to_string();
+    match member.index.as_str() {
+        "movies" => index
+            .get_document::<Movie>(&id)
+            .await
+            .ok()
+            .map(SearchHit::from),
+        "books" => index
+            .get_document::<Book>(&id)
+            .await
+            .ok()
+            .map(SearchHit::from),
+        _ => None,
+    }
+}
+
+async fn query(
+    text: &str,
+    filter: Option<Filter>,
+    limit: usize,
+) -> Result<Vec<Collection>, String> {
+    let index = get_index(COLLECTIONS_INDEX);
+    let expression = filter.and_then(|f| f.to_expression());
+    let mut search = index.search();
+    search.with_query(text);
+    search.with_limit(limit);
+    if let Some(ref f) = expression {
+        search.with_filter(f);
+    }
+    if text.is_empty() {
+        search.with_sort(&["id:asc"]);
+    }
+
+    match search.execute::<Collection>().await {
+        Ok(results) => Ok(results.hits.into_iter().map(|h| h.result).collect()),
+        // Nothing has been saved or seeded yet
+        Err(e) if is_index_not_found(&e) => Ok(vec![]),
+        Err(e) => Err(format!("Failed to load collections: {e}")),
+    }
+}
+
+pub async fn list() -> Result<Vec<Collection>, String> {
+    query("", None, LIST_LIMIT).await
+}
+
+pub async fn search(text: &str) -> Vec<Collection> {
+    query(text, None, MATCH_LIMIT).await.unwrap_or_default()
+}
+
+pub async fn get(id: i64) -> Result<CollectionDetail, String> {
+    let collection: Collection = get_index(COLLECTIONS_INDEX)
+        .get_document(&id.to_string())
+        .await
+        .map_err(|e| format!("Collection not found: {e}"))?;
+
+    // Members deleted since the collection was saved are skipped
+    let mut members = Vec::new();
+    for member in &collection.members {
+        if let Some(hit) = member_hit(member).await {
+            members.push(hit);
+        }
+    }
+    set_positions(std::slice::from_ref(&collection), &mut members);
+
+    Ok(CollectionDetail {
+        collection,
+        members,
+    })
+}
+
+pub async fn save(mut collection: Collection) -> Result<Collection, String> {
+    collection.validate()?;
+
+    if collection.id == 0 {
+        collection.id = new_document_id();
+    }
+
+    collection.member_titles.clear();
+    for member in &collection.members {
+        if let Some(hit) = member_hit(member).await {
+            collection.member_titles.push(hit.title);
+            collection.member_titles.extend(hit.title_en);
+        }
+    }
+    collection.member_keys = collection.members.iter().map(|m| m.key()).collect();
+
+    let client = get_client();
+    let task = get_index(COLLECTIONS_INDEX)
+        .add_documents(&[collection.clone()], Some("id"))
+        .await
+        .map_err(|e| format!("Failed to store collection: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for collection: {e}"))?;
+
+    configure_collections_index().await?;
+
+    Ok(collection)
+}
+
+pub async fn delete(id: i64) -> Result<(), String> {
+    get_index(COLLECTIONS_INDEX)
+        .delete_document(&id.to_string())
+        .await
+        .map_err(|e| format!("Failed to delete collection: {e}"))?;
+    Ok(())
+}
+
+pub async fn seed() -> Result<usize, String> {
+    let collections = bundled_collections();
+    let count = collections.len();
+    for collection in collections {
+        save(collection).await?;
+    }
+    Ok(count)
+}
+
+// Fills `series` on hits that belong to a series, using the first one found
+pub fn set_positions(collections: &[Collection], hits: &mut [SearchHit]) {
+    for hit in hits.iter_mut() {
+        hit.series = collections
+            .iter()
+            .filter(|c| c.kind == CollectionKind::Series)
+            .find_map(|c| {
+                c.position_of(&hit.index, hit.id)
+                    .map(|position| SeriesPosition {
+                        collection_id: c.id,
+                        collection_name: c.name.clone(),
+                        position,
+                        total: c.members.len(),
+                    })
+            });
+    }
+}
+
+// Looks up the series for a page of hits; a failure just leaves them unannotated
+pub async fn annotate(hits: &mut [SearchHit]) {
+    if hits.is_empty() {
+        return;
+    }
+    let keys: Vec<Value> = hits
+        .iter()
+        .map(|h| format!("{}:{}", h.index, h.id).into())
+        .collect();
+    let filter = Filter::And(vec![
+        Filter::In("member_keys", keys),
+        Filter::eq("kind", "series"),
+    ]);
+    if let Ok(collections) = query("", Some(filter), LIST_LIMIT).await {
+        set_positions(&collections, hits);
+    }
+}
@@ -0,0 +1,621 @@
+// Application settings. Defaults are overridden by a TOML file (`--config`,
+// CONFIG_FILE or `./config.toml` when present), which is overridden in turn by
+// environment variables, so `docker-compose.yml` and `.env` keep working. The
+// result is validated once at startup and read everywhere through `config()`.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use meilisearch_sdk::settings::Embedder;
+use serde::{Deserialize, Serialize};
+
+use crate::server::normalize::KanaFolding;
+
+pub const DEFAULT_MASTER_KEY: &str = "masterKey";
+const DEFAULT_FILE: &str = "config.toml";
+// Meilisearch itself refuses shorter master keys in production
+const MASTER_KEY_MIN_LEN: usize = 16;
+const REDACTED: &str = "********";
+
+// Every index the app uses, by the name the code refers to it with. `[indexes]`
+// maps any of them to a different Meilisearch uid, e.g. to share an instance.
+pub const INDEX_NAMES: [&str; 18] = [
+    "movies",
+    "books",
+    "series",
+    "web",
+    "people",
+    "collections",
+    "users",
+    "sessions",
+    "watchlist",
+    "reviews",
+    "user_tags",
+    "tag_rules",
+    "saved_searches",
+    "notifications",
+    "search_settings",
+    "search_settings_preview",
+    "search_events",
+    "click_events",
+];
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    #[default]
+    Development,
+    Production,
+}
+
+// See `server::embedding` for what each mode does
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SemanticSearch {
+    #[default]
+    Off,
+    Local,
+    Custom,
+    Fake,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+    Pretty,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub environment: Environment,
+    pub meilisearch: MeilisearchConfig,
+    pub searxng: SearxngConfig,
+    pub indexes: BTreeMap<String, String>,
+    pub search: SearchConfig,
+    pub features: Features,
+    pub embedder: EmbedderConfig,
+    pub saved_searches: SavedSearchesConfig,
+    pub analytics: AnalyticsConfig,
+    pub users: UsersConfig,
+    pub log: LogConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MeilisearchConfig {
+    pub url: String,
+    // Empty for an instance running without authentication
+    pub master_key: String,
+    pub timeout_secs: u64,
+    // Run requests with search-only and admin keys made from the master key
+    pub scoped_keys: bool,
+    // Scoped keys are replaced after this many days and expire after twice
+    // that; 0 keeps them until revoked
+    pub key_rotation_days: u64,
+}
+
+impl Default for MeilisearchConfig {
+    fn default() -> Self {
+        MeilisearchConfig {
+            url: "http://localhost:7700".to_string(),
+            master_key: DEFAULT_MASTER_KEY.to_string(),
+            timeout_secs: 10,
+            scoped_keys: true,
+            key_rotation_days: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearxngConfig {
+    pub url: String,
+    pub timeout_secs: u64,
+}
+
+impl Default for SearxngConfig {
+    fn default() -> Self {
+        SearxngConfig {
+            url: "http://searxng:8080".to_string(),
+            timeout_secs: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    pub hits_per_page: usize,
+    pub reviews_per_page: usize,
+    pub kana_folding: KanaFolding,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            hits_per_page: 12,
+            reviews_per_page: 5,
+            kana_folding: KanaFolding::Off,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Features {
+    pub web_fallback: bool,
+    pub semantic_search: SemanticSearch,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbedderConfig {
+    // Hugging Face model for `local`, the built-in default when unset
+    pub model: Option<String>,
+    // Embedder object in Meilisearch's `embedders` format for `custom`
+    pub config: Option<Embedder>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SavedSearchesConfig {
+    // 0 turns the re-run job off
+    pub interval_secs: u64,
+    pub webhook_timeout_secs: u64,
+}
+
+impl Default for SavedSearchesConfig {
+    fn default() -> Self {
+        SavedSearchesConfig {
+            interval_secs: 60 * 60,
+            webhook_timeout_secs: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalyticsConfig {
+    // 0 turns search logging off
+    pub retention_days: u64,
+    // Addresses of reverse proxies whose X-Forwarded-For is believed; other
+    // peers are counted by their own address
+    pub trusted_proxies: Vec<String>,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        AnalyticsConfig {
+            retention_days: 90,
+            trusted_proxies: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct UsersConfig {
+    // Usernames allowed into the admin pages and their server functions, once
+    // their account was made with `admin create`; they cannot register
+    pub admins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub format: LogFormat,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Str,
+    // Enum values, matched case-insensitively like before the config file existed
+    Name,
+    Bool,
+    Int,
+    Json,
+    // Comma-separated
+    List,
+}
+
+// Environment variables and the settings they override
+const ENV_OVERRIDES: [(&str, &str, Kind); 15] = [
+    ("APP_ENV", "environment", Kind::Name),
+    ("MEILI_URL", "meilisearch.url", Kind::Str),
+    ("MEILI_MASTER_KEY", "meilisearch.master_key", Kind::Str),
+    ("SEARXNG_URL", "searxng.url", Kind::Str),
+    ("HITS_PER_PAGE", "search.hits_per_page", Kind::Int),
+    ("QUERY_KANA_FOLDING", "search.kana_folding", Kind::Name),
+    ("WEB_FALLBACK", "features.web_fallback", Kind::Bool),
+    ("SEMANTIC_SEARCH", "features.semantic_search", Kind::Name),
+    ("EMBEDDER_MODEL", "embedder.model", Kind::Str),
+    ("EMBEDDER_CONFIG", "embedder.config", Kind::Json),
+    (
+        "SAVED_SEARCH_INTERVAL_SECS",
+        "saved_searches.interval_secs",
+        Kind::Int,
+    ),
+    (
+        "ANALYTICS_RETENTION_DAYS",
+        "analytics.retention_days",
+        Kind::Int,
+    ),
+    ("TRUSTED_PROXIES", "analytics.trusted_proxies", Kind::List),
+    ("ADMIN_USERNAMES", "users.admins", Kind::List),
+    ("LOG_FORMAT", "log.format", Kind::Name),
+];
+
+fn env_value(var: &str, raw: &str, kind: Kind) -> Result<toml::Value, String> {
+    match kind {
+        Kind::Str => Ok(toml::Value::String(raw.to_string())),
+        Kind::Name => Ok(toml::Value::String(raw.to_lowercase())),
+        Kind::Bool => match raw.to_lowercase().as_str() {
+            "1" | "true" | "on" | "yes" => Ok(toml::Value::Boolean(true)),
+            "0" | "false" | "off" | "no" => Ok(toml::Value::Boolean(false)),
+            _ => Err(format!("{var} must be true or false, got `{raw}`")),
+        },
+        Kind::Int => raw
+            .parse::<i64>()
+            .map(toml::Value::Integer)
+            .map_err(|_| format!("{var} must be a whole number, got `{raw}`")),
+        Kind::Json => serde_json::from_str::<serde_json::Value>(raw)
+            .map_err(|e| e.to_string())
+            .and_then(|json| toml::Value::try_from(json).map_err(|e| e.to_string()))
+            .map_err(|e| format!("{var} is not a valid JSON object: {e}")),
+        Kind::List => Ok(toml::Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| toml::Value::String(item.to_string()))
+                .collect(),
+        )),
+    }
+}
+
+fn set_path(table: &mut toml::Table, path: &str, value: toml::Value) {
+    match path.split_once('.') {
+        Some((section, key)) => {
+            let entry = table
+                .entry(section)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if !entry.is_table() {
+                *entry = toml::Value::Table(toml::Table::new());
+            }
+            if let toml::Value::Table(inner) = entry {
+                set_path(inner, key, value);
+            }
+        }
+        None => {
+            table.insert(path.to_string(), value);
+        }
+    }
+}
+
+impl AppConfig {
+    // From `path`, CONFIG_FILE or `./config.toml`, then the environment
+    pub fn load(path: Option<&Path>) -> Result<AppConfig, String> {
+        let path = path.map(Path::to_path_buf).or_else(|| {
+            std::env::var("CONFIG_FILE")
+                .ok()
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from)
+        });
+        let file = match path {
+            Some(path) => Some(
+                std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {e}", path.display()))?,
+            ),
+            None => std::fs::read_to_string(DEFAULT_FILE).ok(),
+        };
+        AppConfig::from_sources(file.as_deref(), |var| std::env::var(var).ok())
+            .map_err(|problems| format!("Invalid configuration:\n  - {}", problems.join("\n  - ")))
+    }
+
+    // Empty variables count as unset, as Compose passes `${VAR:-}` through
+    pub fn from_sources(
+        file: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<AppConfig, Vec<String>> {
+        let mut table = match file {
+            Some(text) => toml::from_str::<toml::Table>(text).map_err(|e| vec![e.to_string()])?,
+            None => toml::Table::new(),
+        };
+        let mut problems = Vec::new();
+        for (var, path, kind) in ENV_OVERRIDES {
+            let Some(raw) = env(var)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+            else {
+                continue;
+            };
+            match env_value(var, &raw, kind) {
+                Ok(value) => set_path(&mut table, path, value),
+                Err(e) => problems.push(e),
+            }
+        }
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        let config: AppConfig = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| vec![e.message().to_string()])?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+
+        for (name, url) in [
+            ("meilisearch.url", &self.meilisearch.url),
+            ("searxng.url", &self.searxng.url),
+        ] {
+            match reqwest::Url::parse(url) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+                _ => problems.push(format!("{name} must be an http(s) URL, got `{url}`")),
+            }
+        }
+
+        if self.environment == Environment::Production {
+            let key = &self.meilisearch.master_key;
+            if key.is_empty() || key == DEFAULT_MASTER_KEY {
+                problems.push(
+                    "meilisearch.master_key must be set to a secret key in production, not the default"
+                        .to_string(),
+                );
+            } else if key.chars().count() < MASTER_KEY_MIN_LEN {
+                problems.push(format!(
+                    "meilisearch.master_key must be at least {MASTER_KEY_MIN_LEN} characters in production"
+                ));
+            }
+        }
+
+        for (name, secs) in [
+            ("meilisearch.timeout_secs", self.meilisearch.timeout_secs),
+            ("searxng.timeout_secs", self.searxng.timeout_secs),
+            (
+                "saved_searches.webhook_timeout_secs",
+                self.saved_searches.webhook_timeout_secs,
+            ),
+        ] {
+            if secs == 0 {
+                problems.push(format!("{name} must be greater than 0"));
+            }
+        }
+        for proxy in &self.analytics.trusted_proxies {
+            if proxy.parse::<std::net::IpAddr>().is_err() {
+                problems.push(format!(
+                    "analytics.trusted_proxies must list IP addresses, got `{proxy}`"
+                ));
+            }
+        }
+        if !(1..=100).contains(&self.search.hits_per_page) {
+            problems.push("search.hits_per_page must be between 1 and 100".to_string());
+        }
+        if !(1..=50).contains(&self.search.reviews_per_page) {
+            problems.push("search.reviews_per_page must be between 1 and 50".to_string());
+        }
+        if self.features.semantic_search == SemanticSearch::Custom && self.embedder.config.is_none()
+        {
+            problems
+                .push("features.semantic_search = \"custom\" needs embedder.config".to_string());
+        }
+
+        let mut uids = HashSet::new();
+        for name in INDEX_NAMES {
+            let uid = self.index_uid(name);
+            if !uids.insert(uid) {
+                problems.push(format!("indexes: `{uid}` is used for more than one index"));
+            }
+        }
+        for (name, uid) in &self.indexes {
+            if !INDEX_NAMES.contains(&name.as_str()) {
+                problems.push(format!("indexes: unknown index `{name}`"));
+            }
+            let plain = uid
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+            if uid.is_empty() || !plain {
+                problems.push(format!(
+                    "indexes.{name} must be letters, digits, `-` or `_`, got `{uid}`"
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    // The Meilisearch uid of an index, which is its name unless `[indexes]` says otherwise
+    pub fn index_uid<'a>(&'a self, name: &'a str) -> &'a str {
+        self.indexes.get(name).map_or(name, String::as_str)
+    }
+
+    pub fn meilisearch_timeout(&self) -> Duration {
+        Duration::from_secs(self.meilisearch.timeout_secs)
+    }
+
+    pub fn searxng_timeout(&self) -> Duration {
+        Duration::from_secs(self.searxng.timeout_secs)
+    }
+
+    // The configuration as TOML for `--print-config`, without the master key
+    // or the embedder's API key
+    pub fn to_toml(&self) -> String {
+        let mut shown = self.clone();
+        if !shown.meilisearch.master_key.is_empty() {
+            shown.meilisearch.master_key = REDACTED.to_string();
+        }
+        if let Some(api_key) = shown
+            .embedder
+            .config
+            .as_mut()
+            .and_then(|e| e.api_key.as_mut())
+        {
+            *api_key = REDACTED.to_string();
+        }
+        toml::to_string_pretty(&shown)
+            .unwrap_or_else(|e| format!("# Failed to print configuration: {e}\n"))
+    }
+}
+
+static CONFIG: OnceLock<AppConfig> = OnceLock::new();
+
+// Installs the configuration loaded at startup; only the first call counts
+pub fn init(config: AppConfig) {
+    let _ = CONFIG.set(config);
+}
+
+// Outside `main` (tests, tools) the configuration is loaded on first use
+pub fn config() -> &'static AppConfig {
+    CONFIG.get_or_init(|| AppConfig::load(None).unwrap_or_else(|e| panic!("{e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(file: &str, env: &[(&str, &str)]) -> Result<AppConfig, Vec<String>> {
+        AppConfig::from_sources(Some(file), |var| {
+            env.iter()
+                .find(|(k, _)| *k == var)
+                .map(|(_, v)| v.to_string())
+        })
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        let config = load("", &[]).unwrap();
+        assert_eq!(config, AppConfig::default());
+        assert_eq!(config.index_uid("movies"), "movies");
+    }
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let file = r#"
+            [meilisearch]
+            url = "http://meili.internal:7700"
+            timeout_secs = 5
+
+            [search]
+            hits_per_page = 24
+
+            [features]
+            web_fallback = true
+        "#;
+        let config = load(
+            file,
+            &[
+                ("MEILI_URL", "http://localhost:7700"),
+                ("WEB_FALLBACK", "off"),
+                ("QUERY_KANA_FOLDING", "Katakana"),
+                ("SEMANTIC_SEARCH", ""),
+                ("ADMIN_USERNAMES", "alice, bob,"),
+                (
+                    "EMBEDDER_CONFIG",
+                    r#"{"source":"ollama","model":"nomic-embed-text"}"#,
+                ),
+            ],
+        )
+        .unwrap();
+        assert_eq!(config.meilisearch.url, "http://localhost:7700");
+        assert_eq!(config.meilisearch.timeout_secs, 5);
+        assert_eq!(config.search.hits_per_page, 24);
+        assert!(!config.features.web_fallback);
+        assert_eq!(config.search.kana_folding, KanaFolding::Katakana);
+        assert_eq!(config.features.semantic_search, SemanticSearch::Off);
+        assert_eq!(config.users.admins, vec!["alice", "bob"]);
+        assert_eq!(
+            config.embedder.config.unwrap().model.as_deref(),
+            Some("nomic-embed-text")
+        );
+    }
+
+    #[test]
+    fn production_refuses_the_default_master_key() {
+        let problems = load("environment = \"production\"", &[]).unwrap_err();
+        assert!(problems[0].contains("master_key"), "{problems:?}");
+        assert!(load(
+            "",
+            &[("APP_ENV", "production"), ("MEILI_MASTER_KEY", "short")]
+        )
+        .is_err());
+        let key = "a-long-enough-secret-key";
+        assert!(load("", &[("APP_ENV", "production"), ("MEILI_MASTER_KEY", key)]).is_ok());
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let file = r#"
+            [meilisearch]
+            url = "localhost:7700"
+
+            [search]
+            hits_per_page = 0
+
+            [features]
+            semantic_search = "custom"
+
+            [indexes]
+            movies = "books"
+            films = "films"
+        "#;
+        let problems = load(file, &[("HITS_PER_PAGE", "lots")]).unwrap_err();
+        assert_eq!(
+            problems,
+            vec!["HITS_PER_PAGE must be a whole number, got `lots`"]
+        );
+
+        let problems = load(file, &[]).unwrap_err();
+        for expected in [
+            "meilisearch.url must be an http(s) URL",
+            "search.hits_per_page",
+            "needs embedder.config",
+            "`books` is used for more than one index",
+            "unknown index `films`",
+        ] {
+            assert!(
+                problems.iter().any(|p| p.contains(expected)),
+                "{expected}: {problems:?}"
+            );
+        }
+        assert!(load("[search]\npage_size = 10", &[]).is_err());
+    }
+
+    #[test]
+    fn printed_config_reloads_without_the_key() {
+        let config = load(
+            "[indexes]\nmovies = \"staging_movies\"",
+            &[(
+                "EMBEDDER_CONFIG",
+                r#"{"source":"openAi","model":"text-embedding-3-small","apiKey":"sk-secret"}"#,
+            )],
+        )
+        .unwrap();
+        assert_eq!(config.index_uid("movies"), "staging_movies");
+        let printed = config.to_toml();
+        assert!(!printed.contains(DEFAULT_MASTER_KEY), "{printed}");
+        assert!(!printed.contains("sk-secret"), "{printed}");
+        let reloaded = load(&printed, &[]).unwrap();
+        assert_eq!(reloaded.indexes, config.indexes);
+        assert_eq!(reloaded.search, config.search);
+        assert_eq!(
+            reloaded.embedder.config.unwrap().api_key.as_deref(),
+            Some(REDACTED)
+        );
+    }
+
+    #[test]
+    fn example_file_is_valid() {
+        load(include_str!("../../config.example.toml"), &[]).unwrap();
+    }
+}
@@ -0,0 +1,330 @@
+// "もしかして" suggestions for queries with few or no hits. Free-text words in the
+// query are compared against a dictionary of indexed titles, creators and genres
+// using edit distance over a folded form (lowercase hiragana without spaces), so
+// katakana, hiragana and romaji spellings of the same term line up.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::model::query::{query_words, quoted, QueryWord};
+use crate::model::reading::Readings;
+use crate::server::kana::to_hiragana;
+use crate::server::meilisearch::get_search_index;
+use crate::server::metrics;
+use crate::server::normalize::normalize_value;
+use crate::server::schema::schema;
+
+// Below this many hits the search response carries suggestions
+pub const FEW_HITS: usize = 3;
+const MAX_SUGGESTIONS: usize = 3;
+// The dictionary is rebuilt from the index at most this often
+const DICTIONARY_TTL: Duration = Duration::from_secs(5 * 60);
+const DICTIONARY_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub value: String,
+    keys: Vec<String>,
+}
+
+impl Term {
+    pub fn new(value: &str, readings: Option<&Readings>) -> Self {
+        let mut keys = vec![fold(value)];
+        if let Some(r) = readings {
+            keys.extend(r.hiragana.iter().chain(&r.romaji).map(|k| fold(k)));
+        }
+        keys.dedup();
+        Term {
+            value: value.to_string(),
+            keys,
+        }
+    }
+
+    fn distance(&self, key: &str) -> usize {
+        self.keys
+            .iter()
+            .map(|k| edit_distance(k, key))
+            .min()
+            .unwrap_or(usize::MAX)
+    }
+}
+
+fn fold(text: &str) -> String {
+    to_hiragana(&normalize_value(text).to_lowercase())
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (diagonal + usize::from(ca != *cb))
+                .min(above + 1)
+                .min(row[j] + 1);
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+// Short keys get no slack at all, longer ones roughly one edit per three characters
+fn max_distance(key: &str) -> usize {
+    match key.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        6..=9 => 2,
+        _ => 3,
+    }
+}
+
+// Closest dictionary terms for `key`, nearest first. Terms that already spell
+// the key are skipped since suggesting them would change nothing.
+fn closest<'a>(dictionary: &'a [Term], key: &str, limit: usize) -> Vec<&'a Term> {
+    let threshold = max_distance(key);
+    let mut matches: Vec<(usize, &Term)> = dictionary
+        .iter()
+        .filter(|t| fold(&t.value) != key)
+        .map(|t| (t.distance(key), t))
+        .filter(|(d, _)| *d <= threshold)
+        .collect();
+    matches.sort_by(|(da, ta), (db, tb)| da.cmp(db).then_with(|| ta.value.cmp(&tb.value)));
+    matches.into_iter().take(limit).map(|(_, t)| t).collect()
+}
+
+// Rebuilds the query with its free text replaced by `text`, keeping structured parts in place
+fn rewrite(words: &[QueryWord], text: &str) -> String {
+    let mut parts = Vec::new();
+    let mut placed = false;
+    for word in words {
+        if !word.free {
+            parts.push(word.text.clone());
+        } else if !placed {
+            parts.push(text.to_string());
+            placed = true;
+        }
+    }
+    parts.join(" ")
+}
+
+pub fn suggest_queries(dictionary: &[Term], query: &str) -> Vec<String> {
+    // A query that does not parse gets its error, not a suggestion
+    let Ok(words) = query_words(query) else {
+        return vec![];
+    };
+    let free: Vec<&str> = words
+        .iter()
+        .filter(|w| w.free)
+        .map(|w| w.text.as_str())
+        .collect();
+    if free.is_empty() {
+        return vec![];
+    }
+
+    let mut suggestions: Vec<String> = closest(dictionary, &fold(&free.join(" ")), MAX_SUGGESTIONS)
+        .into_iter()
+        .map(|t| rewrite(&words, &quoted(&t.value)))
+        .collect();
+
+    // Several free words may each be a near miss for a different term
+    if free.len() > 1 {
+        let corrected: Vec<String> = free
+            .iter()
+            .map(|w| match closest(dictionary, &fold(w), 1).first() {
+                Some(t) => quoted(&t.value),
+                None => w.to_string(),
+            })
+            .collect();
+        suggestions.push(rewrite(&words, &corrected.join(" ")));
+    }
+
+    let mut seen = Vec::new();
+    suggestions.retain(|s| {
+        let keep = s != query && !seen.contains(s);
+        seen.push(s.clone());
+        keep
+    });
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+fn add_term(terms: &mut Vec<Term>, value: Option<&str>, readings: Option<Readings>) {
+    let Some(value) = value.filter(|v| !v.trim().is_empty()) else {
+        return;
+    };
+    if terms.iter().any(|t| t.value == value) {
+        return;
+    }
+    terms.push(Term::new(value, readings.as_ref()));
+}
+
+async fn load_dictionary(index_name: &str) -> Result<Vec<Term>, String> {
+    let schema = schema(index_name)?;
+    let creator = schema.creator.unwrap_or("creator");
+    let creator_reading = format!("{creator}_reading");
+
+    let index = get_search_index(index_name);
+    let mut search = index.search();
+    search.with_query("");
+    search.with_limit(DICTIONARY_LIMIT);
+    let attributes = [
+        "title",
+        "title_en",
+        "title_reading",
+        creator,
+        &creator_reading,
+        "genres",
+    ];
+    search.with_attributes_to_retrieve(meilisearch_sdk::search::Selectors::Some(&attributes));
+
+    let results = search
+        .execute::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("Failed to load suggestion terms: {e}"))?;
+
+    let readings = |doc: &serde_json::Value, field: &str| -> Option<Readings> {
+        serde_json::from_value(doc.get(field)?.clone()).ok()
+    };
+
+    let mut terms = Vec::new();
+    for hit in results.hits {
+        let doc = hit.result;
+        let str_field = |field: &str| doc.get(field).and_then(|v| v.as_str());
+        add_term(
+            &mut terms,
+            str_field("title"),
+            readings(&doc, "title_reading"),
+        );
+        add_term(&mut terms, str_field("title_en"), None);
+        add_term(
+            &mut terms,
+            str_field(creator),
+            readings(&doc, &creator_reading),
+        );
+        for genre in doc
+            .get("genres")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+        {
+            add_term(&mut terms, genre.as_str(), None);
+        }
+    }
+    Ok(terms)
+}
+
+type Dictionaries = HashMap<String, (Instant, Arc<Vec<Term>>)>;
+
+static DICTIONARIES: OnceLock<Mutex<Dictionaries>> = OnceLock::new();
+
+async fn dictionary(index_name: &str) -> Result<Arc<Vec<Term>>, String> {
+    let cache = DICTIONARIES.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some((at, terms)) = cache.lock().unwrap().get(index_name) {
+        if at.elapsed() < DICTIONARY_TTL {
+            metrics::cache_lookup("did_you_mean", true);
+            return Ok(terms.clone());
+        }
+    }
+    metrics::cache_lookup("did_you_mean", false);
+
+    let terms = Arc::new(load_dictionary(index_name).await?);
+    cache
+        .lock()
+        .unwrap()
+        .insert(index_name.to_string(), (Instant::now(), terms.clone()));
+    Ok(terms)
+}
+
+pub async fn suggestions(index_name: &str, query: &str) -> Vec<String> {
+    match dictionary(index_name).await {
+        Ok(terms) => suggest_queries(&terms, query),
+        // Suggestions are optional; the search itself still answers
+        Err(error) => {
+            tracing::warn!(error = %error, index = index_name, "did-you-mean dictionary not loaded");
+            vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary() -> Vec<Term> {
+        vec![
+            Term::new(
+                "千と千尋の神隠し",
+                Some(&Readings {
+                    hiragana: Some("せんとちひろのかみかくし".into()),
+                    katakana: None,
+                    romaji: Some("sen to chihiro no kamikakushi".into()),
+                    source: None,
+                }),
+            ),
+            Term::new(
+                "宮崎駿",
+                Some(&Readings {
+                    hiragana: Some("みやざきはやお".into()),
+                    katakana: None,
+                    romaji: Some("miyazaki hayao".into()),
+                    source: None,
+                }),
+            ),
+            Term::new("Star Wars", None),
+            Term::new("アニメ", None),
+            Term::new("SF", None),
+        ]
+    }
+
+    #[test]
+    fn edit_distance_counts_chars() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("せんと", "せんど"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn corrects_typos_across_spacing_and_case() {
+        assert_eq!(
+            suggest_queries(&dictionary(), "starwors"),
+            vec!["\"Star Wars\"".to_string()]
+        );
+    }
+
+    #[test]
+    fn compares_kana_and_romaji_readings() {
+        assert_eq!(
+            suggest_queries(&dictionary(), "ミヤザキハヤオ"),
+            vec!["宮崎駿".to_string()]
+        );
+        assert_eq!(
+            suggest_queries(&dictionary(), "miyazaki hayo"),
+            vec!["宮崎駿".to_string()]
+        );
+        assert_eq!(
+            suggest_queries(&dictionary(), "千と千尋の神穏し"),
+            vec!["千と千尋の神隠し".to_string()]
+        );
+    }
+
+    #[test]
+    fn keeps_structured_parts_of_the_query() {
+        assert_eq!(
+            suggest_queries(&dictionary(), "year:2001 あにめー"),
+            vec!["year:2001 アニメ".to_string()]
+        );
+        assert!(suggest_queries(&dictionary(), "director:宮崎 -SF").is_empty());
+    }
+
+    #[test]
+    fn short_or_exact_words_get_no_suggestion() {
+        assert!(suggest_queries(&dictionary(), "SF").is_empty());
+        assert!(suggest_queries(&dictionary(), "xy").is_empty());
+    }
+}
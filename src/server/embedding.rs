@@ -0,0 +1,249 @@
+// Optional hybrid search. Depending on `features.semantic_search`
+// (SEMANTIC_SEARCH) the movies and books
+// indexes get a Meilisearch embedder named `default`:
+//
+// - `local`: Meilisearch's built-in Hugging Face embedder, which downloads the
+//   model once and embeds documents on CPU at index time, no external API
+// - `custom`: any embedder object from `embedder.config` or EMBEDDER_CONFIG (in
+//   Meilisearch's `embedders` setting format, e.g. an `ollama` or `rest` source)
+// - `fake`: a deterministic feature-hashing embedder computed here and sent as
+//   user-provided vectors, so tests and offline setups need no model at all
+//
+// Anything else leaves semantic search off and `semantic_ratio` is ignored.
+
+use std::collections::HashMap;
+
+use meilisearch_sdk::settings::{Embedder, EmbedderSource};
+use serde::Serialize;
+
+use crate::server::config::{config, SemanticSearch};
+use crate::server::kana::to_hiragana;
+use crate::server::meilisearch::{get_client, get_index};
+use crate::server::normalize::normalize_value;
+
+pub const EMBEDDER: &str = "default";
+pub const FAKE_DIMENSIONS: usize = 64;
+
+const DEFAULT_LOCAL_MODEL: &str = "sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2";
+const DOCUMENT_TEMPLATE: &str =
+    "{{doc.title}} {{doc.title_en}} {{doc.genres | join: ' '}} {{doc.description}}";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmbedderMode {
+    Off,
+    Local(String),
+    Custom(Box<Embedder>),
+    Fake,
+}
+
+impl EmbedderMode {
+    // `features.semantic_search` with the `[embedder]` model or config
+    pub fn from_config() -> Self {
+        let config = config();
+        match config.features.semantic_search {
+            SemanticSearch::Local => EmbedderMode::Local(
+                config
+                    .embedder
+                    .model
+                    .clone()
+                    .filter(|m| !m.is_empty())
+                    .unwrap_or_else(|| DEFAULT_LOCAL_MODEL.to_string()),
+            ),
+            // Validation makes sure `custom` comes with a config
+            SemanticSearch::Custom => config
+                .embedder
+                .config
+                .clone()
+                .map(|e| EmbedderMode::Custom(Box::new(e)))
+                .unwrap_or(EmbedderMode::Off),
+            SemanticSearch::Fake => EmbedderMode::Fake,
+            SemanticSearch::Off => EmbedderMode::Off,
+        }
+    }
+
+    pub fn settings(&self) -> Option<Embedder> {
+        match self {
+            EmbedderMode::Off => None,
+            EmbedderMode::Local(model) => Some(Embedder {
+                source: EmbedderSource::HuggingFace,
+                model: Some(model.clone()),
+                document_template: Some(DOCUMENT_TEMPLATE.to_string()),
+                ..Embedder::default()
+            }),
+            EmbedderMode::Custom(embedder) => {
+                let mut embedder = (**embedder).clone();
+                if embedder.document_template.is_none() {
+                    embedder.document_template = Some(DOCUMENT_TEMPLATE.to_string());
+                }
+                Some(embedder)
+            }
+            EmbedderMode::Fake => Some(Embedder {
+                source: EmbedderSource::UserProvided,
+                dimensions: Some(FAKE_DIMENSIONS),
+                ..Embedder::default()
+            }),
+        }
+    }
+}
+
+pub fn is_enabled() -> bool {
+    EmbedderMode::from_config() != EmbedderMode::Off
+}
+
+// FNV-1a, chosen because it is stable across processes unlike std's RandomState
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Latin words are kept whole, everything else is split into character bigrams
+// since Japanese has no spaces to split on
+fn features(text: &str) -> Vec<String> {
+    let folded = to_hiragana(&normalize_value(text).to_lowercase());
+    let mut features = Vec::new();
+    for word in folded.split(|c: char| !c.is_alphanumeric()) {
+        if word.is_empty() {
+            continue;
+        }
+        if word.is_ascii() {
+            features.push(word.to_string());
+            continue;
+        }
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() == 1 {
+            features.push(word.to_string());
+        }
+        features.extend(chars.windows(2).map(|w| w.iter().collect::<String>()));
+    }
+    features
+}
+
+pub fn fake_embedding(text: &str) -> Vec<f32> {
+    let mut vector = vec![0.0f32; FAKE_DIMENSIONS];
+    for feature in features(text) {
+        let hash = fnv1a(&feature);
+        let slot = (hash % FAKE_DIMENSIONS as u64) as usize;
+        vector[slot] += if hash >> 63 == 0 { 1.0 } else { -1.0 };
+    }
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+    vector
+}
+
+fn document_text(doc: &serde_json::Value) -> String {
+    let field = |name: &str| doc.get(name).and_then(|v| v.as_str()).unwrap_or_default();
+    let genres: Vec<&str> = doc
+        .get("genres")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|g| g.as_str())
+        .collect();
+    format!(
+        "{} {} {} {}",
+        field("title"),
+        field("title_en"),
+        genres.join(" "),
+        field("description")
+    )
+}
+
+// The vector to send with a hybrid query, only needed for user-provided embeddings
+pub fn query_vector(query: &str) -> Option<Vec<f32>> {
+    (EmbedderMode::from_config() == EmbedderMode::Fake).then(|| fake_embedding(query))
+}
+
+// Documents as they should be sent to Meilisearch. In fake mode each one carries
+// its own `_vectors`; the other modes let Meilisearch embed the document itself.
+pub fn with_vectors<T: Serialize>(documents: &[T]) -> Result<Vec<serde_json::Value>, String> {
+    let fake = EmbedderMode::from_config() == EmbedderMode::Fake;
+    documents
+        .iter()
+        .map(|doc| {
+            let mut value = serde_json::to_value(doc)
+                .map_err(|e| format!("Failed to serialise document: {e}"))?;
+            if fake {
+                let vector = fake_embedding(&document_text(&value));
+                if let Some(object) = value.as_object_mut() {
+                    object.insert(
+                        "_vectors".to_string(),
+                        serde_json::json!({ EMBEDDER: vector }),
+                    );
+                }
+            }
+            Ok(value)
+        })
+        .collect()
+}
+
+// Needs to run before documents with user-provided vectors are added
+pub async fn configure(index_name: &str) -> Result<(), String> {
+    let Some(embedder) = EmbedderMode::from_config().settings() else {
+        return Ok(());
+    };
+    let client = get_client();
+    let task = get_index(index_name)
+        .set_embedders(&HashMap::from([(EMBEDDER.to_string(), embedder)]))
+        .await
+        .map_err(|e| format!("Failed to set embedders: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for embedders: {e}"))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[test]
+    fn fake_embedding_is_deterministic_and_normalised() {
+        let a = fake_embedding("ロボットの悲しい物語");
+        assert_eq!(a, fake_embedding("ロボットの悲しい物語"));
+        assert_eq!(a.len(), FAKE_DIMENSIONS);
+        assert!((cosine(&a, &a) - 1.0).abs() < 1e-5);
+        assert!(fake_embedding("").iter().all(|v| *v == 0.0));
+    }
+
+    #[test]
+    fn fake_embedding_ranks_overlapping_text_higher() {
+        let query = fake_embedding("ろぼっと 孤独");
+        let walle = fake_embedding("ゴミ処理ロボットが孤独に暮らす地球");
+        let samurai = fake_embedding("侍が村を守るために戦う時代劇");
+        assert!(cosine(&query, &walle) > cosine(&query, &samurai));
+    }
+
+    #[test]
+    fn modes_map_to_embedder_settings() {
+        assert_eq!(EmbedderMode::Off.settings(), None);
+
+        let local = EmbedderMode::Local("m".into()).settings().unwrap();
+        assert_eq!(local.source, EmbedderSource::HuggingFace);
+        assert_eq!(local.model.as_deref(), Some("m"));
+        assert!(local.document_template.is_some());
+
+        let fake = EmbedderMode::Fake.settings().unwrap();
+        assert_eq!(fake.source, EmbedderSource::UserProvided);
+        assert_eq!(fake.dimensions, Some(FAKE_DIMENSIONS));
+    }
+
+    #[test]
+    fn document_text_covers_indexed_fields() {
+        let doc = serde_json::json!({
+            "title": "ウォーリー",
+            "title_en": "WALL-E",
+            "genres": ["SF", "アニメ"],
+            "description": "ロボット",
+        });
+        assert_eq!(document_text(&doc), "ウォーリー WALL-E SF アニメ ロボット");
+    }
+}
@@ -0,0 +1,191 @@
+// Typed Meilisearch filter expressions. Values are always quoted and escaped when
+// serialised, and attribute names are expected to come from `server::schema`.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Float(f64),
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::Str(v.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::Str(v)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(v: i32) -> Self {
+        Value::Int(v as i64)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(v: i64) -> Self {
+        Value::Int(v)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Float(v)
+    }
+}
+
+impl Value {
+    fn to_expression(&self) -> Option<String> {
+        match self {
+            Value::Str(s) => Some(format!(
+                "\"{}\"",
+                s.replace('\\', "\\\\").replace('"', "\\\"")
+            )),
+            Value::Int(i) => Some(i.to_string()),
+            Value::Float(f) if f.is_finite() => Some(f.to_string()),
+            Value::Float(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Eq(&'static str, Value),
+    In(&'static str, Vec<Value>),
+    Range {
+        field: &'static str,
+        min: Option<Value>,
+        max: Option<Value>,
+    },
+    Not(Box<Filter>),
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Exists(&'static str),
+    IsNull(&'static str),
+}
+
+impl Filter {
+    pub fn eq(field: &'static str, value: impl Into<Value>) -> Self {
+        Filter::Eq(field, value.into())
+    }
+
+    pub fn negate(filter: Filter) -> Self {
+        Filter::Not(Box::new(filter))
+    }
+
+    // None when the filter places no constraint, e.g. an empty `And`
+    pub fn to_expression(&self) -> Option<String> {
+        match self {
+            Filter::Eq(field, value) => Some(format!("{field} = {}", value.to_expression()?)),
+            Filter::In(field, values) => {
+                let values: Vec<String> = values.iter().filter_map(Value::to_expression).collect();
+                if values.is_empty() {
+                    return None;
+                }
+                Some(format!("{field} IN [{}]", values.join(", ")))
+            }
+            Filter::Range { field, min, max } => {
+                let min = min.as_ref().and_then(Value::to_expression);
+                let max = max.as_ref().and_then(Value::to_expression);
+                match (min, max) {
+                    (Some(lo), Some(hi)) => Some(format!("{field} >= {lo} AND {field} <= {hi}")),
+                    (Some(lo), None) => Some(format!("{field} >= {lo}")),
+                    (None, Some(hi)) => Some(format!("{field} <= {hi}")),
+                    (None, None) => None,
+                }
+            }
+            Filter::Not(inner) => Some(format!("NOT ({})", inner.to_expression()?)),
+            Filter::And(filters) => join(filters, " AND "),
+            Filter::Or(filters) => join(filters, " OR "),
+            Filter::Exists(field) => Some(format!("{field} EXISTS")),
+            Filter::IsNull(field) => Some(format!("{field} IS NULL")),
+        }
+    }
+}
+
+fn join(filters: &[Filter], separator: &str) -> Option<String> {
+    let parts: Vec<String> = filters
+        .iter()
+        .filter_map(Filter::to_expression)
+        .map(|e| format!("({e})"))
+        .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join(separator))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_quotes_and_backslashes() {
+        let filter = Filter::eq("genres", r#"SF" OR genres = "x\"#);
+        assert_eq!(
+            filter.to_expression().unwrap(),
+            r#"genres = "SF\" OR genres = \"x\\""#
+        );
+    }
+
+    #[test]
+    fn combines_nested_filters() {
+        let filter = Filter::And(vec![
+            Filter::Or(vec![
+                Filter::eq("genres", "SF"),
+                Filter::eq("genres", "ドラマ"),
+            ]),
+            Filter::negate(Filter::eq("genres", "ホラー")),
+            Filter::Range {
+                field: "year",
+                min: Some(1990.into()),
+                max: Some(2000.into()),
+            },
+        ]);
+        assert_eq!(
+            filter.to_expression().unwrap(),
+            r#"((genres = "SF") OR (genres = "ドラマ")) AND (NOT (genres = "ホラー")) AND (year >= 1990 AND year <= 2000)"#
+        );
+    }
+
+    #[test]
+    fn empty_groups_place_no_constraint() {
+        assert_eq!(Filter::And(vec![]).to_expression(), None);
+        assert_eq!(Filter::In("genres", vec![]).to_expression(), None);
+        assert_eq!(
+            Filter::And(vec![Filter::Or(vec![]), Filter::Exists("rating")]).to_expression(),
+            Some("(rating EXISTS)".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_non_finite_numbers() {
+        assert_eq!(Filter::eq("rating", f64::NAN).to_expression(), None);
+        assert_eq!(
+            Filter::Range {
+                field: "rating",
+                min: Some(f64::INFINITY.into()),
+                max: Some(9.5.into()),
+            }
+            .to_expression(),
+            Some("rating <= 9.5".to_string())
+        );
+    }
+
+    #[test]
+    fn in_and_null_checks() {
+        assert_eq!(
+            Filter::In("language", vec!["ja".into(), "en".into()]).to_expression(),
+            Some(r#"language IN ["ja", "en"]"#.to_string())
+        );
+        assert_eq!(
+            Filter::IsNull("poster_url").to_expression(),
+            Some("poster_url IS NULL".to_string())
+        );
+    }
+}
@@ -0,0 +1,107 @@
+// `/healthz` answers as long as the process serves requests; `/readyz` checks
+// the dependencies searching needs: Meilisearch itself, the catalogue indexes
+// with the settings `configure_index` applies, and SearXNG, which only web
+// results depend on and so degrades rather than fails readiness.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use actix_web::HttpResponse;
+
+use crate::model::health::{missing_attributes, Check, CheckStatus, HealthReport, Readiness};
+use crate::server::meilisearch::{get_client, get_index};
+use crate::server::schema::{schema, CATALOGUE_INDEXES};
+use crate::server::searxng;
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+async fn run_check<F>(name: &str, required: bool, check: F) -> Check
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let started = Instant::now();
+    let result = match actix_web::rt::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("no answer within {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    Check {
+        name: name.to_string(),
+        status: if result.is_ok() { CheckStatus::Ok } else { CheckStatus::Failed },
+        required,
+        detail: result.err(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+async fn check_meilisearch() -> Result<(), String> {
+    let health = get_client()
+        .health()
+        .await
+        .map_err(|e| format!("Meilisearch is unreachable: {e}"))?;
+    if health.status != "available" {
+        return Err(format!("Meilisearch reports status {}", health.status));
+    }
+    Ok(())
+}
+
+// The index exists and has the attributes search filters and sorts on
+async fn check_index(index_name: &str) -> Result<(), String> {
+    let schema = schema(index_name)?;
+    let index = get_index(index_name);
+    let filterable = index
+        .get_filterable_attributes()
+        .await
+        .map_err(|e| format!("index {index_name} is unavailable ({e}); run the seed to create it"))?;
+    let sortable = index
+        .get_sortable_attributes()
+        .await
+        .map_err(|e| format!("index {index_name} is unavailable ({e})"))?;
+
+    let missing: Vec<&str> = missing_attributes(schema.filterable, &filterable)
+        .into_iter()
+        .chain(missing_attributes(schema.sortable, &sortable))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "index {index_name} is missing attributes: {}; run the seed to reconfigure it",
+            missing.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+pub async fn readiness() -> HealthReport {
+    let meilisearch = run_check("meilisearch", true, check_meilisearch()).await;
+    let mut checks = Vec::new();
+    let reachable = meilisearch.status == CheckStatus::Ok;
+    checks.push(meilisearch);
+    // Index checks would only repeat the connection error
+    if reachable {
+        // The web index only exists once a web search has stored results
+        for index_name in CATALOGUE_INDEXES.into_iter().filter(|i| *i != "web") {
+            checks.push(run_check(&format!("index:{index_name}"), true, check_index(index_name)).await);
+        }
+    }
+    checks.push(run_check("searxng", false, searxng::ping(CHECK_TIMEOUT)).await);
+
+    let report = HealthReport::new(checks);
+    if report.status != Readiness::Ready {
+        for check in report.checks.iter().filter(|c| c.status == CheckStatus::Failed) {
+            tracing::warn!(check = %check.name, detail = ?check.detail, "readiness check failed");
+        }
+    }
+    report
+}
+
+pub async fn liveness_endpoint() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({ "status": "ok" }))
+}
+
+// 503 only when searching is impossible; a degraded service still takes traffic
+pub async fn readiness_endpoint() -> HttpResponse {
+    let report = readiness().await;
+    match report.status {
+        Readiness::Unavailable => HttpResponse::ServiceUnavailable().json(report),
+        Readiness::Ready | Readiness::Degraded => HttpResponse::Ok().json(report),
+    }
+}
@@ -0,0 +1,173 @@
+// Katakana and hiragana blocks are laid out in parallel, 0x60 code points apart
+const KANA_OFFSET: u32 = 0x60;
+
+fn is_hiragana(c: char) -> bool {
+    ('\u{3041}'..='\u{3096}').contains(&c)
+}
+
+fn is_katakana(c: char) -> bool {
+    ('\u{30A1}'..='\u{30F6}').contains(&c)
+}
+
+pub fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if is_katakana(c) {
+                char::from_u32(c as u32 - KANA_OFFSET).unwrap_or(c)
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+pub fn to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if is_hiragana(c) {
+                char::from_u32(c as u32 + KANA_OFFSET).unwrap_or(c)
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+// True when the text is written entirely in kana (spaces and ・ are allowed)
+pub fn is_kana_only(text: &str) -> bool {
+    text.chars().any(|c| is_hiragana(c) || is_katakana(c))
+        && text.chars().all(|c| {
+            is_hiragana(c) || is_katakana(c) || matches!(c, 'ー' | '・' | ' ' | '　')
+        })
+}
+
+#[rustfmt::skip]
+fn romaji_of(kana: &str) -> Option<&'static str> {
+    let r = match kana {
+        "あ" => "a", "い" => "i", "う" => "u", "え" => "e", "お" => "o",
+        "か" => "ka", "き" => "ki", "く" => "ku", "け" => "ke", "こ" => "ko",
+        "さ" => "sa", "し" => "shi", "す" => "su", "せ" => "se", "そ" => "so",
+        "た" => "ta", "ち" => "chi", "つ" => "tsu", "て" => "te", "と" => "to",
+        "な" => "na", "に" => "ni", "ぬ" => "nu", "ね" => "ne", "の" => "no",
+        "は" => "ha", "ひ" => "hi", "ふ" => "fu", "へ" => "he", "ほ" => "ho",
+        "ま" => "ma", "み" => "mi", "む" => "mu", "め" => "me", "も" => "mo",
+        "や" => "ya", "ゆ" => "yu", "よ" => "yo",
+        "ら" => "ra", "り" => "ri", "る" => "ru", "れ" => "re", "ろ" => "ro",
+        "わ" => "wa", "ゐ" => "i", "ゑ" => "e", "を" => "o", "ん" => "n",
+        "が" => "ga", "ぎ" => "gi", "ぐ" => "gu", "げ" => "ge", "ご" => "go",
+        "ざ" => "za", "じ" => "ji", "ず" => "zu", "ぜ" => "ze", "ぞ" => "zo",
+        "だ" => "da", "ぢ" => "ji", "づ" => "zu", "で" => "de", "ど" => "do",
+        "ば" => "ba", "び" => "bi", "ぶ" => "bu", "べ" => "be", "ぼ" => "bo",
+        "ぱ" => "pa", "ぴ" => "pi", "ぷ" => "pu", "ぺ" => "pe", "ぽ" => "po",
+        "ゔ" => "vu",
+        "ぁ" => "a", "ぃ" => "i", "ぅ" => "u", "ぇ" => "e", "ぉ" => "o",
+        "ゃ" => "ya", "ゅ" => "yu", "ょ" => "yo", "ゎ" => "wa",
+        "きゃ" => "kya", "きゅ" => "kyu", "きょ" => "kyo",
+        "しゃ" => "sha", "しゅ" => "shu", "しょ" => "sho", "しぇ" => "she",
+        "ちゃ" => "cha", "ちゅ" => "chu", "ちょ" => "cho", "ちぇ" => "che",
+        "にゃ" => "nya", "にゅ" => "nyu", "にょ" => "nyo",
+        "ひゃ" => "hya", "ひゅ" => "hyu", "ひょ" => "hyo",
+        "みゃ" => "mya", "みゅ" => "myu", "みょ" => "myo",
+        "りゃ" => "rya", "りゅ" => "ryu", "りょ" => "ryo",
+        "ぎゃ" => "gya", "ぎゅ" => "gyu", "ぎょ" => "gyo",
+        "じゃ" => "ja", "じゅ" => "ju", "じょ" => "jo", "じぇ" => "je",
+        "びゃ" => "bya", "びゅ" => "byu", "びょ" => "byo",
+        "ぴゃ" => "pya", "ぴゅ" => "pyu", "ぴょ" => "pyo",
+        "ふぁ" => "fa", "ふぃ" => "fi", "ふぇ" => "fe", "ふぉ" => "fo",
+        "てぃ" => "ti", "でぃ" => "di", "とぅ" => "tu", "どぅ" => "du",
+        "うぃ" => "wi", "うぇ" => "we", "うぉ" => "wo",
+        "ゔぁ" => "va", "ゔぃ" => "vi", "ゔぇ" => "ve", "ゔぉ" => "vo",
+        _ => return None,
+    };
+    Some(r)
+}
+
+// Modified Hepburn romanisation of a kana reading, lowercased
+pub fn to_romaji(text: &str) -> String {
+    let chars: Vec<char> = to_hiragana(text).chars().collect();
+    let mut out = String::new();
+    let mut geminate = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == 'っ' {
+            geminate = true;
+            i += 1;
+            continue;
+        }
+
+        if c == 'ー' {
+            if let Some(last) = out.chars().last().filter(|l| "aiueo".contains(*l)) {
+                out.push(last);
+            }
+            i += 1;
+            continue;
+        }
+
+        let pair: String = chars[i..chars.len().min(i + 2)].iter().collect();
+        let single = c.to_string();
+        let (romaji, width) = match romaji_of(&pair).filter(|_| pair.chars().count() == 2) {
+            Some(r) => (Some(r), 2),
+            None => (romaji_of(&single), 1),
+        };
+
+        match romaji {
+            Some(r) => {
+                if geminate {
+                    // っち is written "tchi" in Hepburn
+                    let first = if r.starts_with("ch") { 't' } else { r.chars().next().unwrap() };
+                    out.push(first);
+                }
+                out.push_str(r);
+            }
+            None => match c {
+                '・' | '　' => out.push(' '),
+                _ => out.extend(c.to_lowercase()),
+            },
+        }
+
+        geminate = false;
+        i += width;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kana_converts_between_scripts() {
+        assert_eq!(to_hiragana("カタカナ ABC ー"), "かたかな ABC ー");
+        assert_eq!(to_katakana("ひらがな・漢字"), "ヒラガナ・漢字");
+        assert_eq!(to_hiragana(&to_katakana("ゔぁいおれっと")), "ゔぁいおれっと");
+    }
+
+    #[test]
+    fn kana_only_allows_separators_but_not_kanji() {
+        assert!(is_kana_only("ルパン・ザ・サード"));
+        assert!(is_kana_only("となりの　トトロ"));
+        assert!(!is_kana_only("千と千尋"));
+        assert!(!is_kana_only("ー・ "));
+        assert!(!is_kana_only(""));
+    }
+
+    #[test]
+    fn romaji_follows_hepburn() {
+        assert_eq!(to_romaji("となりのととろ"), "tonarinototoro");
+        assert_eq!(to_romaji("しんじ"), "shinji");
+        // Digraphs, double consonants and っち
+        assert_eq!(to_romaji("きょうと"), "kyouto");
+        assert_eq!(to_romaji("がっこう"), "gakkou");
+        assert_eq!(to_romaji("まっちゃ"), "matcha");
+        // The long vowel mark repeats the vowel, ・ becomes a space
+        assert_eq!(to_romaji("ラーメン"), "raamen");
+        assert_eq!(to_romaji("ゴジラ・ミニラ"), "gojira minira");
+        assert_eq!(to_romaji("ヴァイオレット"), "vaioretto");
+        // Anything else is kept, lowercased
+        assert_eq!(to_romaji("ＳＦアニメ"), "ｓｆanime");
+    }
+}
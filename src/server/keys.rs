@@ -0,0 +1,322 @@
+// Scoped Meilisearch API keys. The master key is only used here, to look up or
+// create two keys by name: a search key that can do nothing but search the
+// catalogue indexes, used for every read-only search, and an admin key with
+// the document, settings and task actions the app needs on its own indexes,
+// used for everything else. The newest matching key of each kind wins, so keys
+// created by `keys rotate` or by another instance are picked up on the next
+// refresh. With `meilisearch.key_rotation_days` set, keys expire after twice
+// that period and a replacement is created once the current one is older than
+// it, which leaves other instances a full period to switch over. Looking keys
+// up needs the master key too, so a server holds it for as long as it runs:
+// scoped keys narrow what each request can do, not what the process can.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use meilisearch_sdk::key::{Action, Key, KeyBuilder, KeysQuery};
+use time::OffsetDateTime;
+
+use crate::server::config::{config, AppConfig, INDEX_NAMES};
+use crate::server::meilisearch::{master_client, use_keys};
+use crate::server::schema::CATALOGUE_INDEXES;
+
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+const KEY_LIST_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyKind {
+    Search,
+    Admin,
+}
+
+impl KeyKind {
+    pub const ALL: [KeyKind; 2] = [KeyKind::Search, KeyKind::Admin];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyKind::Search => "meilisearch-search-app search",
+            KeyKind::Admin => "meilisearch-search-app admin",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            KeyKind::Search => "Read-only searches of the catalogue indexes",
+            KeyKind::Admin => "Documents, settings and tasks of the app's indexes",
+        }
+    }
+
+    pub fn actions(self) -> Vec<Action> {
+        match self {
+            KeyKind::Search => vec![Action::Search],
+            KeyKind::Admin => vec![
+                Action::Search,
+                Action::DocumentsAdd,
+                Action::DocumentsGet,
+                Action::DocumentsDelete,
+                Action::IndexesCreate,
+                Action::IndexesGet,
+                Action::IndexesUpdate,
+                Action::SettingsGet,
+                Action::SettingsUpdate,
+                Action::TasksGet,
+                Action::StatsGet,
+            ],
+        }
+    }
+
+    // Meilisearch uids, so `[indexes]` renames are covered
+    pub fn indexes(self, config: &AppConfig) -> Vec<String> {
+        let names: &[&str] = match self {
+            // Searched by anonymous visitors, and so all the search key may read
+            KeyKind::Search => &CATALOGUE_INDEXES,
+            KeyKind::Admin => &INDEX_NAMES,
+        };
+        names
+            .iter()
+            .map(|name| config.index_uid(name).to_string())
+            .collect()
+    }
+
+    // Made for this kind with the actions and indexes the current config needs
+    pub fn matches(self, key: &Key, config: &AppConfig) -> bool {
+        key.name.as_deref() == Some(self.name())
+            && same_items(&key.actions, &self.actions())
+            && same_items(&key.indexes, &self.indexes(config))
+    }
+}
+
+fn same_items<T: PartialEq>(a: &[T], b: &[T]) -> bool {
+    a.iter().all(|x| b.contains(x)) && b.iter().all(|x| a.contains(x))
+}
+
+fn rotation(config: &AppConfig) -> Option<time::Duration> {
+    let days = config.meilisearch.key_rotation_days;
+    (days > 0).then(|| time::Duration::days(days as i64))
+}
+
+pub fn is_app_key(key: &Key) -> bool {
+    KeyKind::ALL
+        .iter()
+        .any(|kind| key.name.as_deref() == Some(kind.name()))
+}
+
+// The key of `kind` to use, `None` when there is none or a replacement is due
+pub fn current<'a>(
+    kind: KeyKind,
+    keys: &'a [Key],
+    config: &AppConfig,
+    now: OffsetDateTime,
+) -> Option<&'a Key> {
+    // A key about to expire would fail before the next refresh
+    let valid_until = now + REFRESH_INTERVAL * 2;
+    keys.iter()
+        .filter(|key| kind.matches(key, config))
+        .filter(|key| key.expires_at.is_none_or(|at| at > valid_until))
+        .max_by_key(|key| key.created_at)
+        .filter(|key| rotation(config).is_none_or(|period| key.created_at + period > now))
+}
+
+pub fn enabled() -> bool {
+    let meilisearch = &config().meilisearch;
+    meilisearch.scoped_keys && !meilisearch.master_key.is_empty()
+}
+
+// uids of the admin and search keys in use, unset while requests use the master key
+static ACTIVE: Mutex<Option<(String, String)>> = Mutex::new(None);
+
+pub async fn list() -> Result<Vec<Key>, String> {
+    let mut query = KeysQuery::new();
+    query.with_limit(KEY_LIST_LIMIT);
+    master_client()
+        .get_keys_with(&query)
+        .await
+        .map(|results| results.results)
+        .map_err(|e| format!("Failed to list API keys: {e}"))
+}
+
+async fn create(kind: KeyKind, config: &AppConfig, now: OffsetDateTime) -> Result<Key, String> {
+    let mut builder = KeyBuilder::new();
+    builder
+        .with_name(kind.name())
+        .with_description(kind.description())
+        .with_actions(kind.actions())
+        .with_indexes(kind.indexes(config));
+    if let Some(period) = rotation(config) {
+        builder.with_expires_at(now + period * 2);
+    }
+    let key = master_client()
+        .create_key(builder)
+        .await
+        .map_err(|e| format!("Failed to create the {} key: {e}", kind.name()))?;
+    tracing::info!(key = kind.name(), uid = %key.uid, "created Meilisearch API key");
+    Ok(key)
+}
+
+// Looks up or creates both keys and switches requests to them. Runs at startup
+// and every REFRESH_INTERVAL, which is also when rotation happens.
+pub async fn refresh() -> Result<(), String> {
+    let config = config();
+    let now = OffsetDateTime::now_utc();
+    let keys = list().await?;
+
+    let search = match current(KeyKind::Search, &keys, config, now) {
+        Some(key) => key.clone(),
+        None => create(KeyKind::Search, config, now).await?,
+    };
+    let admin = match current(KeyKind::Admin, &keys, config, now) {
+        Some(key) => key.clone(),
+        None => create(KeyKind::Admin, config, now).await?,
+    };
+    let uids = (admin.uid.clone(), search.uid.clone());
+    {
+        let mut active = ACTIVE.lock().unwrap();
+        if active.as_ref() != Some(&uids) {
+            use_keys(&admin.key, &search.key);
+            tracing::info!(admin = %admin.uid, search = %search.uid, "using scoped Meilisearch API keys");
+            *active = Some(uids);
+        }
+    }
+
+    // Meilisearch keeps listing expired keys until they are deleted
+    for key in keys
+        .iter()
+        .filter(|key| is_app_key(key) && key.expires_at.is_some_and(|at| at <= now))
+    {
+        if let Err(e) = master_client().delete_key(&key.uid).await {
+            tracing::warn!(uid = %key.uid, error = %e, "expired API key not deleted");
+        }
+    }
+    Ok(())
+}
+
+// Creates fresh keys of both kinds; servers switch to them on their next refresh
+pub async fn rotate() -> Result<Vec<Key>, String> {
+    let config = config();
+    let now = OffsetDateTime::now_utc();
+    let mut created = Vec::new();
+    for kind in KeyKind::ALL {
+        created.push(create(kind, config, now).await?);
+    }
+    Ok(created)
+}
+
+pub async fn revoke(uid: &str) -> Result<(), String> {
+    master_client()
+        .delete_key(uid)
+        .await
+        .map_err(|e| format!("Failed to revoke key {uid}: {e}"))
+}
+
+fn action_name(action: &Action) -> String {
+    serde_json::to_value(action)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_else(|| format!("{action:?}"))
+}
+
+// One key for `keys list`, marking the ones this app would use now
+pub fn describe(key: &Key, config: &AppConfig, now: OffsetDateTime) -> String {
+    let usable = KeyKind::ALL
+        .iter()
+        .any(|kind| current(*kind, std::slice::from_ref(key), config, now).is_some());
+    let expires = match key.expires_at {
+        Some(at) if at <= now => format!("expired {}", at.date()),
+        Some(at) => format!("expires {}", at.date()),
+        None => "never expires".to_string(),
+    };
+    let actions: Vec<String> = key.actions.iter().map(action_name).collect();
+    format!(
+        "{uid}  {name}{marker}\n    {expires}, created {created}\n    actions: {actions}\n    indexes: {indexes}",
+        uid = key.uid,
+        name = key.name.as_deref().unwrap_or("(unnamed)"),
+        marker = if usable { "  [usable]" } else { "" },
+        created = key.created_at.date(),
+        actions = actions.join(", "),
+        indexes = key.indexes.join(", "),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(kind: KeyKind, uid: &str, created_days: i64, expires_days: Option<i64>) -> Key {
+        let config = AppConfig::default();
+        let day = |d: i64| OffsetDateTime::UNIX_EPOCH + time::Duration::days(d);
+        Key {
+            actions: kind.actions().into_iter().rev().collect(),
+            created_at: day(created_days),
+            description: None,
+            name: Some(kind.name().to_string()),
+            expires_at: expires_days.map(day),
+            indexes: kind.indexes(&config),
+            key: format!("secret-{uid}"),
+            uid: uid.to_string(),
+            updated_at: day(created_days),
+        }
+    }
+
+    fn at(days: i64) -> OffsetDateTime {
+        OffsetDateTime::UNIX_EPOCH + time::Duration::days(days)
+    }
+
+    #[test]
+    fn keys_match_on_name_actions_and_indexes() {
+        let config = AppConfig::default();
+        let search = key(KeyKind::Search, "s", 0, None);
+        assert!(KeyKind::Search.matches(&search, &config));
+        assert!(!KeyKind::Admin.matches(&search, &config));
+        assert_eq!(search.indexes, vec!["movies", "books", "series", "web"]);
+
+        let mut broader = search.clone();
+        broader.actions.push(Action::DocumentsGet);
+        assert!(!KeyKind::Search.matches(&broader, &config));
+
+        // Renaming an index needs a key covering the new uid
+        let mut renamed = config.clone();
+        renamed
+            .indexes
+            .insert("movies".into(), "staging_movies".into());
+        assert!(!KeyKind::Search.matches(&search, &renamed));
+    }
+
+    #[test]
+    fn newest_valid_key_is_used_until_rotation_is_due() {
+        let mut config = AppConfig::default();
+        config.meilisearch.key_rotation_days = 30;
+        let keys = vec![
+            key(KeyKind::Search, "old", 0, Some(60)),
+            key(KeyKind::Search, "new", 20, Some(80)),
+            key(KeyKind::Admin, "admin", 25, Some(85)),
+        ];
+        let uid = |now| current(KeyKind::Search, &keys, &config, at(now)).map(|k| k.uid.as_str());
+        assert_eq!(uid(25), Some("new"));
+        // Past 30 days a new key is due even though this one is still valid
+        assert_eq!(uid(50), None);
+
+        let expiring = vec![key(KeyKind::Search, "s", 0, Some(10))];
+        assert!(current(KeyKind::Search, &expiring, &config, at(10)).is_none());
+
+        config.meilisearch.key_rotation_days = 0;
+        let forever = vec![key(KeyKind::Search, "s", 0, None)];
+        assert!(current(KeyKind::Search, &forever, &config, at(1000)).is_some());
+    }
+
+    #[test]
+    fn listing_marks_usable_keys() {
+        let config = AppConfig::default();
+        let usable = describe(&key(KeyKind::Admin, "a", 0, Some(40)), &config, at(1));
+        assert!(
+            usable.starts_with("a  meilisearch-search-app admin  [usable]"),
+            "{usable}"
+        );
+        assert!(usable.contains("actions: stats.get, tasks.get"), "{usable}");
+
+        let expired = describe(&key(KeyKind::Admin, "b", 0, Some(10)), &config, at(20));
+        assert!(
+            !expired.contains("[usable]") && expired.contains("expired 1970-01-11"),
+            "{expired}"
+        );
+    }
+}
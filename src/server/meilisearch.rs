@@ -1,104 +1,629 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
 use meilisearch_sdk::client::Client;
-use std::sync::OnceLock;
+use meilisearch_sdk::documents::DocumentsQuery;
+use meilisearch_sdk::errors::{Error, ErrorCode, MeilisearchError};
+use meilisearch_sdk::indexes::Index;
+use meilisearch_sdk::task_info::TaskInfo;
+use meilisearch_sdk::tasks::Task;
+use std::sync::{Arc, OnceLock, RwLock};
 
-static CLIENT: OnceLock<Client> = OnceLock::new();
+use crate::model::book::Book;
+use crate::model::movie::Movie;
+use crate::model::query::{parse_query, ParsedQuery};
+use crate::model::search::{BookFilters, MovieFilters, SearchHit, SearchRequest, SeriesFilters};
+use crate::model::user::is_listable;
+use crate::model::series::Series;
+use crate::server::config::config;
+use crate::server::filter::{Filter, Value};
+use crate::server::metrics::MeteredHttpClient;
+use crate::server::normalize::{normalize_query, normalize_value};
+use crate::server::schema::{schema, IndexSchema};
+use crate::server::search_settings;
+use crate::server::tags;
 
-pub fn get_client() -> &'static Client {
-    CLIENT.get_or_init(|| {
-        let url = std::env::var("MEILI_URL").unwrap_or_else(|_| "http://localhost:7700".to_string());
-        let key = std::env::var("MEILI_MASTER_KEY").unwrap_or_else(|_| "masterKey".to_string());
-        Client::new(url, Some(key)).expect("Failed to create Meilisearch client")
+// Requests run with the scoped keys `server::keys` installs: searches with the
+// search key, everything else with the admin key. Until then, or when scoped
+// keys are turned off, both use the master key.
+struct Clients {
+    admin: Arc<Client<MeteredHttpClient>>,
+    search: Arc<Client<MeteredHttpClient>>,
+}
+
+static CLIENTS: OnceLock<RwLock<Clients>> = OnceLock::new();
+
+fn new_client(key: Option<&str>) -> Client<MeteredHttpClient> {
+    let config = config();
+    let http_client = MeteredHttpClient::new(key, config.meilisearch_timeout())
+        .expect("Failed to create Meilisearch client");
+    Client::new_with_client(&config.meilisearch.url, key, http_client)
+}
+
+fn clients() -> &'static RwLock<Clients> {
+    CLIENTS.get_or_init(|| {
+        let master = Arc::new(master_client());
+        RwLock::new(Clients {
+            admin: master.clone(),
+            search: master,
+        })
     })
 }
 
-pub async fn configure_index(index_name: &str, is_movie: bool) -> Result<(), String> {
-    let client = get_client();
-    let index = client.index(index_name);
+// Only for managing keys; requests go through `get_client` and `get_search_client`
+pub fn master_client() -> Client<MeteredHttpClient> {
+    let key = &config().meilisearch.master_key;
+    new_client(Some(key.as_str()).filter(|k| !k.is_empty()))
+}
 
-    let searchable = if is_movie {
-        vec!["title", "title_en", "description", "director", "genres"]
-    } else {
-        vec!["title", "title_en", "description", "author", "genres"]
-    };
+pub fn use_keys(admin_key: &str, search_key: &str) {
+    let mut clients = clients().write().unwrap();
+    clients.admin = Arc::new(new_client(Some(admin_key)));
+    clients.search = Arc::new(new_client(Some(search_key)));
+}
+
+pub fn get_client() -> Arc<Client<MeteredHttpClient>> {
+    clients().read().unwrap().admin.clone()
+}
+
+pub fn get_search_client() -> Arc<Client<MeteredHttpClient>> {
+    clients().read().unwrap().search.clone()
+}
+
+// An index by the name the code uses, under the uid `[indexes]` maps it to
+pub fn get_index(name: &str) -> Index<MeteredHttpClient> {
+    get_client().index(config().index_uid(name))
+}
+
+// A catalogue index for read-only searches, through the search key
+pub fn get_search_index(name: &str) -> Index<MeteredHttpClient> {
+    get_search_client().index(config().index_uid(name))
+}
+
+// App indexes are created on first write, so reading one before that is not an error
+pub fn is_index_not_found(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Meilisearch(MeilisearchError {
+            error_code: ErrorCode::IndexNotFound,
+            ..
+        })
+    )
+}
+
+// A missing document, or a missing index and with it every document
+pub fn is_document_not_found(error: &Error) -> bool {
+    is_index_not_found(error)
+        || matches!(
+            error,
+            Error::Meilisearch(MeilisearchError {
+                error_code: ErrorCode::DocumentNotFound,
+                ..
+            })
+        )
+}
 
-    let filterable = vec!["genres", "year", "rating", "language"];
-    let sortable = vec!["year", "rating", "title"];
+// Random rather than `max + 1`, so concurrent writers cannot share an id.
+// 53 bits keeps ids exact as JavaScript numbers.
+pub fn new_document_id() -> i64 {
+    (OsRng.next_u64() >> 11) as i64 + 1
+}
+
+// Every document of an index, read a page at a time; a missing index is empty
+pub async fn all_documents<T>(index_name: &str) -> Result<Vec<T>, String>
+where
+    T: serde::de::DeserializeOwned + Send + Sync + 'static,
+{
+    matching_documents(index_name, None).await
+}
+
+// Like `all_documents`, narrowed by a filter expression
+pub async fn matching_documents<T>(index_name: &str, filter: Option<&str>) -> Result<Vec<T>, String>
+where
+    T: serde::de::DeserializeOwned + Send + Sync + 'static,
+{
+    const PAGE: usize = 1000;
+    let index = get_index(index_name);
+    let mut documents = Vec::new();
+    loop {
+        let mut query = DocumentsQuery::new(&index);
+        query.with_offset(documents.len()).with_limit(PAGE);
+        if let Some(filter) = filter {
+            query.with_filter(filter);
+        }
+        let page = query.execute::<T>().await;
+        match page {
+            Ok(page) => {
+                let done = page.results.len() < PAGE;
+                documents.extend(page.results);
+                if done {
+                    return Ok(documents);
+                }
+            }
+            Err(e) if is_index_not_found(&e) => return Ok(documents),
+            Err(e) => return Err(format!("Failed to read {index_name}: {e}")),
+        }
+    }
+}
+
+// Waits for a queued task, turning a failed one into an error naming `what`
+pub async fn wait_for_task(task: Result<TaskInfo, Error>, what: &str) -> Result<(), String> {
+    let task = task
+        .map_err(|e| format!("Failed to {what}: {e}"))?
+        .wait_for_completion(&get_client(), None, None)
+        .await
+        .map_err(|e| format!("Failed waiting to {what}: {e}"))?;
+    match task {
+        Task::Failed { content } => Err(format!("Failed to {what}: {}", content.error)),
+        _ => Ok(()),
+    }
+}
+
+async fn fetch_hits<T>(index_name: &str, ids: &[i64]) -> Result<Vec<SearchHit>, String>
+where
+    T: serde::de::DeserializeOwned + Send + Sync + 'static,
+    SearchHit: From<T>,
+{
+    let index = get_index(index_name);
+    let ids: Vec<String> = ids.iter().map(i64::to_string).collect();
+    let mut query = DocumentsQuery::new(&index);
+    query
+        .with_ids(ids.iter().map(String::as_str))
+        .with_limit(ids.len());
+    query
+        .execute::<T>()
+        .await
+        .map(|results| results.results.into_iter().map(SearchHit::from).collect())
+        .map_err(|e| format!("Failed to fetch {index_name}: {e}"))
+}
+
+// Several catalogue items of one index in a single request; missing ones are left out
+pub async fn get_hits(index_name: &str, ids: &[i64]) -> Result<Vec<SearchHit>, String> {
+    match index_name {
+        "movies" => fetch_hits::<Movie>(index_name, ids).await,
+        "books" => fetch_hits::<Book>(index_name, ids).await,
+        "series" => fetch_hits::<Series>(index_name, ids).await,
+        _ => Err(format!("Unknown index: {index_name}")),
+    }
+}
+
+// Which of `ids` still have a document in a catalogue index. Partial updates
+// are upserts, so write-backs are only sent for these: anything else would
+// create a stub document without the fields searches deserialize.
+pub async fn existing_ids(index_name: &str, ids: &[i64]) -> Result<Vec<i64>, String> {
+    #[derive(serde::Deserialize)]
+    struct Id {
+        id: i64,
+    }
+    if !is_listable(index_name) {
+        return Err(format!("Unknown index: {index_name}"));
+    }
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let index = get_index(index_name);
+    let keys: Vec<String> = ids.iter().map(i64::to_string).collect();
+    let mut query = DocumentsQuery::new(&index);
+    query
+        .with_ids(keys.iter().map(String::as_str))
+        .with_fields(["id"])
+        .with_limit(keys.len());
+    match query.execute::<Id>().await {
+        Ok(results) => Ok(results.results.into_iter().map(|d| d.id).collect()),
+        Err(e) if is_index_not_found(&e) => Ok(vec![]),
+        Err(e) => Err(format!("Failed to fetch {index_name}: {e}")),
+    }
+}
+
+// A catalogue item by index name, for features that store references to items
+pub async fn get_hit(index_name: &str, id: i64) -> Option<SearchHit> {
+    let index = get_index(index_name);
+    let id = id.to_string();
+    match index_name {
+        "movies" => index
+            .get_document::<Movie>(&id)
+            .await
+            .ok()
+            .map(SearchHit::from),
+        "books" => index
+            .get_document::<Book>(&id)
+            .await
+            .ok()
+            .map(SearchHit::from),
+        "series" => index
+            .get_document::<Series>(&id)
+            .await
+            .ok()
+            .map(SearchHit::from),
+        _ => None,
+    }
+}
+
+pub async fn configure_index(index_name: &str) -> Result<(), String> {
+    let index = get_index(index_name);
+
+    let schema = schema(index_name)?;
+    let searchable = schema.searchable;
+    let filterable = schema.filterable;
+    let sortable = schema.sortable;
 
     index
-        .set_searchable_attributes(&searchable)
+        .set_searchable_attributes(searchable)
         .await
         .map_err(|e| format!("Failed to set searchable attributes: {e}"))?;
 
     index
-        .set_filterable_attributes(&filterable)
+        .set_filterable_attributes(filterable)
         .await
         .map_err(|e| format!("Failed to set filterable attributes: {e}"))?;
 
     index
-        .set_sortable_attributes(&sortable)
+        .set_sortable_attributes(sortable)
         .await
         .map_err(|e| format!("Failed to set sortable attributes: {e}"))?;
 
+    search_settings::apply(&search_settings::latest(index_name).await?).await?;
+
     Ok(())
 }
 
 pub async fn configure_web_index() -> Result<(), String> {
-    let client = get_client();
-    let index = client.index("web");
+    let index = get_index("web");
 
-    let searchable = vec!["title", "description", "url", "source_engine"];
-    let filterable = vec!["genres", "year", "rating", "language"];
-    let sortable = vec!["year", "rating", "title"];
+    let schema = schema("web")?;
+    let searchable = schema.searchable;
+    let filterable = schema.filterable;
+    let sortable = schema.sortable;
 
     index
-        .set_searchable_attributes(&searchable)
+        .set_searchable_attributes(searchable)
         .await
         .map_err(|e| format!("Failed to set searchable attributes: {e}"))?;
 
     index
-        .set_filterable_attributes(&filterable)
+        .set_filterable_attributes(filterable)
         .await
         .map_err(|e| format!("Failed to set filterable attributes: {e}"))?;
 
     index
-        .set_sortable_attributes(&sortable)
+        .set_sortable_attributes(sortable)
         .await
         .map_err(|e| format!("Failed to set sortable attributes: {e}"))?;
 
+    search_settings::apply(&search_settings::latest("web").await?).await?;
+
     Ok(())
 }
 
+fn text(value: &str) -> Value {
+    Value::Str(normalize_value(value))
+}
+
+// One argument per filter source, mirroring `SearchRequest`
+#[allow(clippy::too_many_arguments)]
 pub fn build_filter(
     genres: &[String],
+    tags: &[String],
     year_min: Option<i32>,
     year_max: Option<i32>,
     rating_min: Option<f64>,
+    scoped: &ParsedQuery,
+    movie: &MovieFilters,
+    book: &BookFilters,
+    series: &SeriesFilters,
+    schema: &IndexSchema,
 ) -> Option<String> {
     let mut conditions = Vec::new();
 
     if !genres.is_empty() {
-        let genre_filters: Vec<String> = genres
-            .iter()
-            .map(|g| format!("genres = \"{}\"", g.replace('"', "\\\"")))
-            .collect();
-        conditions.push(format!("({})", genre_filters.join(" OR ")));
+        conditions.push(Filter::Or(
+            genres.iter().map(|g| Filter::Eq("genres", text(g))).collect(),
+        ));
     }
 
-    if let Some(min) = year_min {
-        conditions.push(format!("year >= {min}"));
+    for genre in &scoped.genres {
+        conditions.push(Filter::Eq("genres", text(genre)));
+    }
+
+    for genre in &scoped.excluded_genres {
+        conditions.push(Filter::negate(Filter::Eq("genres", text(genre))));
+    }
+
+    if let Some(field) = schema.creator {
+        for creator in &scoped.creators {
+            conditions.push(Filter::Eq(field, text(creator)));
+        }
+        for creator in &scoped.excluded_creators {
+            conditions.push(Filter::negate(Filter::Eq(field, text(creator))));
+        }
+    }
+
+    if !scoped.languages.is_empty() {
+        conditions.push(Filter::In(
+            "language",
+            scoped.languages.iter().map(|l| text(l)).collect(),
+        ));
+    }
+
+    for (min, max) in [(year_min, year_max), (scoped.year_min, scoped.year_max)] {
+        match schema.period_end {
+            // Started no later than `max` and still running at `min`
+            Some(end) => {
+                conditions.push(Filter::Range {
+                    field: "year",
+                    min: None,
+                    max: max.map(Value::from),
+                });
+                if let Some(min) = min {
+                    conditions.push(Filter::Or(vec![
+                        Filter::Range {
+                            field: end,
+                            min: Some(min.into()),
+                            max: None,
+                        },
+                        Filter::IsNull(end),
+                    ]));
+                }
+            }
+            None => conditions.push(Filter::Range {
+                field: "year",
+                min: min.map(Value::from),
+                max: max.map(Value::from),
+            }),
+        }
+    }
+
+    for (min, max) in [(rating_min, None), (scoped.rating_min, scoped.rating_max)] {
+        conditions.push(Filter::Range {
+            field: "rating",
+            min: min.map(Value::from),
+            max: max.map(Value::from),
+        });
+    }
+
+    // Movie-only attributes are skipped for indexes that cannot filter on them
+    let filterable = |field: &str| schema.filterable.contains(&field);
+
+    if filterable("user_rating") {
+        conditions.push(Filter::Range {
+            field: "user_rating",
+            min: scoped.user_rating_min.map(Value::from),
+            max: scoped.user_rating_max.map(Value::from),
+        });
+    }
+
+    // Tags arrive in their canonical form, see `server::tags::canonical`
+    if filterable("tags") {
+        for tag in tags.iter().chain(&scoped.tags) {
+            conditions.push(Filter::eq("tags", tag.as_str()));
+        }
+        for tag in &scoped.excluded_tags {
+            conditions.push(Filter::negate(Filter::eq("tags", tag.as_str())));
+        }
+    }
+
+    if filterable("cast") {
+        for name in &scoped.cast {
+            conditions.push(Filter::Eq("cast", text(name)));
+        }
+    }
+
+    if filterable("runtime_minutes") {
+        let runtime = |m: u32| Value::from(i64::from(m));
+        for (min, max) in [
+            (movie.runtime_min.map(runtime), movie.runtime_max.map(runtime)),
+            (
+                scoped.runtime_min.map(Value::from),
+                scoped.runtime_max.map(Value::from),
+            ),
+        ] {
+            conditions.push(Filter::Range {
+                field: "runtime_minutes",
+                min,
+                max,
+            });
+        }
+    }
+
+    if filterable("isbn") {
+        if let Some(ref isbn) = scoped.isbn {
+            conditions.push(Filter::eq("isbn", isbn.as_str()));
+        }
+    }
+
+    for (field, values) in [
+        ("countries", &movie.countries),
+        ("certification", &movie.certifications),
+        ("publisher", &book.publishers),
+        ("format", &book.formats),
+        ("status", &series.statuses),
+    ] {
+        if filterable(field) && !values.is_empty() {
+            conditions.push(Filter::In(field, values.iter().map(|v| text(v)).collect()));
+        }
+    }
+
+    Filter::And(conditions).to_expression()
+}
+
+// A search request resolved into what is sent to Meilisearch
+pub struct PreparedSearch {
+    // Width-folded input, before the field syntax is taken out
+    pub raw_query: String,
+    // The free text part, kana-folded
+    pub query: String,
+    pub filter: Option<String>,
+}
+
+// Parses the query syntax and resolves names and tags to their stored form.
+// Shared by `search_items` and saved searches so both match the same documents.
+pub async fn prepare_search(
+    request: &SearchRequest,
+    schema: &IndexSchema,
+) -> Result<PreparedSearch, String> {
+    // Facet panel selections, rejected on indexes that do not offer the facet
+    for (facet, selected) in [("genres", &request.genres), ("tags", &request.tags)] {
+        if !selected.is_empty() {
+            schema.check_facet(facet)?;
+        }
+    }
+    let raw_query = normalize_value(&request.query);
+    let mut parsed = parse_query(&raw_query).map_err(|e| e.to_string())?;
+    // Indexes without ISBNs search a typed ISBN as plain text instead
+    if !schema.filterable.contains(&"isbn") {
+        if let Some(isbn) = parsed.isbn.take() {
+            parsed.text = format!("{} {isbn}", parsed.text).trim().to_string();
+        }
+    }
+    let query = normalize_query(&parsed.text);
+
+    if let Some(creator_field) = schema.creator {
+        for creator in parsed
+            .creators
+            .iter_mut()
+            .chain(parsed.excluded_creators.iter_mut())
+        {
+            *creator = resolve_creator(&request.index, creator_field, creator).await?;
+        }
+    }
+    if schema.filterable.contains(&"cast") {
+        for name in parsed.cast.iter_mut() {
+            *name = resolve_creator(&request.index, "cast", name).await?;
+        }
+    }
+
+    // Tags are matched in the form they were stored in, after synonyms
+    let mut tags = request.tags.clone();
+    if schema.filterable.contains(&"tags")
+        && !(tags.is_empty() && parsed.tags.is_empty() && parsed.excluded_tags.is_empty())
+    {
+        let rules = tags::rules().await;
+        for list in [&mut tags, &mut parsed.tags, &mut parsed.excluded_tags] {
+            *list = list
+                .iter()
+                .filter_map(|t| tags::canonical(&rules, t))
+                .collect();
+        }
+    }
+
+    let filter = build_filter(
+        &request.genres,
+        &tags,
+        request.year_min,
+        request.year_max,
+        request.rating_min,
+        &parsed,
+        &request.movie_filters,
+        &request.book_filters,
+        &request.series_filters,
+        schema,
+    );
+
+    Ok(PreparedSearch {
+        raw_query,
+        query,
+        filter,
+    })
+}
+
+// Maps a partial creator name from `director:宮崎` to the stored value it
+// stands for, e.g. "宮崎駿". A name that is stored as typed wins; a partial
+// name matching several people is an error rather than a guess.
+pub async fn resolve_creator(
+    index_name: &str,
+    creator_field: &'static str,
+    term: &str,
+) -> Result<String, String> {
+    let index = get_search_index(index_name);
+    let term = normalize_value(term);
+
+    let mut query = index.facet_search(creator_field);
+    query.with_facet_query(&term);
+
+    let values: Vec<String> = match query.execute().await {
+        Ok(response) => response.facet_hits.into_iter().map(|hit| hit.value).collect(),
+        Err(_) => return Ok(term),
+    };
+    pick_creator(&term, values)
+}
+
+fn pick_creator(term: &str, values: Vec<String>) -> Result<String, String> {
+    let folded = term.to_lowercase();
+    if let Some(exact) = values.iter().find(|v| v.to_lowercase() == folded) {
+        return Ok(exact.clone());
+    }
+    match values.as_slice() {
+        [] => Ok(term.to_string()),
+        [only] => Ok(only.clone()),
+        several => Err(format!(
+            "「{term}」に一致する名前が複数あります: {}",
+            several.join(", ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn year_filter(index: &str, min: Option<i32>, max: Option<i32>) -> Option<String> {
+        build_filter(
+            &[],
+            &[],
+            min,
+            max,
+            None,
+            &ParsedQuery::default(),
+            &MovieFilters::default(),
+            &BookFilters::default(),
+            &SeriesFilters::default(),
+            schema(index).unwrap(),
+        )
     }
 
-    if let Some(max) = year_max {
-        conditions.push(format!("year <= {max}"));
+    #[test]
+    fn series_years_match_any_overlap_with_the_broadcast_period() {
+        assert_eq!(
+            year_filter("series", Some(2015), Some(2020)).as_deref(),
+            Some("(year <= 2020) AND ((end_year >= 2015) OR (end_year IS NULL))")
+        );
+        // Open-ended ranges only bound one side of the period
+        assert_eq!(
+            year_filter("series", Some(2015), None).as_deref(),
+            Some("((end_year >= 2015) OR (end_year IS NULL))")
+        );
+        assert_eq!(
+            year_filter("series", None, Some(2020)).as_deref(),
+            Some("(year <= 2020)")
+        );
+        assert_eq!(year_filter("series", None, None), None);
     }
 
-    if let Some(min) = rating_min {
-        conditions.push(format!("rating >= {min}"));
+    #[test]
+    fn partial_names_must_match_one_person() {
+        let names = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(pick_creator("宮崎駿", names(&["宮崎駿"])).unwrap(), "宮崎駿");
+        assert_eq!(pick_creator("nolan", names(&["Christopher Nolan"])).unwrap(), "Christopher Nolan");
+        // Nothing stored matches; the filter then finds nothing either
+        assert_eq!(pick_creator("誰か", vec![]).unwrap(), "誰か");
+        // Typed in full, the name wins over longer ones it is part of
+        assert_eq!(
+            pick_creator("christopher nolan", names(&["Christopher Nolan", "Christopher Nolan Jr."])).unwrap(),
+            "Christopher Nolan"
+        );
+        assert_eq!(
+            pick_creator("宮崎", names(&["宮崎駿", "宮崎吾朗"])).unwrap_err(),
+            "「宮崎」に一致する名前が複数あります: 宮崎駿, 宮崎吾朗"
+        );
     }
 
-    if conditions.is_empty() {
-        None
-    } else {
-        Some(conditions.join(" AND "))
+    #[test]
+    fn other_indexes_filter_on_the_year_alone() {
+        assert_eq!(
+            year_filter("movies", Some(2015), Some(2020)).as_deref(),
+            Some("(year >= 2015 AND year <= 2020)")
+        );
+        assert_eq!(
+            year_filter("books", Some(2015), None).as_deref(),
+            Some("(year >= 2015)")
+        );
     }
 }
@@ -0,0 +1,308 @@
+// Prometheus metrics, served in text format at `/metrics`. Requests are counted
+// by the `track_requests` middleware, Meilisearch calls by `MeteredHttpClient`
+// (every SDK call goes through it), and index document counts are polled by a
+// job in `main.rs`.
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::HttpResponse;
+use async_trait::async_trait;
+use meilisearch_sdk::errors::Error;
+use meilisearch_sdk::request::{HttpClient, Method};
+use meilisearch_sdk::reqwest::ReqwestClient;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::Instrument;
+
+use crate::server::meilisearch::get_client;
+
+pub const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    meilisearch_duration: HistogramVec,
+    meilisearch_errors: IntCounterVec,
+    searxng_duration: HistogramVec,
+    cache_lookups: IntCounterVec,
+    index_documents: IntGaugeVec,
+}
+
+impl Metrics {
+    fn new() -> Metrics {
+        let registry = Registry::new();
+        let metrics = Metrics {
+            requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests by handler and status"),
+                &["handler", "status"],
+            )
+            .unwrap(),
+            request_duration: HistogramVec::new(
+                HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by handler"),
+                &["handler"],
+            )
+            .unwrap(),
+            meilisearch_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "meilisearch_request_duration_seconds",
+                    "Meilisearch API call latency by operation",
+                ),
+                &["operation"],
+            )
+            .unwrap(),
+            meilisearch_errors: IntCounterVec::new(
+                Opts::new("meilisearch_request_errors_total", "Failed Meilisearch API calls by operation"),
+                &["operation"],
+            )
+            .unwrap(),
+            searxng_duration: HistogramVec::new(
+                HistogramOpts::new("searxng_request_duration_seconds", "SearXNG search latency by outcome"),
+                &["outcome"],
+            )
+            .unwrap(),
+            cache_lookups: IntCounterVec::new(
+                Opts::new("cache_lookups_total", "In-process cache lookups by cache and result"),
+                &["cache", "result"],
+            )
+            .unwrap(),
+            index_documents: IntGaugeVec::new(
+                Opts::new("meilisearch_index_documents", "Documents per Meilisearch index"),
+                &["index"],
+            )
+            .unwrap(),
+            registry,
+        };
+        let collectors: [Box<dyn prometheus::core::Collector>; 7] = [
+            Box::new(metrics.requests.clone()),
+            Box::new(metrics.request_duration.clone()),
+            Box::new(metrics.meilisearch_duration.clone()),
+            Box::new(metrics.meilisearch_errors.clone()),
+            Box::new(metrics.searxng_duration.clone()),
+            Box::new(metrics.cache_lookups.clone()),
+            Box::new(metrics.index_documents.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector).unwrap();
+        }
+        metrics
+    }
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+// Server function URL paths to their type names, filled in at registration
+static SERVER_FNS: OnceLock<RwLock<HashMap<String, &'static str>>> = OnceLock::new();
+
+fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+fn server_fns() -> &'static RwLock<HashMap<String, &'static str>> {
+    SERVER_FNS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+// Labels requests to `path` with the server function's name, e.g. `SearchItems`
+pub fn name_server_fn(path: &str, name: &'static str) {
+    server_fns().write().unwrap().insert(path.to_string(), name);
+}
+
+// Bounded label for a request path, so page URLs with ids do not each get a series
+pub fn handler_label(path: &str) -> String {
+    if let Some(name) = server_fns().read().unwrap().get(path) {
+        return name.to_string();
+    }
+    let label = if path == "/metrics" {
+        "metrics"
+    } else if path == "/healthz" || path == "/readyz" {
+        "health"
+    } else if path.starts_with("/pkg/") || path.starts_with("/assets/") {
+        "static"
+    } else if path.starts_with("/feeds/") {
+        "feed"
+    } else if path.starts_with("/api/") {
+        "unknown_server_fn"
+    } else {
+        "page"
+    };
+    label.to_string()
+}
+
+pub async fn track_requests(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let handler = handler_label(request.path());
+    let started = Instant::now();
+    let response = next.call(request).await;
+    let status = match &response {
+        Ok(r) => r.status().as_u16(),
+        Err(e) => e.as_response_error().status_code().as_u16(),
+    };
+    let m = metrics();
+    m.requests
+        .with_label_values(&[handler.as_str(), &status.to_string()])
+        .inc();
+    m.request_duration
+        .with_label_values(&[handler.as_str()])
+        .observe(started.elapsed().as_secs_f64());
+    response
+}
+
+pub async fn endpoint() -> HttpResponse {
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    if let Err(e) = encoder.encode(&metrics().registry.gather(), &mut buffer) {
+        return HttpResponse::InternalServerError().body(format!("Failed to encode metrics: {e}"));
+    }
+    HttpResponse::Ok()
+        .content_type(encoder.format_type())
+        .body(buffer)
+}
+
+// The kind of Meilisearch API call, from its URL
+pub fn meilisearch_operation(url: &str) -> &'static str {
+    let path = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest.split_once('/').map_or("", |(_, p)| p));
+    let path = path.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        ["indexes", _, "search", ..] => "search",
+        ["indexes", _, "facet-search", ..] => "facet_search",
+        ["indexes", _, "similar", ..] => "similar",
+        ["indexes", _, "documents", ..] => "documents",
+        ["indexes", _, "settings", ..] => "settings",
+        ["indexes", _, "stats", ..] | ["stats", ..] => "stats",
+        ["indexes", ..] => "indexes",
+        ["multi-search", ..] => "multi_search",
+        ["tasks", ..] => "tasks",
+        ["health", ..] => "health",
+        ["keys", ..] => "keys",
+        _ => "other",
+    }
+}
+
+pub fn observe_meilisearch(operation: &str, elapsed: Duration, ok: bool) {
+    let m = metrics();
+    m.meilisearch_duration
+        .with_label_values(&[operation])
+        .observe(elapsed.as_secs_f64());
+    if !ok {
+        m.meilisearch_errors.with_label_values(&[operation]).inc();
+    }
+}
+
+pub fn observe_searxng(elapsed: Duration, ok: bool) {
+    metrics()
+        .searxng_duration
+        .with_label_values(&[if ok { "ok" } else { "error" }])
+        .observe(elapsed.as_secs_f64());
+}
+
+pub fn cache_lookup(cache: &str, hit: bool) {
+    metrics()
+        .cache_lookups
+        .with_label_values(&[cache, if hit { "hit" } else { "miss" }])
+        .inc();
+}
+
+// Refreshes the document count gauge of every index
+pub async fn poll_index_documents() -> Result<(), String> {
+    let stats = get_client()
+        .get_stats()
+        .await
+        .map_err(|e| format!("Failed to read Meilisearch stats: {e}"))?;
+    let gauge = &metrics().index_documents;
+    gauge.reset();
+    for (index, index_stats) in stats.indexes {
+        gauge
+            .with_label_values(&[index.as_str()])
+            .set(index_stats.number_of_documents as i64);
+    }
+    Ok(())
+}
+
+// The SDK's reqwest client with every call bounded by `meilisearch.timeout_secs`,
+// timed, failures counted and a span logged under the current request
+#[derive(Debug, Clone)]
+pub struct MeteredHttpClient {
+    inner: ReqwestClient,
+    timeout: Duration,
+}
+
+impl MeteredHttpClient {
+    pub fn new(api_key: Option<&str>, timeout: Duration) -> Result<Self, Error> {
+        Ok(MeteredHttpClient {
+            inner: ReqwestClient::new(api_key)?,
+            timeout,
+        })
+    }
+}
+
+#[async_trait]
+impl HttpClient for MeteredHttpClient {
+    async fn stream_request<
+        Query: Serialize + Send + Sync,
+        Body: futures_io::AsyncRead + Send + Sync + 'static,
+        Output: DeserializeOwned + 'static,
+    >(
+        &self,
+        url: &str,
+        method: Method<Query, Body>,
+        content_type: &str,
+        expected_status_code: u16,
+    ) -> Result<Output, Error> {
+        let operation = meilisearch_operation(url);
+        let span = tracing::debug_span!("meilisearch", operation);
+        let started = Instant::now();
+        let call = self
+            .inner
+            .stream_request(url, method, content_type, expected_status_code)
+            .instrument(span.clone());
+        let result = actix_web::rt::time::timeout(self.timeout, call)
+            .await
+            .unwrap_or(Err(Error::Timeout));
+        let elapsed = started.elapsed();
+        observe_meilisearch(operation, elapsed, result.is_ok());
+
+        let elapsed_ms = elapsed.as_millis() as u64;
+        span.in_scope(|| match &result {
+            Ok(_) => tracing::debug!(elapsed_ms, "meilisearch call"),
+            // Answers such as "document not found" are often expected by the caller
+            Err(e @ Error::Meilisearch(_)) => tracing::debug!(error = %e, elapsed_ms, "meilisearch error"),
+            Err(e) => tracing::warn!(error = %e, elapsed_ms, "meilisearch call failed"),
+        });
+        result
+    }
+
+    fn is_tokio(&self) -> bool {
+        self.inner.is_tokio()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operations_from_urls() {
+        let op = |path: &str| meilisearch_operation(&format!("http://localhost:7700{path}"));
+        assert_eq!(op("/indexes/movies/search"), "search");
+        assert_eq!(op("/indexes/books/documents/12"), "documents");
+        assert_eq!(op("/indexes/books/documents?limit=20"), "documents");
+        assert_eq!(op("/indexes/books/settings/synonyms"), "settings");
+        assert_eq!(op("/indexes/books/facet-search"), "facet_search");
+        assert_eq!(op("/indexes/books"), "indexes");
+        assert_eq!(op("/tasks/42"), "tasks");
+        assert_eq!(op("/stats"), "stats");
+        assert_eq!(op("/version"), "other");
+    }
+}
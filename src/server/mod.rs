@@ -1,3 +1,27 @@
+pub mod analytics;
+pub mod collections;
+pub mod config;
+pub mod did_you_mean;
+pub mod embedding;
+pub mod filter;
+pub mod health;
+pub mod kana;
+pub mod keys;
 pub mod meilisearch;
+pub mod metrics;
+pub mod normalize;
+pub mod people;
+pub mod reading;
+pub mod reviews;
+pub mod saved_searches;
+pub mod schema;
+pub mod search_settings;
 pub mod searxng;
 pub mod seed;
+pub mod similar;
+pub mod suggest;
+pub mod tags;
+pub mod telemetry;
+pub mod users;
+pub mod watchlist;
+pub mod web_fallback;
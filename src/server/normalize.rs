@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::server::config::config;
+use crate::server::kana::{to_hiragana, to_katakana};
+
+// `search.kana_folding` (QUERY_KANA_FOLDING): fold query kana to one script
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KanaFolding {
+    Off,
+    Hiragana,
+    Katakana,
+}
+
+// Full-width ASCII (U+FF01..U+FF5E) and the ideographic space NFKC may leave behind
+fn fullwidth_to_ascii(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// NFKC + width folding, used for filter values that must match stored facets exactly
+pub fn normalize_value(text: &str) -> String {
+    let folded: String = text.nfkc().map(fullwidth_to_ascii).collect();
+    collapse_whitespace(&folded)
+}
+
+pub fn normalize_query_with(text: &str, folding: KanaFolding) -> String {
+    let normalized = normalize_value(text);
+    match folding {
+        KanaFolding::Off => normalized,
+        KanaFolding::Hiragana => to_hiragana(&normalized),
+        KanaFolding::Katakana => to_katakana(&normalized),
+    }
+}
+
+pub fn normalize_query(text: &str) -> String {
+    normalize_query_with(text, config().search.kana_folding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_fullwidth_ascii() {
+        assert_eq!(normalize_value("ＳＦ"), "SF");
+        assert_eq!(normalize_value("ｆａｎｔａｓｙ　２０２４"), "fantasy 2024");
+        assert_eq!(normalize_value("＃１"), "#1");
+    }
+
+    #[test]
+    fn composes_halfwidth_katakana() {
+        assert_eq!(normalize_value("ｽﾀｰｳｫｰｽﾞ"), "スターウォーズ");
+        assert_eq!(normalize_value("ｶﾞﾝﾀﾞﾑ"), "ガンダム");
+        assert_eq!(normalize_value("ﾊﾟﾗｻｲﾄ"), "パラサイト");
+    }
+
+    #[test]
+    fn keeps_canonical_text_unchanged() {
+        for text in [
+            "千と千尋の神隠し",
+            "Spirited Away",
+            "SF",
+            "1Q84",
+            "君の名は。",
+        ] {
+            assert_eq!(normalize_value(text), text);
+        }
+    }
+
+    #[test]
+    fn collapses_whitespace() {
+        assert_eq!(normalize_value("  宮崎　　駿 "), "宮崎 駿");
+        assert_eq!(normalize_value("\t\n"), "");
+        assert_eq!(normalize_value(""), "");
+    }
+
+    #[test]
+    fn nfkc_expands_compatibility_characters() {
+        assert_eq!(normalize_value("㈱"), "(株)");
+        assert_eq!(normalize_value("①"), "1");
+        assert_eq!(normalize_value("ﬁlm"), "film");
+    }
+
+    #[test]
+    fn kana_folding_is_optional() {
+        assert_eq!(normalize_query_with("トトロ", KanaFolding::Off), "トトロ");
+        assert_eq!(
+            normalize_query_with("トトロ", KanaFolding::Hiragana),
+            "ととろ"
+        );
+        assert_eq!(
+            normalize_query_with("ととろ", KanaFolding::Katakana),
+            "トトロ"
+        );
+    }
+
+    #[test]
+    fn kana_folding_handles_mixed_and_halfwidth_input() {
+        assert_eq!(
+            normalize_query_with("もののけﾋﾒ", KanaFolding::Hiragana),
+            "もののけひめ"
+        );
+        assert_eq!(
+            normalize_query_with("スター・うぉーず", KanaFolding::Katakana),
+            "スター・ウォーズ"
+        );
+        // Kanji, Latin and the prolonged sound mark are left alone
+        assert_eq!(
+            normalize_query_with("宮崎 ＳＦ ー", KanaFolding::Hiragana),
+            "宮崎 SF ー"
+        );
+    }
+}
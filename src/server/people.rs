@@ -0,0 +1,295 @@
+// People are the single source for creator names. Movies and books keep the
+// display name alongside `director_id` / `author_id` so results render without
+// a second lookup, and `migrate` links existing free-text names to people.
+
+use crate::model::book::Book;
+use crate::model::movie::Movie;
+use crate::model::person::{MigrationResult, Person, Role};
+use crate::model::search::SearchHit;
+use crate::server::embedding::with_vectors;
+use crate::server::filter::Filter;
+use crate::server::meilisearch::{all_documents, get_client, get_index, new_document_id};
+use crate::server::normalize::normalize_value;
+use crate::server::reading::{annotate_book, annotate_movie, annotate_person};
+
+pub const PEOPLE_INDEX: &str = "people";
+// Upper bound on the works listed per person
+const SCAN_LIMIT: usize = 1000;
+
+// Linking reads the people, adds the missing ones and stores them; one at a
+// time, so two saves naming the same new person do not both create them
+static LINKING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn bundled_people() -> Vec<Person> {
+    let data: &str = include_str!("../../seed/people.json");
+    serde_json::from_str(data).unwrap_or_default()
+}
+
+fn key(name: &str) -> String {
+    normalize_value(name).to_lowercase()
+}
+
+pub fn is_known_as(person: &Person, name: &str) -> bool {
+    let name = key(name);
+    key(&person.name) == name || person.aliases.iter().any(|a| key(a) == name)
+}
+
+// Id of the person known by `name`, adding them (or the role) when missing
+pub fn link(people: &mut Vec<Person>, name: &str, role: Role) -> Option<i64> {
+    if name.trim().is_empty() {
+        return None;
+    }
+    if let Some(person) = people.iter_mut().find(|p| is_known_as(p, name)) {
+        if !person.roles.contains(&role) {
+            person.roles.push(role);
+        }
+        return Some(person.id);
+    }
+
+    let id = new_document_id();
+    let mut person = Person {
+        id,
+        name: normalize_value(name),
+        name_reading: None,
+        aliases: vec![],
+        roles: vec![role],
+        bio: None,
+    };
+    annotate_person(&mut person);
+    people.push(person);
+    Some(id)
+}
+
+fn name_of(people: &[Person], id: i64) -> Option<String> {
+    people.iter().find(|p| p.id == id).map(|p| p.name.clone())
+}
+
+// Links the director and switches to the canonical name; true when anything changed
+pub fn link_movie(people: &mut Vec<Person>, movie: &mut Movie) -> bool {
+    let id = link(people, &movie.director, Role::Director);
+    let name = id.and_then(|id| name_of(people, id));
+    let changed = id != movie.director_id || name.as_ref().is_some_and(|n| *n != movie.director);
+    movie.director_id = id;
+    if let Some(name) = name.filter(|n| *n != movie.director) {
+        movie.director = name;
+        movie.director_reading = None;
+    }
+    annotate_movie(movie);
+    changed
+}
+
+pub fn link_book(people: &mut Vec<Person>, book: &mut Book) -> bool {
+    let id = link(people, &book.author, Role::Author);
+    let name = id.and_then(|id| name_of(people, id));
+    let changed = id != book.author_id || name.as_ref().is_some_and(|n| *n != book.author);
+    book.author_id = id;
+    if let Some(name) = name.filter(|n| *n != book.author) {
+        book.author = name;
+        book.author_reading = None;
+    }
+    annotate_book(book);
+    changed
+}
+
+async fn configure_people_index() -> Result<(), String> {
+    let index = get_index(PEOPLE_INDEX);
+
+    index
+        .set_searchable_attributes(["name", "name_reading", "aliases"])
+        .await
+        .map_err(|e| format!("Failed to set searchable attributes: {e}"))?;
+
+    index
+        .set_filterable_attributes(["roles"])
+        .await
+        .map_err(|e| format!("Failed to set filterable attributes: {e}"))?;
+
+    Ok(())
+}
+
+// The people to link against and those already stored, which are compared
+// with them afterwards. Falls back to the bundled list until the index has
+// been populated; the empty baseline then makes the first save store it.
+async fn load_people() -> Result<(Vec<Person>, Vec<Person>), String> {
+    let stored = all_documents::<Person>(PEOPLE_INDEX).await?;
+    if !stored.is_empty() {
+        return Ok((stored.clone(), stored));
+    }
+    let mut people = bundled_people();
+    people.iter_mut().for_each(annotate_person);
+    Ok((people, stored))
+}
+
+async fn store<T: serde::Serialize + Send + Sync>(
+    index_name: &str,
+    documents: &[T],
+) -> Result<(), String> {
+    if documents.is_empty() {
+        return Ok(());
+    }
+    let client = get_client();
+    let task = get_index(index_name)
+        .add_documents(documents, Some("id"))
+        .await
+        .map_err(|e| format!("Failed to store {index_name}: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for {index_name}: {e}"))?;
+
+    Ok(())
+}
+
+async fn store_changed_people(before: &[Person], people: &[Person]) -> Result<(), String> {
+    let changed: Vec<&Person> = people.iter().filter(|p| !before.contains(p)).collect();
+    store(PEOPLE_INDEX, &changed).await?;
+    configure_people_index().await
+}
+
+// Links every movie and book to a person, creating people for unknown names
+pub async fn migrate() -> Result<MigrationResult, String> {
+    let _guard = LINKING.lock().await;
+    let (mut people, before) = load_people().await?;
+
+    let mut movies = all_documents::<Movie>("movies").await?;
+    movies.retain_mut(|m| link_movie(&mut people, m));
+    let mut books = all_documents::<Book>("books").await?;
+    books.retain_mut(|b| link_book(&mut people, b));
+
+    store_changed_people(&before, &people).await?;
+    store("movies", &with_vectors(&movies)?).await?;
+    store("books", &with_vectors(&books)?).await?;
+
+    Ok(MigrationResult {
+        people_count: people.len(),
+        movies_linked: movies.len(),
+        books_linked: books.len(),
+    })
+}
+
+// Used when a single movie or book is written
+pub async fn link_one(movie: Option<&mut Movie>, book: Option<&mut Book>) -> Result<(), String> {
+    let _guard = LINKING.lock().await;
+    let (mut people, before) = load_people().await?;
+    if let Some(movie) = movie {
+        link_movie(&mut people, movie);
+    }
+    if let Some(book) = book {
+        link_book(&mut people, book);
+    }
+    store_changed_people(&before, &people).await
+}
+
+pub async fn get(id: i64) -> Result<Person, String> {
+    get_index(PEOPLE_INDEX)
+        .get_document(&id.to_string())
+        .await
+        .map_err(|e| format!("Person not found: {e}"))
+}
+
+pub async fn works(id: i64) -> Result<Vec<SearchHit>, String> {
+    let mut works = Vec::new();
+
+    let filter = Filter::eq("director_id", id)
+        .to_expression()
+        .unwrap_or_default();
+    let movies_index = get_index("movies");
+    let mut search = movies_index.search();
+    search.with_query("");
+    search.with_filter(&filter);
+    search.with_sort(&["year:asc"]);
+    search.with_limit(SCAN_LIMIT);
+    let movies = search
+        .execute::<Movie>()
+        .await
+        .map_err(|e| format!("Failed to load works: {e}"))?;
+    works.extend(movies.hits.into_iter().map(|h| SearchHit::from(h.result)));
+
+    let filter = Filter::eq("author_id", id)
+        .to_expression()
+        .unwrap_or_default();
+    let books_index = get_index("books");
+    let mut search = books_index.search();
+    search.with_query("");
+    search.with_filter(&filter);
+    search.with_sort(&["year:asc"]);
+    search.with_limit(SCAN_LIMIT);
+    let books = search
+        .execute::<Book>()
+        .await
+        .map_err(|e| format!("Failed to load works: {e}"))?;
+    works.extend(books.hits.into_iter().map(|h| SearchHit::from(h.result)));
+
+    works.sort_by_key(|w| w.year);
+    Ok(works)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn movie(director: &str) -> Movie {
+        Movie {
+            id: 1,
+            title: "千と千尋の神隠し".into(),
+            title_en: None,
+            title_reading: None,
+            description: String::new(),
+            director: director.into(),
+            director_reading: None,
+            director_id: None,
+            year: 2001,
+            genres: vec![],
+            rating: 8.6,
+            poster_url: None,
+            language: "ja".into(),
+            runtime_minutes: None,
+            cast: vec![],
+            countries: vec![],
+            certification: None,
+            release_date: None,
+            original_language: None,
+            spoken_languages: vec![],
+            external_ids: Default::default(),
+            user_rating: None,
+            user_rating_count: 0,
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn aliases_link_to_the_same_person() {
+        let mut people = bundled_people();
+        let a = link(&mut people, "宮崎駿", Role::Director);
+        let b = link(&mut people, "hayao miyazaki", Role::Director);
+        let c = link(&mut people, "Ｈａｙａｏ　Ｍｉｙａｚａｋｉ", Role::Director);
+        assert_eq!(a, Some(1));
+        assert_eq!(a, b);
+        assert_eq!(a, c);
+        assert_eq!(people.len(), bundled_people().len());
+    }
+
+    #[test]
+    fn unknown_names_become_new_people() {
+        let mut people = bundled_people();
+        let id = link(&mut people, "山田洋次", Role::Director).unwrap();
+        assert!(bundled_people().iter().all(|p| p.id != id));
+        assert_eq!(link(&mut people, "山田洋次", Role::Author), Some(id));
+        let person = people.last().unwrap();
+        assert_eq!(person.id, id);
+        assert_eq!(person.roles, vec![Role::Director, Role::Author]);
+        assert_eq!(link(&mut people, "  ", Role::Author), None);
+    }
+
+    #[test]
+    fn linking_a_movie_uses_the_canonical_name() {
+        let mut people = bundled_people();
+        let mut m = movie("Hayao Miyazaki");
+        assert!(link_movie(&mut people, &mut m));
+        assert_eq!(m.director, "宮崎駿");
+        assert_eq!(m.director_id, Some(1));
+        assert!(m.director_reading.is_some());
+        // Already linked documents are left alone on the next run
+        assert!(!link_movie(&mut people, &mut m));
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::model::book::Book;
+use crate::model::movie::Movie;
+use crate::model::person::Person;
+use crate::model::reading::Readings;
+use crate::model::series::Series;
+use crate::server::kana::{is_kana_only, to_hiragana, to_katakana, to_romaji};
+
+static DICTIONARY: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+// Surface form → hiragana reading for titles and names that can't be derived from kana alone
+fn dictionary() -> &'static HashMap<String, String> {
+    DICTIONARY.get_or_init(|| {
+        let data: &str = include_str!("../../seed/readings.json");
+        serde_json::from_str(data).unwrap_or_default()
+    })
+}
+
+pub fn readings_for(text: &str) -> Option<Readings> {
+    let hiragana = match dictionary().get(text.trim()) {
+        Some(reading) => reading.clone(),
+        None if is_kana_only(text) => to_hiragana(text.trim()).replace('・', " "),
+        None => return None,
+    };
+
+    Some(Readings {
+        katakana: Some(to_katakana(&hiragana)),
+        romaji: Some(to_romaji(&hiragana)),
+        hiragana: Some(hiragana),
+        source: Some(text.trim().to_string()),
+    })
+}
+
+// Fills in missing readings, keeping any that were supplied for the current
+// text. Readings made for an earlier text (the title or name was edited) are
+// dropped and derived again; supplied ones without a source count as current.
+fn merge(existing: Option<Readings>, text: &str) -> Option<Readings> {
+    let text = text.trim();
+    let existing = existing.filter(|e| e.source.as_deref().is_none_or(|s| s == text));
+    let merged = match (existing, readings_for(text)) {
+        (Some(e), Some(d)) => Some(Readings {
+            hiragana: e.hiragana.or(d.hiragana),
+            katakana: e.katakana.or(d.katakana),
+            romaji: e.romaji.or(d.romaji),
+            source: None,
+        }),
+        (e, d) => e.or(d),
+    };
+    merged.map(|r| Readings {
+        source: Some(text.to_string()),
+        ..r
+    })
+}
+
+pub fn annotate_movie(movie: &mut Movie) {
+    movie.title_reading = merge(movie.title_reading.take(), &movie.title);
+    movie.director_reading = merge(movie.director_reading.take(), &movie.director);
+}
+
+pub fn annotate_book(book: &mut Book) {
+    book.title_reading = merge(book.title_reading.take(), &book.title);
+    book.author_reading = merge(book.author_reading.take(), &book.author);
+}
+
+pub fn annotate_series(series: &mut Series) {
+    series.title_reading = merge(series.title_reading.take(), &series.title);
+}
+
+pub fn annotate_person(person: &mut Person) {
+    person.name_reading = merge(person.name_reading.take(), &person.name);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supplied(hiragana: &str, source: Option<&str>) -> Option<Readings> {
+        Some(Readings {
+            hiragana: Some(hiragana.into()),
+            source: source.map(String::from),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn kana_titles_derive_all_readings() {
+        let readings = readings_for("トトロ").unwrap();
+        assert_eq!(readings.hiragana.as_deref(), Some("ととろ"));
+        assert_eq!(readings.katakana.as_deref(), Some("トトロ"));
+        assert_eq!(readings.romaji.as_deref(), Some("totoro"));
+        assert_eq!(readings.source.as_deref(), Some("トトロ"));
+        assert_eq!(readings_for("Star Wars"), None);
+    }
+
+    #[test]
+    fn overrides_for_the_current_text_are_kept() {
+        let merged = merge(supplied("ととろ", Some("トトロ")), "トトロ").unwrap();
+        assert_eq!(merged.hiragana.as_deref(), Some("ととろ"));
+        assert_eq!(merged.romaji.as_deref(), Some("totoro"));
+
+        // Supplied with the document, before any source was recorded
+        let merged = merge(supplied("すたあうぉおず", None), "Star Wars").unwrap();
+        assert_eq!(merged.hiragana.as_deref(), Some("すたあうぉおず"));
+        assert_eq!(merged.source.as_deref(), Some("Star Wars"));
+    }
+
+    #[test]
+    fn readings_of_an_earlier_text_are_replaced() {
+        let merged = merge(supplied("ととろ", Some("トトロ")), "ポニョ").unwrap();
+        assert_eq!(merged.hiragana.as_deref(), Some("ぽにょ"));
+        assert_eq!(merged.romaji.as_deref(), Some("ponyo"));
+        assert_eq!(merge(supplied("ととろ", Some("トトロ")), "Ponyo"), None);
+    }
+}
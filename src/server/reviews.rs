@@ -0,0 +1,361 @@
+// User scores and reviews. Reviews live in their own `reviews` index so they
+// can be searched on their own; after every change the item's visible scores
+// are aggregated and written back onto the catalogue document as
+// `user_rating` / `user_rating_count`, where they can be sorted and filtered
+// like any other attribute.
+
+use std::collections::HashMap;
+
+use crate::model::review::{FlagReason, RatingSummary, Review, ReviewPage, ReviewStatus};
+use crate::model::user::{is_listable, User};
+use crate::server::config::config;
+use crate::server::filter::{Filter, Value};
+use crate::server::meilisearch::{
+    all_documents, existing_ids, get_client, get_hit, get_index, is_index_not_found,
+    wait_for_task,
+};
+use crate::server::users::now;
+
+pub const REVIEWS_INDEX: &str = "reviews";
+// Upper bound on reviews listed for moderation
+const SCAN_LIMIT: usize = 1000;
+
+static CONFIGURED: tokio::sync::OnceCell<()> = tokio::sync::OnceCell::const_new();
+
+// Once per process, and waited for, as the aggregate filters on these
+async fn configure_reviews_index() -> Result<(), String> {
+    CONFIGURED
+        .get_or_try_init(|| async {
+            let index = get_index(REVIEWS_INDEX);
+            wait_for_task(
+                index
+                    .set_searchable_attributes(["body", "item_title", "username"])
+                    .await,
+                "set searchable attributes",
+            )
+            .await?;
+            wait_for_task(
+                index
+                    .set_filterable_attributes([
+                        "index",
+                        "item_id",
+                        "user_id",
+                        "status",
+                        "score",
+                        "flag_count",
+                    ])
+                    .await,
+                "set filterable attributes",
+            )
+            .await?;
+            wait_for_task(
+                index
+                    .set_sortable_attributes(["updated_at", "score", "flag_count"])
+                    .await,
+                "set sortable attributes",
+            )
+            .await
+        })
+        .await
+        .copied()
+}
+
+async fn store(review: &Review) -> Result<(), String> {
+    configure_reviews_index().await?;
+    let client = get_client();
+    let task = get_index(REVIEWS_INDEX)
+        .add_documents(std::slice::from_ref(review), Some("id"))
+        .await
+        .map_err(|e| format!("Failed to store review: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for review: {e}"))?;
+    Ok(())
+}
+
+async fn get(id: &str) -> Option<Review> {
+    get_index(REVIEWS_INDEX)
+        .get_document::<Review>(id)
+        .await
+        .ok()
+}
+
+fn item_filter(index: &str, id: i64) -> Filter {
+    Filter::And(vec![
+        Filter::eq("index", index),
+        Filter::eq("item_id", id),
+        Filter::eq("status", ReviewStatus::Visible.as_str()),
+    ])
+}
+
+// An error rather than an empty summary, which would read as "no ratings"
+pub async fn summary(index: &str, id: i64) -> Result<RatingSummary, String> {
+    let Some(expression) = item_filter(index, id).to_expression() else {
+        return Ok(RatingSummary::default());
+    };
+    let ms_index = get_index(REVIEWS_INDEX);
+    let facets = ["score"];
+    let mut search = ms_index.search();
+    search.with_limit(0);
+    search.with_filter(&expression);
+    search.with_facets(meilisearch_sdk::search::Selectors::Some(&facets));
+
+    let distribution: HashMap<String, HashMap<String, usize>> =
+        match search.execute::<Review>().await {
+            Ok(results) => results.facet_distribution.unwrap_or_default(),
+            // Nobody has reviewed anything yet
+            Err(e) if is_index_not_found(&e) => HashMap::new(),
+            Err(e) => return Err(format!("Failed to aggregate ratings: {e}")),
+        };
+    Ok(RatingSummary::from_distribution(
+        distribution
+            .get("score")
+            .into_iter()
+            .flatten()
+            .map(|(score, count)| (score.as_str(), *count)),
+    ))
+}
+
+// The partial update `write_back` sends; no visible reviews clears the score
+fn rating_update(id: i64, summary: &RatingSummary) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "user_rating": summary.average,
+        "user_rating_count": summary.count,
+    })
+}
+
+// Writes the aggregate onto the catalogue document with a partial update, so
+// the rest of the document (and its vectors) stay as they are. Nothing is
+// written when the aggregate cannot be read, or when the item has been
+// deleted since it was reviewed; returns whether the update was sent.
+async fn write_back(index: &str, id: i64) -> Result<bool, String> {
+    if existing_ids(index, &[id]).await?.is_empty() {
+        return Ok(false);
+    }
+    let update = rating_update(id, &summary(index, id).await?);
+    let client = get_client();
+    let task = get_index(index)
+        .add_or_update(&[update], Some("id"))
+        .await
+        .map_err(|e| format!("Failed to update rating: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for rating: {e}"))?;
+    Ok(true)
+}
+
+pub async fn submit(
+    user: &User,
+    index: &str,
+    id: i64,
+    score: u8,
+    body: String,
+) -> Result<Review, String> {
+    if !is_listable(index) {
+        return Err(format!("Unknown index: {index}"));
+    }
+    let hit = get_hit(index, id)
+        .await
+        .ok_or_else(|| format!("Item not found: {index}:{id}"))?;
+
+    let document_id = Review::document_id(user.id, index, id);
+    let now = now();
+    // Editing keeps the original date, flags and moderation state
+    let mut review = get(&document_id).await.unwrap_or(Review {
+        id: document_id,
+        user_id: user.id,
+        username: user.username.clone(),
+        index: index.to_string(),
+        item_id: id,
+        item_title: String::new(),
+        score,
+        body: String::new(),
+        created_at: now,
+        updated_at: now,
+        status: ReviewStatus::Visible,
+        flags: vec![],
+        flag_count: 0,
+    });
+    review.item_title = hit.title;
+    review.score = score;
+    review.body = body;
+    review.updated_at = now;
+    review.normalize();
+    review.validate()?;
+
+    store(&review).await?;
+    write_back(index, id).await?;
+    Ok(review)
+}
+
+pub async fn delete(user: &User, index: &str, id: i64) -> Result<(), String> {
+    if !is_listable(index) {
+        return Err(format!("Unknown index: {index}"));
+    }
+    get_hit(index, id)
+        .await
+        .ok_or_else(|| format!("Item not found: {index}:{id}"))?;
+    let client = get_client();
+    let task = get_index(REVIEWS_INDEX)
+        .delete_document(&Review::document_id(user.id, index, id))
+        .await
+        .map_err(|e| format!("Failed to delete review: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for review: {e}"))?;
+
+    write_back(index, id).await?;
+    Ok(())
+}
+
+// Visible reviews of one item, newest first, optionally narrowed by a query
+pub async fn page(
+    index: &str,
+    id: i64,
+    query: &str,
+    page: usize,
+    viewer: Option<&User>,
+) -> Result<ReviewPage, String> {
+    let page = page.max(1);
+    let own = match viewer {
+        Some(user) => get(&Review::document_id(user.id, index, id)).await,
+        None => None,
+    };
+    let summary = summary(index, id).await?;
+    let per_page = config().search.reviews_per_page;
+
+    let expression = item_filter(index, id).to_expression();
+    let ms_index = get_index(REVIEWS_INDEX);
+    let mut search = ms_index.search();
+    search.with_query(query);
+    search.with_limit(per_page);
+    search.with_offset((page - 1) * per_page);
+    if let Some(ref f) = expression {
+        search.with_filter(f);
+    }
+    if query.is_empty() {
+        search.with_sort(&["updated_at:desc"]);
+    }
+
+    // No review has been written yet until the index exists
+    let (reviews, total) = match search.execute::<Review>().await {
+        Ok(results) => (
+            results.hits.into_iter().map(|h| h.result).collect(),
+            results.estimated_total_hits.unwrap_or(0),
+        ),
+        Err(_) => (vec![], 0),
+    };
+
+    Ok(ReviewPage {
+        reviews,
+        page,
+        total_pages: total.div_ceil(per_page),
+        summary,
+        own,
+    })
+}
+
+// Returns whether the flag pushed the review over the auto-hide threshold
+pub async fn flag(user: &User, review_id: &str, reason: FlagReason) -> Result<bool, String> {
+    let mut review = get(review_id)
+        .await
+        .ok_or_else(|| format!("Review not found: {review_id}"))?;
+    if review.user_id == user.id {
+        return Err("自分のレビューは報告できません".to_string());
+    }
+    let hidden = review.add_flag(user.id, reason, now());
+    store(&review).await?;
+    if hidden {
+        write_back(&review.index, review.item_id).await?;
+    }
+    Ok(hidden)
+}
+
+pub async fn moderate(review_id: &str, status: ReviewStatus) -> Result<Review, String> {
+    let mut review = get(review_id)
+        .await
+        .ok_or_else(|| format!("Review not found: {review_id}"))?;
+    review.moderate(status);
+    store(&review).await?;
+    write_back(&review.index, review.item_id).await?;
+    Ok(review)
+}
+
+// Review search across all items. `flagged_only` turns it into the
+// moderation queue: flagged or hidden reviews, most flagged first.
+pub async fn search(text: &str, flagged_only: bool) -> Result<Vec<Review>, String> {
+    let filter = if flagged_only {
+        Filter::Or(vec![
+            Filter::Range {
+                field: "flag_count",
+                min: Some(Value::from(1)),
+                max: None,
+            },
+            Filter::eq("status", ReviewStatus::Hidden.as_str()),
+        ])
+    } else {
+        Filter::eq("status", ReviewStatus::Visible.as_str())
+    };
+    let expression = filter.to_expression();
+
+    let index = get_index(REVIEWS_INDEX);
+    let mut search = index.search();
+    search.with_query(text);
+    search.with_limit(SCAN_LIMIT);
+    if let Some(ref f) = expression {
+        search.with_filter(f);
+    }
+    if flagged_only {
+        search.with_sort(&["flag_count:desc", "updated_at:desc"]);
+    } else if text.is_empty() {
+        search.with_sort(&["updated_at:desc"]);
+    }
+
+    match search.execute::<Review>().await {
+        Ok(results) => Ok(results.hits.into_iter().map(|h| h.result).collect()),
+        Err(_) => Ok(vec![]),
+    }
+}
+
+// Re-applies every aggregate, e.g. after seeding replaced the catalogue
+// documents and with them their `user_rating`
+pub async fn refresh_all() -> Result<usize, String> {
+    let reviews = all_documents::<Review>(REVIEWS_INDEX).await?;
+
+    let mut items: Vec<(String, i64)> = reviews
+        .into_iter()
+        .map(|r| (r.index, r.item_id))
+        .collect();
+    items.sort();
+    items.dedup();
+    let mut written = 0;
+    for (index, id) in &items {
+        if write_back(index, *id).await? {
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_back_sets_or_clears_the_user_rating() {
+        let summary = RatingSummary::from_distribution([("8", 2), ("5", 1)]);
+        assert_eq!(
+            rating_update(7, &summary),
+            serde_json::json!({ "id": 7, "user_rating": 7.0, "user_rating_count": 3 })
+        );
+        assert_eq!(
+            rating_update(7, &RatingSummary::from_distribution([])),
+            serde_json::json!({ "id": 7, "user_rating": null, "user_rating_count": 0 })
+        );
+    }
+}
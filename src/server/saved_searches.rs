@@ -0,0 +1,477 @@
+// Saved searches and the notifications they produce. A scheduled job (see
+// `main.rs`) re-runs every saved search, compares the matching document ids
+// with those seen on earlier runs, and stores a notification for the new ones.
+// Notifications are shown in the app, served as a per-search Atom feed and,
+// when the user set one, POSTed to a webhook.
+
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::time::Duration;
+
+use reqwest::{redirect, Client, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::model::saved_search::{
+    atom_feed, Notification, NotificationFeed, NotifiedItem, SavedSearch, NOTIFIED_ITEMS_MAX,
+};
+use crate::model::search::SearchRequest;
+use crate::model::user::User;
+use crate::server::config::config;
+use crate::server::filter::Filter;
+use crate::server::meilisearch::{
+    all_documents, get_client, get_hit, get_index, is_document_not_found, is_index_not_found,
+    prepare_search,
+};
+use crate::server::schema::schema;
+use crate::server::users::{new_token, now, tokens_match};
+
+pub const SAVED_SEARCHES_INDEX: &str = "saved_searches";
+pub const NOTIFICATIONS_INDEX: &str = "notifications";
+// Matches compared per run; a search matching more than this is too broad to
+// be useful as an alert anyway
+const MATCH_LIMIT: usize = 1000;
+// Upper bound on one user's saved searches and unread notifications
+const SCAN_LIMIT: usize = 1000;
+const FEED_ENTRIES: usize = 50;
+
+// `saved_searches.interval_secs`, `None` when the job is off
+pub fn interval() -> Option<Duration> {
+    let secs = config().saved_searches.interval_secs;
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+async fn configure_indexes() -> Result<(), String> {
+    let searches = get_index(SAVED_SEARCHES_INDEX);
+    searches
+        .set_filterable_attributes(["user_id"])
+        .await
+        .map_err(|e| format!("Failed to set filterable attributes: {e}"))?;
+    searches
+        .set_sortable_attributes(["created_at"])
+        .await
+        .map_err(|e| format!("Failed to set sortable attributes: {e}"))?;
+
+    let notifications = get_index(NOTIFICATIONS_INDEX);
+    notifications
+        .set_filterable_attributes(["user_id", "saved_search_id", "read"])
+        .await
+        .map_err(|e| format!("Failed to set filterable attributes: {e}"))?;
+    notifications
+        .set_sortable_attributes(["created_at"])
+        .await
+        .map_err(|e| format!("Failed to set sortable attributes: {e}"))?;
+    Ok(())
+}
+
+async fn store<T: Serialize + Send + Sync>(index_name: &str, documents: &[T]) -> Result<(), String> {
+    let client = get_client();
+    let task = get_index(index_name)
+        .add_documents(documents, Some("id"))
+        .await
+        .map_err(|e| format!("Failed to store {index_name}: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for {index_name}: {e}"))?;
+    Ok(())
+}
+
+async fn get(id: &str) -> Result<Option<SavedSearch>, String> {
+    match get_index(SAVED_SEARCHES_INDEX)
+        .get_document::<SavedSearch>(id)
+        .await
+    {
+        Ok(search) => Ok(Some(search)),
+        Err(e) if is_document_not_found(&e) => Ok(None),
+        Err(e) => Err(format!("Failed to load saved search: {e}")),
+    }
+}
+
+async fn find<T: for<'de> Deserialize<'de> + Send + Sync + 'static>(
+    index_name: &str,
+    filter: Filter,
+    limit: usize,
+) -> Result<Vec<T>, String> {
+    let index = get_index(index_name);
+    let expression = filter.to_expression();
+    let mut search = index.search();
+    search.with_limit(limit);
+    if let Some(ref f) = expression {
+        search.with_filter(f);
+    }
+    search.with_sort(&["created_at:desc"]);
+    // Nothing has been saved yet until the index exists
+    match search.execute::<T>().await {
+        Ok(results) => Ok(results.hits.into_iter().map(|h| h.result).collect()),
+        Err(e) if is_index_not_found(&e) => Ok(vec![]),
+        Err(e) => Err(format!("Failed to search {index_name}: {e}")),
+    }
+}
+
+#[derive(Deserialize)]
+struct IdOnly {
+    id: i64,
+}
+
+// Ids of the documents the request matches. Keyword matching only: the
+// semantic part of a hybrid search ranks rather than filters, so it would only
+// add noise to "new since last time".
+async fn matching_ids(request: &SearchRequest) -> Result<Vec<i64>, String> {
+    let schema = schema(&request.index)?;
+    let prepared = prepare_search(request, schema).await?;
+    let index = get_index(&request.index);
+    let mut search = index.search();
+    search.with_query(&prepared.query);
+    search.with_limit(MATCH_LIMIT);
+    search.with_attributes_to_retrieve(meilisearch_sdk::search::Selectors::Some(&["id"]));
+    if let Some(ref f) = prepared.filter {
+        search.with_filter(f);
+    }
+    let results = search
+        .execute::<IdOnly>()
+        .await
+        .map_err(|e| format!("Search failed: {e}"))?;
+    Ok(results.hits.into_iter().map(|h| h.result.id).collect())
+}
+
+pub async fn create(
+    user: &User,
+    name: String,
+    request: SearchRequest,
+    webhook_url: Option<String>,
+) -> Result<SavedSearch, String> {
+    let token = new_token();
+    let mut search = SavedSearch {
+        id: format!("{}-{}", user.id, &token[..16]),
+        user_id: user.id,
+        name,
+        request,
+        created_at: now(),
+        last_run_at: None,
+        seen_ids: vec![],
+        webhook_url,
+        feed_token: new_token(),
+    };
+    search.normalize();
+    search.validate()?;
+    if let Some(ref url) = search.webhook_url {
+        webhook_target(url).await?;
+    }
+    configure_indexes().await?;
+    // The first run records what already matches, so the next one reports
+    // only what is new
+    run(&mut search).await?;
+    Ok(search)
+}
+
+pub async fn list(user: &User) -> Result<Vec<SavedSearch>, String> {
+    find(SAVED_SEARCHES_INDEX, Filter::eq("user_id", user.id), SCAN_LIMIT).await
+}
+
+pub async fn owned(user: &User, id: &str) -> Result<SavedSearch, String> {
+    get(id)
+        .await?
+        .filter(|s| s.user_id == user.id)
+        .ok_or_else(|| format!("Saved search not found: {id}"))
+}
+
+pub async fn delete(user: &User, id: &str) -> Result<(), String> {
+    owned(user, id).await?;
+    let client = get_client();
+    let task = get_index(SAVED_SEARCHES_INDEX)
+        .delete_document(id)
+        .await
+        .map_err(|e| format!("Failed to delete saved search: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for saved search: {e}"))?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct WebhookItem<'a> {
+    id: i64,
+    title: &'a str,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    saved_search_id: &'a str,
+    name: &'a str,
+    message: String,
+    new_count: usize,
+    items: Vec<WebhookItem<'a>>,
+}
+
+// Addresses a webhook may reach. The server makes the request, so anything
+// it can reach but users cannot (Meilisearch, cloud metadata, the LAN) is off
+// limits.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            !(v4.is_unspecified()
+                || v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                || v4.is_multicast()
+                || a == 0
+                // Shared address space (carrier-grade NAT)
+                || (a == 100 && (64..128).contains(&b))
+                // IETF protocol assignments
+                || (a == 192 && b == 0 && c == 0)
+                // Benchmarking
+                || (a == 198 && (18..20).contains(&b))
+                // Reserved
+                || a >= 240)
+        }
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            // IPv4-mapped and NAT64 addresses reach the embedded IPv4 address
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [.., hi, lo] = segments;
+                return is_public(IpAddr::V4(((u32::from(hi) << 16) | u32::from(lo)).into()));
+            }
+            !(v6.is_unspecified()
+                || v6.is_loopback()
+                || v6.is_multicast()
+                || v6.is_unique_local()
+                || v6.is_unicast_link_local()
+                // Documentation
+                || (segments[0] == 0x2001 && segments[1] == 0xdb8))
+        }
+    }
+}
+
+// Names that only mean something inside the server's own network
+fn is_internal_name(host: &str) -> bool {
+    let name = host.trim_end_matches('.').to_ascii_lowercase();
+    !name.contains('.')
+        || name == "localhost"
+        || [".localhost", ".local", ".internal", ".lan", ".home.arpa"]
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+}
+
+// Checks a webhook URL and returns the addresses its host resolves to. Every
+// address must be public, so a name with one internal record is refused too.
+async fn webhook_target(url: &str) -> Result<(Url, Vec<SocketAddr>), String> {
+    let parsed = Url::parse(url).map_err(|_| format!("Invalid webhook URL: {url}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Webhook URL must be http(s): {url}"));
+    }
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| format!("Webhook URL has no host: {url}"))?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let internal = || format!("Webhook URL must point to a public host: {url}");
+    if parsed.domain().is_some_and(is_internal_name) {
+        return Err(internal());
+    }
+
+    let addrs: Vec<SocketAddr> = actix_web::rt::task::spawn_blocking(move || {
+        (host.as_str(), port)
+            .to_socket_addrs()
+            .map(|addrs| addrs.collect())
+    })
+    .await
+    .map_err(|e| format!("Failed to resolve the webhook host: {e}"))?
+    .map_err(|e| format!("Failed to resolve the webhook host: {e}"))?;
+    if addrs.is_empty() || addrs.iter().any(|a| !is_public(a.ip())) {
+        return Err(internal());
+    }
+    Ok((parsed, addrs))
+}
+
+async fn deliver(url: &str, notification: &Notification) -> Result<(), String> {
+    let payload = WebhookPayload {
+        saved_search_id: &notification.saved_search_id,
+        name: &notification.saved_search_name,
+        message: notification.message(),
+        new_count: notification.new_count,
+        items: notification
+            .items
+            .iter()
+            .map(|i| WebhookItem {
+                id: i.id,
+                title: &i.title,
+                url: notification.item_url(i.id),
+            })
+            .collect(),
+    };
+    // Resolved once and pinned, so the name cannot re-resolve to an internal
+    // address between the check and the request. Redirects are not followed
+    // for the same reason.
+    let (url, addrs) = webhook_target(url).await?;
+    let mut client = Client::builder().redirect(redirect::Policy::none());
+    if let Some(host) = url.domain() {
+        client = client.resolve_to_addrs(host, &addrs);
+    }
+    let response = client
+        .build()
+        .map_err(|e| format!("Webhook request failed: {e}"))?
+        .post(url)
+        .timeout(Duration::from_secs(config().saved_searches.webhook_timeout_secs))
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("Webhook request failed: {e}"))?;
+    if !response.status().is_success() {
+        return Err(format!("Webhook returned status: {}", response.status()));
+    }
+    Ok(())
+}
+
+// Re-runs one saved search and stores a notification when something new
+// matched. A failed webhook is reported after the notification is stored.
+pub async fn run(search: &mut SavedSearch) -> Result<Option<Notification>, String> {
+    let ids = matching_ids(&search.request).await?;
+    let now = now();
+    let new_ids = search.record_run(&ids, now);
+    store(SAVED_SEARCHES_INDEX, std::slice::from_ref(search)).await?;
+    if new_ids.is_empty() {
+        return Ok(None);
+    }
+
+    let mut items = Vec::new();
+    for id in new_ids.iter().take(NOTIFIED_ITEMS_MAX) {
+        if let Some(hit) = get_hit(&search.request.index, *id).await {
+            items.push(NotifiedItem {
+                id: *id,
+                title: hit.title,
+            });
+        }
+    }
+    let notification = Notification {
+        // Two runs can fall within the same second
+        id: format!("{}-{now}-{}", search.id, &new_token()[..8]),
+        user_id: search.user_id,
+        saved_search_id: search.id.clone(),
+        saved_search_name: search.name.clone(),
+        index: search.request.index.clone(),
+        new_count: new_ids.len(),
+        items,
+        created_at: now,
+        read: false,
+    };
+    store(NOTIFICATIONS_INDEX, std::slice::from_ref(&notification)).await?;
+
+    if let Some(ref url) = search.webhook_url {
+        deliver(url, &notification).await?;
+    }
+    Ok(Some(notification))
+}
+
+// One pass of the scheduled job. Returns the number of notifications and the
+// errors of searches that could not be run.
+pub async fn run_all() -> (usize, Vec<String>) {
+    let searches = match all_documents::<SavedSearch>(SAVED_SEARCHES_INDEX).await {
+        Ok(searches) => searches,
+        Err(e) => return (0, vec![e]),
+    };
+    let mut notified = 0;
+    let mut errors = Vec::new();
+    for mut search in searches {
+        match run(&mut search).await {
+            Ok(Some(_)) => notified += 1,
+            Ok(None) => {}
+            Err(e) => errors.push(format!("{}: {e}", search.id)),
+        }
+    }
+    (notified, errors)
+}
+
+pub async fn notifications(user: &User) -> Result<NotificationFeed, String> {
+    let notifications: Vec<Notification> =
+        find(NOTIFICATIONS_INDEX, Filter::eq("user_id", user.id), FEED_ENTRIES).await?;
+    let unread = notifications.iter().filter(|n| !n.read).count();
+    Ok(NotificationFeed {
+        notifications,
+        unread,
+    })
+}
+
+pub async fn mark_read(user: &User) -> Result<(), String> {
+    let unread: Vec<Notification> = find(
+        NOTIFICATIONS_INDEX,
+        Filter::And(vec![
+            Filter::eq("user_id", user.id),
+            // Meilisearch filters booleans by their text form
+            Filter::eq("read", "false"),
+        ]),
+        SCAN_LIMIT,
+    )
+    .await?;
+    if unread.is_empty() {
+        return Ok(());
+    }
+    let read: Vec<Notification> = unread
+        .into_iter()
+        .map(|n| Notification { read: true, ..n })
+        .collect();
+    store(NOTIFICATIONS_INDEX, &read).await
+}
+
+// The Atom feed of one saved search, or `None` unless the token matches
+pub async fn feed(id: &str, token: &str, base_url: &str) -> Result<Option<String>, String> {
+    let Some(search) = get(id)
+        .await?
+        .filter(|s| tokens_match(token, &s.feed_token))
+    else {
+        return Ok(None);
+    };
+    let notifications: Vec<Notification> = find(
+        NOTIFICATIONS_INDEX,
+        Filter::eq("saved_search_id", id),
+        FEED_ENTRIES,
+    )
+    .await?;
+    Ok(Some(atom_feed(&search, &notifications, base_url)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_public_addresses_are_reachable() {
+        for ip in ["93.184.216.34", "2606:4700::1111", "::ffff:8.8.8.8"] {
+            assert!(is_public(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn internal_host_names_are_refused() {
+        for host in ["localhost", "meilisearch", "api.localhost", "nas.local", "db.internal."] {
+            assert!(is_internal_name(host), "{host}");
+        }
+        assert!(!is_internal_name("hooks.example.com"));
+    }
+}
@@ -0,0 +1,189 @@
+// Attribute allow-lists per index. Anything a client sends as a sort, facet
+// or typo tolerance attribute is checked against these before it reaches
+// Meilisearch.
+
+pub struct IndexSchema {
+    pub creator: Option<&'static str>,
+    // Set for documents spanning several years; year filters then match any
+    // overlap between `year..=<period_end>` and the requested range
+    pub period_end: Option<&'static str>,
+    // In ranking order, as Meilisearch weighs earlier attributes higher
+    pub searchable: &'static [&'static str],
+    pub filterable: &'static [&'static str],
+    pub sortable: &'static [&'static str],
+    pub facets: &'static [&'static str],
+}
+
+const MOVIES: IndexSchema = IndexSchema {
+    creator: Some("director"),
+    period_end: None,
+    searchable: &[
+        "title",
+        "title_en",
+        "title_reading",
+        "description",
+        "director",
+        "director_reading",
+        "cast",
+        "genres",
+    ],
+    filterable: &[
+        "genres",
+        "year",
+        "rating",
+        "language",
+        "director",
+        "director_id",
+        "cast",
+        "runtime_minutes",
+        "countries",
+        "certification",
+        "original_language",
+        "spoken_languages",
+        "user_rating",
+        "user_rating_count",
+        "tags",
+    ],
+    sortable: &[
+        "year",
+        "rating",
+        "title",
+        "runtime_minutes",
+        "release_date",
+        "user_rating",
+        "user_rating_count",
+    ],
+    facets: &["genres", "year", "language", "countries", "certification", "tags"],
+};
+
+const BOOKS: IndexSchema = IndexSchema {
+    creator: Some("author"),
+    period_end: None,
+    searchable: &[
+        "title",
+        "title_en",
+        "title_reading",
+        "description",
+        "author",
+        "author_reading",
+        "original_title",
+        "translators",
+        "publisher",
+        "genres",
+    ],
+    filterable: &[
+        "genres",
+        "year",
+        "rating",
+        "language",
+        "author",
+        "author_id",
+        "isbn",
+        "publisher",
+        "format",
+        "translators",
+        "original_language",
+        "series_number",
+        "user_rating",
+        "user_rating_count",
+        "tags",
+    ],
+    sortable: &[
+        "year",
+        "rating",
+        "title",
+        "publication_date",
+        "series_number",
+        "user_rating",
+        "user_rating_count",
+    ],
+    facets: &["genres", "year", "language", "publisher", "format", "tags"],
+};
+
+const SERIES: IndexSchema = IndexSchema {
+    creator: Some("studio"),
+    period_end: Some("end_year"),
+    searchable: &[
+        "title",
+        "title_en",
+        "title_reading",
+        "description",
+        "studio",
+        "network",
+        "genres",
+    ],
+    filterable: &[
+        "genres",
+        "year",
+        "end_year",
+        "rating",
+        "language",
+        "studio",
+        "network",
+        "status",
+        "user_rating",
+        "user_rating_count",
+        "tags",
+    ],
+    sortable: &[
+        "year",
+        "rating",
+        "title",
+        "episode_count",
+        "user_rating",
+        "user_rating_count",
+    ],
+    facets: &["genres", "year", "language", "status", "tags"],
+};
+
+const WEB: IndexSchema = IndexSchema {
+    creator: None,
+    period_end: None,
+    searchable: &["title", "description", "url", "source_engine"],
+    filterable: &["genres", "year", "rating", "language"],
+    sortable: &["year", "rating", "title"],
+    facets: &["genres", "year", "language"],
+};
+
+// Every index `schema` describes, which visitors search
+pub const CATALOGUE_INDEXES: [&str; 4] = ["movies", "books", "series", "web"];
+
+pub fn schema(index: &str) -> Result<&'static IndexSchema, String> {
+    match index {
+        "movies" => Ok(&MOVIES),
+        "books" => Ok(&BOOKS),
+        "series" => Ok(&SERIES),
+        "web" => Ok(&WEB),
+        _ => Err(format!("Unknown index: {index}")),
+    }
+}
+
+impl IndexSchema {
+    // Accepts `attribute` or `attribute:asc|desc` and returns the canonical form
+    pub fn check_sort(&self, sort: &str) -> Result<String, String> {
+        let (attribute, direction) = sort.split_once(':').unwrap_or((sort, "asc"));
+        if !self.sortable.contains(&attribute) {
+            return Err(format!("Attribute is not sortable: {attribute}"));
+        }
+        if !matches!(direction, "asc" | "desc") {
+            return Err(format!("Invalid sort direction: {direction}"));
+        }
+        Ok(format!("{attribute}:{direction}"))
+    }
+
+    pub fn check_facet(&self, facet: &str) -> Result<&'static str, String> {
+        self.facets
+            .iter()
+            .find(|f| **f == facet)
+            .copied()
+            .ok_or_else(|| format!("Attribute is not a facet: {facet}"))
+    }
+
+    pub fn check_searchable(&self, attribute: &str) -> Result<&'static str, String> {
+        self.searchable
+            .iter()
+            .find(|a| **a == attribute)
+            .copied()
+            .ok_or_else(|| format!("Unknown attribute: {attribute}"))
+    }
+}
@@ -0,0 +1,240 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use meilisearch_sdk::settings::{MinWordSizeForTypos, TypoToleranceSettings};
+use serde_json::Value;
+
+use crate::model::search_settings::{SearchSettings, SettingsPreview};
+use crate::server::filter::Filter;
+use crate::server::meilisearch::{
+    get_client, get_index, get_search_index, is_index_not_found, wait_for_task,
+};
+use crate::server::schema::schema;
+
+const SETTINGS_INDEX: &str = "search_settings";
+// Scratch index draft settings are tried out on
+const PREVIEW_INDEX: &str = "search_settings_preview";
+// Live hits per query variant copied into the preview index
+const PREVIEW_CANDIDATES: usize = 200;
+const PREVIEW_HITS: usize = 10;
+
+// One preview at a time, as they share the scratch index
+static PREVIEW_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn bundled_settings() -> Vec<SearchSettings> {
+    let data: &str = include_str!("../../seed/search_settings.json");
+    serde_json::from_str(data).unwrap_or_default()
+}
+
+fn default_settings(index: &str) -> SearchSettings {
+    bundled_settings()
+        .into_iter()
+        .find(|s| s.index == index)
+        .unwrap_or_else(|| SearchSettings::new(index))
+}
+
+async fn configure_settings_index() -> Result<(), String> {
+    let index = get_index(SETTINGS_INDEX);
+    // Waited for, as `history` filters and sorts on these
+    wait_for_task(
+        index.set_filterable_attributes(["index"]).await,
+        "set filterable attributes",
+    )
+    .await?;
+    wait_for_task(
+        index.set_sortable_attributes(["version"]).await,
+        "set sortable attributes",
+    )
+    .await
+}
+
+pub async fn history(index: &str) -> Result<Vec<SearchSettings>, String> {
+    let ms_index = get_index(SETTINGS_INDEX);
+    let filter = Filter::eq("index", index)
+        .to_expression()
+        .unwrap_or_default();
+
+    let mut search = ms_index.search();
+    search.with_query("");
+    search.with_filter(&filter);
+    search.with_sort(&["version:desc"]);
+    search.with_limit(50);
+
+    match search.execute::<SearchSettings>().await {
+        Ok(results) => Ok(results.hits.into_iter().map(|h| h.result).collect()),
+        // Nothing has been saved yet
+        Err(e) if is_index_not_found(&e) => Ok(vec![]),
+        Err(e) => Err(format!("Failed to load search settings: {e}")),
+    }
+}
+
+// Falls back to the bundled defaults until an admin has saved a version
+pub async fn latest(index: &str) -> Result<SearchSettings, String> {
+    Ok(history(index)
+        .await?
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| default_settings(index)))
+}
+
+// Only catalogue indexes, never users, sessions or other app data, and only
+// attributes the index searches
+pub fn check(settings: &SearchSettings) -> Result<(), String> {
+    let schema = schema(&settings.index)?;
+    settings.validate()?;
+    for attribute in &settings.typo_tolerance.disabled_attributes {
+        schema.check_searchable(attribute)?;
+    }
+    Ok(())
+}
+
+pub async fn save(mut settings: SearchSettings) -> Result<SearchSettings, String> {
+    let client = get_client();
+    configure_settings_index().await?;
+    // An error here must not restart the numbering, which would overwrite v1
+    let current = history(&settings.index)
+        .await?
+        .first()
+        .map_or(0, |s| s.version);
+
+    settings.version = current + 1;
+    settings.id = format!("{}-v{}", settings.index, settings.version);
+    settings.updated_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    // Applied first, so the history only lists versions that took effect
+    apply(&settings).await?;
+
+    let task = get_index(SETTINGS_INDEX)
+        .add_documents(&[settings.clone()], Some("id"))
+        .await
+        .map_err(|e| format!("Failed to store search settings: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for search settings: {e}"))?;
+
+    Ok(settings)
+}
+
+fn typo_settings(settings: &SearchSettings) -> TypoToleranceSettings {
+    let typo = &settings.typo_tolerance;
+    TypoToleranceSettings {
+        enabled: Some(typo.enabled),
+        disable_on_attributes: Some(typo.disabled_attributes.clone()),
+        disable_on_words: Some(typo.disabled_words.clone()),
+        min_word_size_for_typos: Some(MinWordSizeForTypos {
+            one_typo: Some(typo.one_typo_min),
+            two_typos: Some(typo.two_typos_min),
+        }),
+        disable_on_numbers: None,
+    }
+}
+
+// Waited for, so a setting Meilisearch rejects is reported rather than lost
+pub async fn apply(settings: &SearchSettings) -> Result<(), String> {
+    let index = get_index(&settings.index);
+
+    wait_for_task(
+        index.set_synonyms(&settings.synonym_map()).await,
+        "set synonyms",
+    )
+    .await?;
+    wait_for_task(
+        index.set_stop_words(&settings.stop_words).await,
+        "set stop words",
+    )
+    .await?;
+    wait_for_task(
+        index.set_typo_tolerance(&typo_settings(settings)).await,
+        "set typo tolerance",
+    )
+    .await
+}
+
+// Runs `query` under draft settings without touching the live index: the live
+// documents the query or its draft synonyms match are copied into a scratch
+// index with the live settings plus the draft ones, and searched there. Hits
+// are therefore limited to what the live index can find for those variants.
+pub async fn preview(settings: &SearchSettings, query: &str) -> Result<SettingsPreview, String> {
+    let _guard = PREVIEW_LOCK.lock().await;
+    let live = get_index(&settings.index);
+
+    let mut documents: Vec<Value> = Vec::new();
+    for variant in settings.variants(query) {
+        let results = get_search_index(&settings.index)
+            .search()
+            .with_query(&variant)
+            .with_limit(PREVIEW_CANDIDATES)
+            .execute::<Value>()
+            .await
+            .map_err(|e| format!("Search failed: {e}"))?;
+        for hit in results.hits {
+            let mut document = hit.result;
+            if let Some(fields) = document.as_object_mut() {
+                fields.remove("_vectors");
+            }
+            if !documents.iter().any(|d| d.get("id") == document.get("id")) {
+                documents.push(document);
+            }
+        }
+    }
+
+    let mut draft = live
+        .get_settings()
+        .await
+        .map_err(|e| format!("Failed to read the live settings: {e}"))?
+        .with_synonyms(settings.synonym_map())
+        .with_stop_words(settings.stop_words.clone())
+        .with_typo_tolerance(typo_settings(settings));
+    // Copied documents carry no vectors, and embedding them again is slow
+    draft.embedders = None;
+
+    // Settings first, as they create the index on the first preview
+    let scratch = get_index(PREVIEW_INDEX);
+    wait_for_task(scratch.set_settings(&draft).await, "apply the draft settings").await?;
+    wait_for_task(scratch.delete_all_documents().await, "clear the preview index").await?;
+    if !documents.is_empty() {
+        wait_for_task(
+            scratch.add_documents(&documents, Some("id")).await,
+            "copy documents for the preview",
+        )
+        .await?;
+    }
+
+    let results = scratch
+        .search()
+        .with_query(query)
+        .with_limit(PREVIEW_HITS)
+        .execute::<Value>()
+        .await
+        .map_err(|e| format!("Search failed: {e}"))?;
+    let titles = results
+        .hits
+        .iter()
+        .filter_map(|h| h.result.get("title").and_then(|t| t.as_str()))
+        .map(|t| t.to_string())
+        .collect();
+
+    Ok(SettingsPreview {
+        analysis: settings.analyze(query),
+        titles,
+        total_hits: results.estimated_total_hits.unwrap_or(0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typo_exceptions_must_be_searchable_attributes() {
+        let mut settings = SearchSettings::new("books");
+        settings.typo_tolerance.disabled_attributes = vec!["author".into()];
+        assert!(check(&settings).is_ok());
+        settings.typo_tolerance.disabled_attributes = vec!["director".into()];
+        assert_eq!(check(&settings), Err("Unknown attribute: director".to_string()));
+        assert!(check(&SearchSettings::new("users")).is_err());
+    }
+}
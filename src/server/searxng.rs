@@ -1,11 +1,15 @@
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
 use reqwest::Client;
 use serde::Deserialize;
+use tracing::Instrument;
 
 use crate::model::web_result::WebResult;
+use crate::server::config::config;
+use crate::server::{metrics, telemetry};
 
 static HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
 
@@ -13,8 +17,8 @@ fn get_http_client() -> &'static Client {
     HTTP_CLIENT.get_or_init(Client::new)
 }
 
-fn get_searxng_url() -> String {
-    std::env::var("SEARXNG_URL").unwrap_or_else(|_| "http://searxng:8080".to_string())
+fn get_searxng_url() -> &'static str {
+    &config().searxng.url
 }
 
 #[derive(Debug, Deserialize)]
@@ -43,12 +47,32 @@ fn url_to_id(url: &str) -> i64 {
 }
 
 pub async fn search_web(query: &str) -> Result<Vec<WebResult>, String> {
+    let span = tracing::info_span!("searxng", query);
+    let started = Instant::now();
+    let result = fetch(query).instrument(span.clone()).await;
+    let elapsed = started.elapsed();
+    metrics::observe_searxng(elapsed, result.is_ok());
+
+    let elapsed_ms = elapsed.as_millis() as u64;
+    span.in_scope(|| match &result {
+        Ok(results) => tracing::info!(results = results.len(), elapsed_ms, "searxng search"),
+        Err(e) => tracing::warn!(error = %e, elapsed_ms, "searxng search failed"),
+    });
+    result
+}
+
+async fn fetch(query: &str) -> Result<Vec<WebResult>, String> {
     let base_url = get_searxng_url();
     let client = get_http_client();
 
-    let resp = client
+    let mut request = client
         .get(format!("{base_url}/search"))
         .query(&[("q", query), ("format", "json")])
+        .timeout(config().searxng_timeout());
+    if let Some(id) = telemetry::current_request_id() {
+        request = request.header(telemetry::REQUEST_ID_HEADER, id);
+    }
+    let resp = request
         .send()
         .await
         .map_err(|e| format!("SearXNG request failed: {e}"))?;
@@ -83,3 +107,17 @@ pub async fn search_web(query: &str) -> Result<Vec<WebResult>, String> {
 
     Ok(results)
 }
+
+// SearXNG's own liveness endpoint, used by `/readyz`
+pub async fn ping(timeout: Duration) -> Result<(), String> {
+    let resp = get_http_client()
+        .get(format!("{}/healthz", get_searxng_url()))
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|e| format!("SearXNG request failed: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("SearXNG returned status: {}", resp.status()));
+    }
+    Ok(())
+}
@@ -1,49 +1,97 @@
 use crate::model::book::Book;
 use crate::model::movie::Movie;
-use crate::server::meilisearch::{configure_index, get_client};
+use crate::model::series::Series;
+use crate::server::embedding;
+use crate::server::meilisearch::{configure_index, get_client, get_index};
+use crate::server::reading::{annotate_book, annotate_movie, annotate_series};
 
 pub async fn seed_movies() -> Result<usize, String> {
     let data: &str = include_str!("../../seed/movies.json");
-    let movies: Vec<Movie> =
+    let mut movies: Vec<Movie> =
         serde_json::from_str(data).map_err(|e| format!("Failed to parse movies.json: {e}"))?;
+    for movie in movies.iter_mut() {
+        movie.normalize();
+        movie
+            .validate()
+            .map_err(|e| format!("Invalid movie {} in movies.json: {e}", movie.id))?;
+        annotate_movie(movie);
+    }
 
     let count = movies.len();
     let client = get_client();
-    let index = client.index("movies");
+    let index = get_index("movies");
+    embedding::configure("movies").await?;
 
     let task = index
-        .add_documents(&movies, Some("id"))
+        .add_documents(&embedding::with_vectors(&movies)?, Some("id"))
         .await
         .map_err(|e| format!("Failed to add movies: {e}"))?;
 
-    task.wait_for_completion(client, None, None)
+    task.wait_for_completion(&client, None, None)
         .await
         .map_err(|e| format!("Failed waiting for movie indexing: {e}"))?;
 
-    configure_index("movies", true).await?;
+    configure_index("movies").await?;
 
     Ok(count)
 }
 
 pub async fn seed_books() -> Result<usize, String> {
     let data: &str = include_str!("../../seed/books.json");
-    let books: Vec<Book> =
+    let mut books: Vec<Book> =
         serde_json::from_str(data).map_err(|e| format!("Failed to parse books.json: {e}"))?;
+    for book in books.iter_mut() {
+        book.normalize();
+        book.validate()
+            .map_err(|e| format!("Invalid book {} in books.json: {e}", book.id))?;
+        annotate_book(book);
+    }
 
     let count = books.len();
     let client = get_client();
-    let index = client.index("books");
+    let index = get_index("books");
+    embedding::configure("books").await?;
 
     let task = index
-        .add_documents(&books, Some("id"))
+        .add_documents(&embedding::with_vectors(&books)?, Some("id"))
         .await
         .map_err(|e| format!("Failed to add books: {e}"))?;
 
-    task.wait_for_completion(client, None, None)
+    task.wait_for_completion(&client, None, None)
         .await
         .map_err(|e| format!("Failed waiting for book indexing: {e}"))?;
 
-    configure_index("books", false).await?;
+    configure_index("books").await?;
+
+    Ok(count)
+}
+
+pub async fn seed_series() -> Result<usize, String> {
+    let data: &str = include_str!("../../seed/series.json");
+    let mut series: Vec<Series> =
+        serde_json::from_str(data).map_err(|e| format!("Failed to parse series.json: {e}"))?;
+    for s in series.iter_mut() {
+        s.normalize();
+        s.validate()
+            .map_err(|e| format!("Invalid series {} in series.json: {e}", s.id))?;
+        annotate_series(s);
+    }
+
+    let count = series.len();
+    let client = get_client();
+    let index = get_index("series");
+    embedding::configure("series").await?;
+
+    let task = index
+        .add_documents(&embedding::with_vectors(&series)?, Some("id"))
+        .await
+        .map_err(|e| format!("Failed to add series: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for series indexing: {e}"))?;
+
+    configure_index("series").await?;
 
     Ok(count)
 }
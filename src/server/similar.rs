@@ -0,0 +1,280 @@
+// Related items for the detail pages. Candidates are gathered from both the
+// movies and books indexes, then scored on a handful of explainable signals so
+// the UI can say why each item was picked.
+
+use crate::model::book::Book;
+use crate::model::movie::Movie;
+use crate::model::search::{SearchHit, SimilarItem};
+use crate::server::filter::{Filter, Value};
+use crate::server::meilisearch::get_search_index;
+use crate::server::normalize::normalize_value;
+
+const CANDIDATES_PER_QUERY: usize = 20;
+const MAX_SIMILAR: usize = 8;
+// Items scoring below this share too little with the source to be worth showing
+const MIN_SCORE: f64 = 1.0;
+
+const ADAPTATION_WEIGHT: f64 = 4.0;
+const CREATOR_WEIGHT: f64 = 3.0;
+const GENRE_WEIGHT: f64 = 1.0;
+const MAX_GENRE_SCORE: f64 = 3.0;
+const YEAR_WINDOW: i32 = 5;
+const RATING_WINDOW: f64 = 0.5;
+const RATING_WEIGHT: f64 = 0.5;
+
+fn same_text(a: &str, b: &str) -> bool {
+    let a = normalize_value(a).to_lowercase();
+    !a.is_empty() && a == normalize_value(b).to_lowercase()
+}
+
+// A movie and a book sharing a title are treated as an adaptation pair
+fn is_adaptation(source: &SearchHit, candidate: &SearchHit) -> bool {
+    if source.index == candidate.index {
+        return false;
+    }
+    let titles = |h: &SearchHit| {
+        std::iter::once(h.title.clone())
+            .chain(h.title_en.clone())
+            .collect::<Vec<_>>()
+    };
+    let candidate_titles = titles(candidate);
+    titles(source)
+        .iter()
+        .any(|t| candidate_titles.iter().any(|c| same_text(t, c)))
+}
+
+pub fn score(source: &SearchHit, candidate: &SearchHit) -> (f64, Vec<String>) {
+    let mut score = 0.0;
+    let mut reasons = Vec::new();
+
+    if is_adaptation(source, candidate) {
+        score += ADAPTATION_WEIGHT;
+        reasons.push(
+            if candidate.index == "movies" {
+                "映画化作品"
+            } else {
+                "原作"
+            }
+            .to_string(),
+        );
+    }
+
+    let same_creator = match (source.creator_id, candidate.creator_id) {
+        (Some(a), Some(b)) => a == b,
+        _ => same_text(&source.creator, &candidate.creator),
+    };
+    if same_creator {
+        score += CREATOR_WEIGHT;
+        let reason = match (source.index.as_str(), candidate.index.as_str()) {
+            ("movies", "movies") => "同じ監督",
+            ("books", "books") => "同じ著者",
+            _ => "同じ作者",
+        };
+        reasons.push(reason.to_string());
+    }
+
+    let shared: Vec<&str> = source
+        .genres
+        .iter()
+        .filter(|g| candidate.genres.contains(g))
+        .map(String::as_str)
+        .collect();
+    if !shared.is_empty() {
+        score += (shared.len() as f64 * GENRE_WEIGHT).min(MAX_GENRE_SCORE);
+        reasons.push(format!("共通ジャンル: {}", shared.join("・")));
+    }
+
+    let year_gap = (source.year - candidate.year).abs();
+    if year_gap <= YEAR_WINDOW {
+        score += f64::from(YEAR_WINDOW - year_gap) / f64::from(YEAR_WINDOW);
+        if year_gap <= 2 {
+            reasons.push("近い年代".to_string());
+        }
+    }
+
+    if (source.rating - candidate.rating).abs() <= RATING_WINDOW {
+        score += RATING_WEIGHT;
+        reasons.push("評価が近い".to_string());
+    }
+
+    (score, reasons)
+}
+
+pub fn rank(source: &SearchHit, candidates: Vec<SearchHit>) -> Vec<SimilarItem> {
+    let mut items: Vec<SimilarItem> = Vec::new();
+    for hit in candidates {
+        let is_source = hit.index == source.index && hit.id == source.id;
+        let seen = items
+            .iter()
+            .any(|i| i.hit.index == hit.index && i.hit.id == hit.id);
+        if is_source || seen {
+            continue;
+        }
+        let (score, reasons) = score(source, &hit);
+        if score >= MIN_SCORE {
+            items.push(SimilarItem {
+                hit,
+                score,
+                reasons,
+            });
+        }
+    }
+    items.sort_by(|a, b| b.score.total_cmp(&a.score));
+    items.truncate(MAX_SIMILAR);
+    items
+}
+
+async fn fetch(index_name: &str, query: &str, filter: Option<Filter>) -> Vec<SearchHit> {
+    let index = get_search_index(index_name);
+    let expression = filter.and_then(|f| f.to_expression());
+    let mut search = index.search();
+    search.with_query(query);
+    search.with_limit(CANDIDATES_PER_QUERY);
+    if let Some(ref f) = expression {
+        search.with_filter(f);
+    }
+
+    // A failing sub-query only narrows the candidate pool
+    let hits = if index_name == "movies" {
+        search.execute::<Movie>().await.map(|r| {
+            r.hits
+                .into_iter()
+                .map(|h| SearchHit::from(h.result))
+                .collect()
+        })
+    } else {
+        search.execute::<Book>().await.map(|r| {
+            r.hits
+                .into_iter()
+                .map(|h| SearchHit::from(h.result))
+                .collect()
+        })
+    };
+    hits.unwrap_or_default()
+}
+
+pub async fn similar(source: &SearchHit) -> Vec<SimilarItem> {
+    let genres: Vec<Value> = source.genres.iter().map(|g| g.as_str().into()).collect();
+    let mut candidates = Vec::new();
+
+    for (index_name, creator_field) in [("movies", "director"), ("books", "author")] {
+        if !source.creator.is_empty() {
+            let filter = Filter::eq(creator_field, source.creator.as_str());
+            candidates.extend(fetch(index_name, "", Some(filter)).await);
+        }
+        // An empty `In` places no constraint, which would fetch arbitrary documents
+        if !genres.is_empty() {
+            let filter = Filter::In("genres", genres.clone());
+            candidates.extend(fetch(index_name, "", Some(filter)).await);
+        }
+        if index_name != source.index {
+            candidates.extend(fetch(index_name, &source.title, None).await);
+        }
+    }
+
+    rank(source, candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(
+        index: &str,
+        id: i64,
+        title: &str,
+        creator: &str,
+        year: i32,
+        genres: &[&str],
+    ) -> SearchHit {
+        SearchHit {
+            id,
+            title: title.to_string(),
+            title_en: None,
+            description: String::new(),
+            creator: creator.to_string(),
+            creator_id: None,
+            year,
+            genres: genres.iter().map(|g| g.to_string()).collect(),
+            rating: 7.0,
+            image_url: None,
+            language: "ja".to_string(),
+            index: index.to_string(),
+            series: None,
+            user_rating: None,
+            user_rating_count: 0,
+        }
+    }
+
+    #[test]
+    fn explains_creator_and_genre_matches() {
+        let source = hit(
+            "movies",
+            1,
+            "千と千尋の神隠し",
+            "宮崎駿",
+            2001,
+            &["アニメ", "ファンタジー"],
+        );
+        let other = hit(
+            "movies",
+            2,
+            "ハウルの動く城",
+            "宮崎駿",
+            2004,
+            &["アニメ", "ファンタジー"],
+        );
+        let (score, reasons) = score(&source, &other);
+        assert_eq!(
+            reasons,
+            vec![
+                "同じ監督",
+                "共通ジャンル: アニメ・ファンタジー",
+                "評価が近い"
+            ]
+        );
+        assert!(score > CREATOR_WEIGHT + 2.0);
+    }
+
+    #[test]
+    fn finds_adaptations_across_indexes() {
+        let novel = hit("books", 1, "ノルウェイの森", "村上春樹", 1987, &["文学"]);
+        let film = hit(
+            "movies",
+            9,
+            "ノルウェイの森",
+            "トラン・アン・ユン",
+            2010,
+            &["ドラマ"],
+        );
+        let (_, reasons) = score(&novel, &film);
+        assert_eq!(reasons[0], "映画化作品");
+        let (_, reasons) = score(&film, &novel);
+        assert_eq!(reasons[0], "原作");
+    }
+
+    #[test]
+    fn rank_drops_source_duplicates_and_weak_matches() {
+        let source = hit("movies", 1, "A", "X", 2000, &["SF"]);
+        let mut unrelated = hit("books", 3, "C", "Y", 1950, &["歴史"]);
+        unrelated.rating = 3.0;
+        let ranked = rank(
+            &source,
+            vec![
+                source.clone(),
+                hit("movies", 2, "B", "Z", 2001, &["SF"]),
+                hit("movies", 2, "B", "Z", 2001, &["SF"]),
+                hit("books", 4, "D", "X", 1980, &[]),
+                unrelated,
+            ],
+        );
+        let ids: Vec<(String, i64)> = ranked
+            .iter()
+            .map(|i| (i.hit.index.clone(), i.hit.id))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![("books".to_string(), 4), ("movies".to_string(), 2)]
+        );
+    }
+}
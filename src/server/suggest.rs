@@ -0,0 +1,94 @@
+use crate::model::search::{Suggestion, SuggestionKind};
+use crate::server::analytics;
+use crate::server::meilisearch::get_search_index;
+use crate::server::normalize::normalize_value;
+use crate::server::schema::schema;
+
+const TITLE_LIMIT: usize = 5;
+const FACET_LIMIT: usize = 3;
+
+async fn facet_completions(
+    index_name: &str,
+    facet: &'static str,
+    prefix: &str,
+    kind: SuggestionKind,
+) -> Vec<Suggestion> {
+    let index = get_search_index(index_name);
+    let mut query = index.facet_search(facet);
+    query.with_facet_query(prefix);
+
+    match query.execute().await {
+        Ok(response) => response
+            .facet_hits
+            .into_iter()
+            .take(FACET_LIMIT)
+            .map(|hit| Suggestion {
+                kind,
+                value: hit.value,
+                count: Some(hit.count),
+            })
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+async fn title_completions(index_name: &str, prefix: &str) -> Result<Vec<Suggestion>, String> {
+    let index = get_search_index(index_name);
+    let mut search = index.search();
+    search.with_query(prefix);
+    search.with_limit(TITLE_LIMIT);
+    search.with_attributes_to_retrieve(meilisearch_sdk::search::Selectors::Some(&[
+        "title", "title_en",
+    ]));
+
+    let results = search
+        .execute::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("Suggest query failed: {e}"))?;
+
+    let lowered = prefix.to_lowercase();
+    let mut suggestions = Vec::new();
+    for hit in results.hits {
+        if let Some(title) = hit.result.get("title").and_then(|v| v.as_str()) {
+            suggestions.push(Suggestion {
+                kind: SuggestionKind::Title,
+                value: title.to_string(),
+                count: None,
+            });
+        }
+        // English titles are only worth offering when they are what the user is typing
+        if let Some(title_en) = hit.result.get("title_en").and_then(|v| v.as_str()) {
+            if title_en.to_lowercase().contains(&lowered)
+                && !suggestions.iter().any(|s| s.value == title_en)
+            {
+                suggestions.push(Suggestion {
+                    kind: SuggestionKind::TitleEn,
+                    value: title_en.to_string(),
+                    count: None,
+                });
+            }
+        }
+    }
+
+    Ok(suggestions)
+}
+
+pub async fn suggestions(index_name: &str, prefix: &str) -> Result<Vec<Suggestion>, String> {
+    let schema = schema(index_name)?;
+    let prefix = normalize_value(prefix);
+    if prefix.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut suggestions = title_completions(index_name, &prefix).await?;
+
+    if let Some(creator) = schema.creator {
+        suggestions
+            .extend(facet_completions(index_name, creator, &prefix, SuggestionKind::Creator).await);
+    }
+    suggestions
+        .extend(facet_completions(index_name, "genres", &prefix, SuggestionKind::Genre).await);
+    suggestions.extend(analytics::popular_queries(index_name, &prefix).await);
+
+    Ok(suggestions)
+}
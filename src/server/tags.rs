@@ -0,0 +1,321 @@
+// Free-form user tags. Each user's tags on an item are one document in the
+// `user_tags` index; the tags enough users agree on are written back onto the
+// catalogue document as `tags`, where they can be filtered and faceted like
+// genres. Synonyms and bans are admin rules kept in `tag_rules` and applied
+// whenever a tag is stored or searched for.
+
+use std::collections::HashMap;
+
+use meilisearch_sdk::documents::DocumentDeletionQuery;
+
+use crate::model::search::FacetValue;
+use crate::model::tag::{popular_tags, ItemTags, TagOverview, TagRules, UserTags};
+use crate::model::user::{is_listable, User};
+use crate::server::filter::Filter;
+use crate::server::meilisearch::{
+    all_documents, existing_ids, get_client, get_hit, get_index, is_index_not_found,
+    matching_documents, wait_for_task,
+};
+use crate::server::normalize::normalize_value;
+use crate::server::users::now;
+
+pub const USER_TAGS_INDEX: &str = "user_tags";
+const TAG_RULES_INDEX: &str = "tag_rules";
+
+static CONFIGURED: tokio::sync::OnceCell<()> = tokio::sync::OnceCell::const_new();
+
+// Once per process, and waited for, as counting and rewriting filter on these
+async fn configure_user_tags_index() -> Result<(), String> {
+    CONFIGURED
+        .get_or_try_init(|| async {
+            wait_for_task(
+                get_index(USER_TAGS_INDEX)
+                    .set_filterable_attributes(["index", "item_id", "user_id", "tags"])
+                    .await,
+                "set filterable attributes",
+            )
+            .await
+        })
+        .await
+        .copied()
+}
+
+async fn store_user_tags(documents: &[UserTags]) -> Result<(), String> {
+    configure_user_tags_index().await?;
+    let client = get_client();
+    let task = get_index(USER_TAGS_INDEX)
+        .add_documents(documents, Some("id"))
+        .await
+        .map_err(|e| format!("Failed to store tags: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for tags: {e}"))?;
+    Ok(())
+}
+
+// No rules until an admin has merged or banned a tag
+pub async fn rules() -> TagRules {
+    get_index(TAG_RULES_INDEX)
+        .get_document::<TagRules>(&TagRules::default().id)
+        .await
+        .unwrap_or_default()
+}
+
+async fn save_rules(rules: &mut TagRules) -> Result<(), String> {
+    rules.updated_at = now();
+    let client = get_client();
+    let task = get_index(TAG_RULES_INDEX)
+        .add_documents(std::slice::from_ref(rules), Some("id"))
+        .await
+        .map_err(|e| format!("Failed to store tag rules: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for tag rules: {e}"))?;
+    Ok(())
+}
+
+// Width folding first, then the case folding, synonyms and bans of the rules
+pub fn canonical(rules: &TagRules, text: &str) -> Option<String> {
+    rules.canonical(&normalize_value(text))
+}
+
+// Tag usage over the documents matching `filter`, by number of user lists.
+// An error rather than no tags, which `write_back` would store.
+async fn tag_counts(filter: Filter) -> Result<Vec<FacetValue>, String> {
+    let ms_index = get_index(USER_TAGS_INDEX);
+    let expression = filter.to_expression();
+    let facets = ["tags"];
+    let mut search = ms_index.search();
+    search.with_limit(0);
+    if let Some(ref f) = expression {
+        search.with_filter(f);
+    }
+    search.with_facets(meilisearch_sdk::search::Selectors::Some(&facets));
+
+    let distribution: HashMap<String, HashMap<String, usize>> =
+        match search.execute::<UserTags>().await {
+            Ok(results) => results.facet_distribution.unwrap_or_default(),
+            // Nobody has tagged anything yet
+            Err(e) if is_index_not_found(&e) => HashMap::new(),
+            Err(e) => return Err(format!("Failed to count tags: {e}")),
+        };
+    let mut counts: Vec<FacetValue> = distribution
+        .get("tags")
+        .into_iter()
+        .flatten()
+        .map(|(value, count)| FacetValue {
+            value: value.clone(),
+            count: *count,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    Ok(counts)
+}
+
+fn item_filter(index: &str, id: i64) -> Filter {
+    Filter::And(vec![Filter::eq("index", index), Filter::eq("item_id", id)])
+}
+
+// Copies the popular tags onto the catalogue document with a partial update.
+// The update is an upsert, so an item deleted since it was tagged gets no
+// stub document; its tag lists are dropped instead. Returns whether the item
+// still exists.
+async fn write_back(index: &str, id: i64) -> Result<bool, String> {
+    if existing_ids(index, &[id]).await?.is_empty() {
+        let Some(expression) = item_filter(index, id).to_expression() else {
+            return Ok(false);
+        };
+        configure_user_tags_index().await?;
+        let user_tags = get_index(USER_TAGS_INDEX);
+        wait_for_task(
+            DocumentDeletionQuery::new(&user_tags)
+                .with_filter(&expression)
+                .execute::<UserTags>()
+                .await,
+            "drop the tags of a deleted item",
+        )
+        .await?;
+        return Ok(false);
+    }
+    let tags = popular_tags(&tag_counts(item_filter(index, id)).await?);
+    let update = serde_json::json!({ "id": id, "tags": tags });
+    let client = get_client();
+    let task = get_index(index)
+        .add_or_update(&[update], Some("id"))
+        .await
+        .map_err(|e| format!("Failed to update tags: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for tags: {e}"))?;
+    Ok(true)
+}
+
+pub async fn item_tags(index: &str, id: i64, viewer: Option<&User>) -> Result<ItemTags, String> {
+    let own = match viewer {
+        Some(user) => get_index(USER_TAGS_INDEX)
+            .get_document::<UserTags>(&UserTags::document_id(user.id, index, id))
+            .await
+            .map(|t| t.tags)
+            .unwrap_or_default(),
+        None => vec![],
+    };
+    Ok(ItemTags {
+        tags: tag_counts(item_filter(index, id)).await?,
+        own,
+    })
+}
+
+// Replaces the user's tags on an item; an empty list removes them
+pub async fn set_tags(
+    user: &User,
+    index: &str,
+    id: i64,
+    texts: &[String],
+) -> Result<ItemTags, String> {
+    if !is_listable(index) {
+        return Err(format!("Unknown index: {index}"));
+    }
+    get_hit(index, id)
+        .await
+        .ok_or_else(|| format!("Item not found: {index}:{id}"))?;
+
+    let rules = rules().await;
+    let folded: Vec<String> = texts.iter().map(|t| normalize_value(t)).collect();
+    let tags = rules.canonical_list(&folded)?;
+    let document_id = UserTags::document_id(user.id, index, id);
+
+    if tags.is_empty() {
+        let client = get_client();
+        let task = get_index(USER_TAGS_INDEX)
+            .delete_document(&document_id)
+            .await
+            .map_err(|e| format!("Failed to delete tags: {e}"))?;
+        task.wait_for_completion(&client, None, None)
+            .await
+            .map_err(|e| format!("Failed waiting for tags: {e}"))?;
+    } else {
+        store_user_tags(&[UserTags {
+            id: document_id,
+            user_id: user.id,
+            index: index.to_string(),
+            item_id: id,
+            tags,
+            updated_at: now(),
+        }])
+        .await?;
+    }
+
+    write_back(index, id).await?;
+    item_tags(index, id, Some(user)).await
+}
+
+pub async fn overview() -> Result<TagOverview, String> {
+    Ok(TagOverview {
+        tags: tag_counts(Filter::And(vec![])).await?,
+        rules: rules().await,
+    })
+}
+
+// Re-canonicalises every list containing `tag` under the current rules and
+// refreshes the affected items
+async fn rewrite(tag: &str, rules: &TagRules) -> Result<usize, String> {
+    let Some(expression) = Filter::eq("tags", tag).to_expression() else {
+        return Ok(0);
+    };
+    // Every list is read before any is rewritten, so paging is not thrown off
+    // by lists dropping out of the filter
+    configure_user_tags_index().await?;
+    let lists = matching_documents::<UserTags>(USER_TAGS_INDEX, Some(&expression)).await?;
+    if lists.is_empty() {
+        return Ok(0);
+    }
+
+    let updated: Vec<UserTags> = lists
+        .into_iter()
+        .map(|mut list| {
+            let mut tags: Vec<String> = Vec::new();
+            for tag in list.tags.iter().filter_map(|t| rules.canonical(t)) {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            list.tags = tags;
+            list
+        })
+        .collect();
+    store_user_tags(&updated).await?;
+
+    let mut items: Vec<(String, i64)> = updated
+        .iter()
+        .map(|l| (l.index.clone(), l.item_id))
+        .collect();
+    items.sort();
+    items.dedup();
+    let mut written = 0;
+    for (index, id) in &items {
+        if write_back(index, *id).await? {
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+// Folds `from` into `to` on every item and keeps it as a synonym for new tags
+pub async fn merge(from: &str, to: &str) -> Result<TagOverview, String> {
+    let mut rules = rules().await;
+    let (from, _) = rules.merge(&normalize_value(from), &normalize_value(to))?;
+    save_rules(&mut rules).await?;
+    rewrite(&from, &rules).await?;
+    overview().await
+}
+
+pub async fn ban(tag: &str) -> Result<TagOverview, String> {
+    let mut rules = rules().await;
+    let tag = rules.ban(&normalize_value(tag));
+    save_rules(&mut rules).await?;
+    rewrite(&tag, &rules).await?;
+    overview().await
+}
+
+// Lists that contained the tag lost it when it was banned; it only comes back
+// as users add it again
+pub async fn unban(tag: &str) -> Result<TagOverview, String> {
+    let mut rules = rules().await;
+    rules.unban(&normalize_value(tag));
+    save_rules(&mut rules).await?;
+    overview().await
+}
+
+// Re-applies every item's popular tags, e.g. after seeding replaced the
+// catalogue documents
+pub async fn refresh_all() -> Result<usize, String> {
+    let lists = all_documents::<UserTags>(USER_TAGS_INDEX).await?;
+
+    let mut items: Vec<(String, i64)> = lists.into_iter().map(|l| (l.index, l.item_id)).collect();
+    items.sort();
+    items.dedup();
+    let mut written = 0;
+    for (index, id) in &items {
+        if write_back(index, *id).await? {
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_folds_width_before_rules() {
+        let mut rules = TagRules::default();
+        rules.merge("feel good", "癒やし").unwrap();
+        assert_eq!(canonical(&rules, "ＦＥＥＬ　ＧＯＯＤ").as_deref(), Some("癒やし"));
+        assert_eq!(canonical(&rules, "ｹﾞﾝｻｸｱﾘ").as_deref(), Some("ゲンサクアリ"));
+        assert_eq!(canonical(&rules, "ＳＦ").as_deref(), Some("sf"));
+    }
+}
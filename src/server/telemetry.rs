@@ -0,0 +1,140 @@
+// Logging and per-request tracing. Every HTTP request gets a span carrying a
+// request id, taken from an incoming `X-Request-Id` or generated, which is
+// returned in the same header. Meilisearch and SearXNG calls log inside that
+// span, SearXNG also receives the header, and server function errors are
+// logged and shown to users with the id so a report can be matched to the log.
+
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Instant;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use leptos::prelude::ServerFnError;
+use tracing::Instrument;
+use tracing_subscriber::EnvFilter;
+
+use crate::server::config::LogFormat;
+use crate::server::users::new_token;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const REQUEST_ID_MAX_LEN: usize = 64;
+const DEFAULT_FILTER: &str = "info";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+// `log.format` (LOG_FORMAT) picks `json`, `pretty` (multi-line) or the default
+// one-line text; RUST_LOG sets levels, e.g. `info,meilisearch_search_app=debug`
+pub fn init(format: LogFormat) {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    let result = match format {
+        LogFormat::Json => builder.json().try_init(),
+        LogFormat::Pretty => builder.pretty().try_init(),
+        LogFormat::Text => builder.try_init(),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to set up logging: {e}");
+    }
+}
+
+// Accepts ids from a proxy or client only when they are short and plain
+pub fn valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= REQUEST_ID_MAX_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+// Runs `future` in the current request's span and id, for work spawned off a request
+pub fn in_current_request<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let id = current_request_id();
+    let future = future.in_current_span();
+    async move {
+        match id {
+            Some(id) => REQUEST_ID.scope(id, future).await,
+            None => future.await,
+        }
+    }
+}
+
+// Logs a server function failure and returns it with the request id appended
+pub fn server_error(message: impl Display) -> ServerFnError {
+    tracing::warn!(error = %message, "server function failed");
+    match current_request_id() {
+        Some(id) => ServerFnError::new(format!("{message} (リクエストID: {id})")),
+        None => ServerFnError::new(message.to_string()),
+    }
+}
+
+pub async fn trace_requests(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| valid_request_id(v))
+        .map(str::to_string)
+        .unwrap_or_else(|| new_token()[..16].to_string());
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.path(),
+    );
+    let started = Instant::now();
+    let result = REQUEST_ID
+        .scope(id.clone(), next.call(request))
+        .instrument(span.clone())
+        .await;
+
+    let _entered = span.enter();
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(mut response) => {
+            let status = response.status().as_u16();
+            if status >= 500 {
+                tracing::error!(status, elapsed_ms, "request failed");
+            } else {
+                tracing::info!(status, elapsed_ms, "request finished");
+            }
+            if let Ok(value) = HeaderValue::from_str(&id) {
+                response
+                    .headers_mut()
+                    .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            Ok(response)
+        }
+        Err(e) => {
+            tracing::error!(error = %e, elapsed_ms, "request failed");
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_ids_from_clients_are_checked() {
+        assert!(valid_request_id("3f2a9c1e-77b0"));
+        assert!(valid_request_id("req_42"));
+        assert!(!valid_request_id(""));
+        assert!(!valid_request_id("a b"));
+        assert!(!valid_request_id("id\r\nSet-Cookie: x"));
+        assert!(!valid_request_id(&"a".repeat(65)));
+    }
+}
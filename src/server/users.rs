@@ -0,0 +1,341 @@
+// Accounts and login sessions. Both live in their own Meilisearch indexes like
+// people and collections, so the app still needs no database of its own.
+// Passwords are stored as Argon2 hashes; a session is a random token kept in
+// an HttpOnly cookie and looked up on every request that needs a user.
+
+use actix_web::http::header::{HeaderValue, SET_COOKIE};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+
+use crate::model::user::{validate_password, validate_username, User};
+use crate::server::config::config;
+use crate::server::filter::Filter;
+use crate::server::meilisearch::{get_client, get_index, new_document_id, wait_for_task};
+
+pub const USERS_INDEX: &str = "users";
+pub const SESSIONS_INDEX: &str = "sessions";
+const SESSION_COOKIE: &str = "session";
+const SESSION_MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredUser {
+    id: i64,
+    username: String,
+    password_hash: String,
+    created_at: u64,
+    // Created by `admin create`. Admin rights need this as well as a name in
+    // `users.admins`, so registering a listed name first grants nothing.
+    #[serde(default)]
+    admin_account: bool,
+}
+
+impl From<StoredUser> for User {
+    fn from(u: StoredUser) -> Self {
+        User {
+            id: u.id,
+            is_admin: u.admin_account && config().users.admins.contains(&u.username),
+            username: u.username,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Session {
+    // Hex, also the document id
+    token: String,
+    user_id: i64,
+    created_at: u64,
+}
+
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+pub fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// Compares a presented token with the stored one without stopping at the
+// first differing byte, so timing does not reveal how much of it matched
+pub fn tokens_match(presented: &str, stored: &str) -> bool {
+    presented.len() == stored.len()
+        && presented
+            .bytes()
+            .zip(stored.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|e| format!("Failed to hash password: {e}"))
+}
+
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+// Waited for, as registration filters on `username` right away
+async fn configure_indexes() -> Result<(), String> {
+    wait_for_task(
+        get_index(USERS_INDEX)
+            .set_filterable_attributes(["username"])
+            .await,
+        "set filterable attributes",
+    )
+    .await
+}
+
+async fn store<T: Serialize + Send + Sync>(
+    index_name: &str,
+    document: &T,
+    primary_key: &str,
+) -> Result<(), String> {
+    let client = get_client();
+    let task = get_index(index_name)
+        .add_documents(std::slice::from_ref(document), Some(primary_key))
+        .await
+        .map_err(|e| format!("Failed to store {index_name}: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for {index_name}: {e}"))?;
+    Ok(())
+}
+
+async fn find_by_username(username: &str) -> Option<StoredUser> {
+    let index = get_index(USERS_INDEX);
+    let expression = Filter::eq("username", username).to_expression()?;
+    let mut search = index.search();
+    search.with_filter(&expression);
+    search.with_limit(1);
+    search
+        .execute::<StoredUser>()
+        .await
+        .ok()
+        .and_then(|r| r.hits.into_iter().next())
+        .map(|h| h.result)
+}
+
+// Every stored account with `username`
+async fn accounts_named(username: &str) -> Result<Vec<StoredUser>, String> {
+    let expression = Filter::eq("username", username)
+        .to_expression()
+        .ok_or("Invalid username")?;
+    let index = get_index(USERS_INDEX);
+    let mut search = index.search();
+    search.with_filter(&expression);
+    search.with_limit(10);
+    search
+        .execute::<StoredUser>()
+        .await
+        .map(|r| r.hits.into_iter().map(|h| h.result).collect())
+        .map_err(|e| format!("Failed to look up {username}: {e}"))
+}
+
+// Serialises the check and the write of sign-ups in this process
+static REGISTRATION: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+async fn start_session(user_id: i64) -> Result<String, String> {
+    let session = Session {
+        token: new_token(),
+        user_id,
+        created_at: now(),
+    };
+    store(SESSIONS_INDEX, &session, "token").await?;
+    Ok(session.token)
+}
+
+// Returns the new user and a session token for them. Names listed as admins
+// are kept for `admin create`.
+pub async fn register(username: &str, password: &str) -> Result<(User, String), String> {
+    let username = username.trim();
+    if config().users.admins.iter().any(|a| a == username) {
+        return Err(format!("Username is already taken: {username}"));
+    }
+    let user = create_account(username, password, false).await?;
+    let token = start_session(user.id).await?;
+    Ok((user.into(), token))
+}
+
+// The admin account for a name in `users.admins`, created from the command line
+pub async fn create_admin(username: &str, password: &str) -> Result<User, String> {
+    let username = username.trim();
+    if !config().users.admins.iter().any(|a| a == username) {
+        return Err(format!("{username} is not listed in users.admins"));
+    }
+    Ok(create_account(username, password, true).await?.into())
+}
+
+async fn create_account(
+    username: &str,
+    password: &str,
+    admin_account: bool,
+) -> Result<StoredUser, String> {
+    validate_username(username)?;
+    validate_password(password)?;
+    configure_indexes().await?;
+
+    let taken = || format!("Username is already taken: {username}");
+    let _guard = REGISTRATION.lock().await;
+    if !accounts_named(username).await?.is_empty() {
+        return Err(taken());
+    }
+
+    let user = StoredUser {
+        id: new_document_id(),
+        username: username.to_string(),
+        password_hash: hash_password(password)?,
+        created_at: now(),
+        admin_account,
+    };
+    store(USERS_INDEX, &user, "id").await?;
+    // Another instance may have registered the same name meanwhile; the
+    // account that finds a twin steps back, so at most one survives
+    if accounts_named(username).await?.iter().any(|u| u.id != user.id) {
+        wait_for_task(
+            get_index(USERS_INDEX).delete_document(user.id).await,
+            "remove the duplicate account",
+        )
+        .await?;
+        return Err(taken());
+    }
+    Ok(user)
+}
+
+pub async fn login(username: &str, password: &str) -> Result<(User, String), String> {
+    // One message for both cases so usernames cannot be probed
+    let invalid = || "Invalid username or password".to_string();
+    let user = find_by_username(username.trim()).await.ok_or_else(invalid)?;
+    if !verify_password(password, &user.password_hash) {
+        return Err(invalid());
+    }
+    let token = start_session(user.id).await?;
+    Ok((user.into(), token))
+}
+
+pub async fn logout(token: &str) -> Result<(), String> {
+    get_index(SESSIONS_INDEX)
+        .delete_document(token)
+        .await
+        .map_err(|e| format!("Failed to end session: {e}"))?;
+    Ok(())
+}
+
+pub async fn user_for_token(token: &str) -> Option<User> {
+    // Tokens are hex; anything else cannot be a document id
+    if token.is_empty() || !token.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let session: Session = get_index(SESSIONS_INDEX)
+        .get_document(token)
+        .await
+        .ok()?;
+    if now().saturating_sub(session.created_at) > SESSION_MAX_AGE_SECS {
+        return None;
+    }
+    let user: StoredUser = get_index(USERS_INDEX)
+        .get_document(&session.user_id.to_string())
+        .await
+        .ok()?;
+    Some(user.into())
+}
+
+// The session token sent with the current server function call
+pub async fn request_token() -> Option<String> {
+    let request = leptos_actix::extract::<actix_web::HttpRequest>().await.ok()?;
+    request.cookie(SESSION_COOKIE).map(|c| c.value().to_string())
+}
+
+pub async fn current_user() -> Option<User> {
+    user_for_token(&request_token().await?).await
+}
+
+pub async fn require_user() -> Result<User, String> {
+    current_user()
+        .await
+        .ok_or_else(|| "ログインが必要です".to_string())
+}
+
+// For moderation and the other admin pages
+pub async fn require_admin() -> Result<User, String> {
+    let user = require_user().await?;
+    if !user.is_admin {
+        return Err("管理者権限が必要です".to_string());
+    }
+    Ok(user)
+}
+
+fn set_cookie(value: &str, max_age: u64) {
+    let cookie = format!(
+        "{SESSION_COOKIE}={value}; Path=/; Max-Age={max_age}; HttpOnly; SameSite=Lax"
+    );
+    if let (Some(response), Ok(header)) = (
+        leptos::prelude::use_context::<leptos_actix::ResponseOptions>(),
+        HeaderValue::from_str(&cookie),
+    ) {
+        response.insert_header(SET_COOKIE, header);
+    }
+}
+
+pub fn set_session_cookie(token: &str) {
+    set_cookie(token, SESSION_MAX_AGE_SECS);
+}
+
+pub fn clear_session_cookie() {
+    set_cookie("", 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passwords_verify_only_against_their_own_hash() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_password("correct horse", &hash));
+        assert!(!verify_password("wrong horse", &hash));
+        assert!(!verify_password("correct horse", "not a hash"));
+    }
+
+    #[test]
+    fn user_ids_are_random_and_positive() {
+        let ids: Vec<i64> = (0..100).map(|_| new_document_id()).collect();
+        assert!(ids.iter().all(|id| (1..=1 << 53).contains(id)));
+        assert!(ids.iter().enumerate().all(|(i, id)| !ids[..i].contains(id)));
+    }
+
+    #[test]
+    fn tokens_are_random_hex() {
+        let token = new_token();
+        assert_eq!(token.len(), 64);
+        assert!(token.bytes().all(|b| b.is_ascii_hexdigit()));
+        assert_ne!(token, new_token());
+    }
+
+    #[test]
+    fn tokens_match_only_in_full() {
+        let token = new_token();
+        assert!(tokens_match(&token, &token.clone()));
+        assert!(!tokens_match(&token[..63], &token));
+        assert!(!tokens_match(&new_token(), &token));
+        assert!(!tokens_match("", &token));
+    }
+}
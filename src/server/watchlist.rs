@@ -0,0 +1,220 @@
+// Personal lists ("見たい / 見た", "読みたい / 読んだ"). Every entry is a
+// document in the `watchlist` index carrying a copy of the item's title,
+// creator and genres, so a user's list has its own full-text search and
+// facets without touching the catalogue indexes.
+
+use std::collections::HashMap;
+
+use crate::model::search::FacetValue;
+use crate::model::user::{
+    is_listable, ExportedEntry, ImportResult, ListStatus, WatchlistEntry, WatchlistExport,
+    WatchlistResponse,
+};
+use crate::server::filter::Filter;
+use crate::server::meilisearch::{
+    get_client, get_hit, get_hits, get_index, is_index_not_found,
+};
+use crate::server::users::now;
+
+pub const WATCHLIST_INDEX: &str = "watchlist";
+// Lists are personal and small; this is the most one page or export returns
+const LIST_LIMIT: usize = 1000;
+// Items looked up per request when importing
+const IMPORT_BATCH: usize = 100;
+
+async fn configure_watchlist_index() -> Result<(), String> {
+    let index = get_index(WATCHLIST_INDEX);
+
+    index
+        .set_searchable_attributes(["title", "title_en", "creator"])
+        .await
+        .map_err(|e| format!("Failed to set searchable attributes: {e}"))?;
+
+    index
+        .set_filterable_attributes(["user_id", "index", "status", "genres"])
+        .await
+        .map_err(|e| format!("Failed to set filterable attributes: {e}"))?;
+
+    index
+        .set_sortable_attributes(["added_at", "year", "rating"])
+        .await
+        .map_err(|e| format!("Failed to set sortable attributes: {e}"))?;
+
+    Ok(())
+}
+
+async fn store(entries: &[WatchlistEntry]) -> Result<(), String> {
+    let client = get_client();
+    let task = get_index(WATCHLIST_INDEX)
+        .add_documents(entries, Some("id"))
+        .await
+        .map_err(|e| format!("Failed to store list entries: {e}"))?;
+
+    task.wait_for_completion(&client, None, None)
+        .await
+        .map_err(|e| format!("Failed waiting for list entries: {e}"))?;
+
+    configure_watchlist_index().await
+}
+
+// `None` removes the item from the user's lists
+pub async fn set_status(
+    user_id: i64,
+    index: &str,
+    id: i64,
+    status: Option<ListStatus>,
+) -> Result<(), String> {
+    if !is_listable(index) {
+        return Err(format!("Unknown index: {index}"));
+    }
+    let document_id = WatchlistEntry::document_id(user_id, index, id);
+
+    let Some(status) = status else {
+        let client = get_client();
+        let task = get_index(WATCHLIST_INDEX)
+            .delete_document(&document_id)
+            .await
+            .map_err(|e| format!("Failed to remove list entry: {e}"))?;
+        task.wait_for_completion(&client, None, None)
+            .await
+            .map_err(|e| format!("Failed waiting for list entry: {e}"))?;
+        return Ok(());
+    };
+
+    let hit = get_hit(index, id)
+        .await
+        .ok_or_else(|| format!("Item not found: {index}:{id}"))?;
+    // Moving an item from 見たい to 見た keeps when it was first added
+    let added_at = get_index(WATCHLIST_INDEX)
+        .get_document::<WatchlistEntry>(&document_id)
+        .await
+        .map(|e| e.added_at)
+        .unwrap_or_else(|_| now());
+    store(&[WatchlistEntry::new(user_id, hit, status, added_at)]).await
+}
+
+pub async fn search(
+    user_id: i64,
+    text: &str,
+    index: Option<&str>,
+    status: Option<ListStatus>,
+    genre: Option<&str>,
+) -> Result<WatchlistResponse, String> {
+    let mut filters = vec![Filter::eq("user_id", user_id)];
+    if let Some(index) = index {
+        filters.push(Filter::eq("index", index));
+    }
+    if let Some(status) = status {
+        filters.push(Filter::eq("status", status.as_str()));
+    }
+    if let Some(genre) = genre {
+        filters.push(Filter::eq("genres", genre));
+    }
+    let expression = Filter::And(filters).to_expression();
+
+    let ms_index = get_index(WATCHLIST_INDEX);
+    let mut search = ms_index.search();
+    search.with_query(text);
+    search.with_limit(LIST_LIMIT);
+    if let Some(ref f) = expression {
+        search.with_filter(f);
+    }
+    if text.is_empty() {
+        search.with_sort(&["added_at:desc"]);
+    }
+
+    // An index that was never written to just means an empty list
+    let entries = match search.execute::<WatchlistEntry>().await {
+        Ok(results) => results.hits.into_iter().map(|h| h.result).collect(),
+        Err(e) if is_index_not_found(&e) => {
+            return Ok(WatchlistResponse {
+                entries: vec![],
+                genres: vec![],
+            })
+        }
+        Err(e) => return Err(format!("Failed to search watchlist: {e}")),
+    };
+
+    Ok(WatchlistResponse {
+        entries,
+        genres: genre_counts(user_id).await,
+    })
+}
+
+async fn genre_counts(user_id: i64) -> Vec<FacetValue> {
+    let Some(expression) = Filter::eq("user_id", user_id).to_expression() else {
+        return vec![];
+    };
+    let index = get_index(WATCHLIST_INDEX);
+    let facets = ["genres"];
+    let mut search = index.search();
+    search.with_limit(0);
+    search.with_filter(&expression);
+    search.with_facets(meilisearch_sdk::search::Selectors::Some(&facets));
+
+    let distribution: HashMap<String, HashMap<String, usize>> = search
+        .execute::<WatchlistEntry>()
+        .await
+        .ok()
+        .and_then(|r| r.facet_distribution)
+        .unwrap_or_default();
+    let mut genres: Vec<FacetValue> = distribution
+        .get("genres")
+        .into_iter()
+        .flatten()
+        .map(|(value, count)| FacetValue {
+            value: value.clone(),
+            count: *count,
+        })
+        .collect();
+    genres.sort_by(|a, b| b.count.cmp(&a.count).then(a.value.cmp(&b.value)));
+    genres
+}
+
+// `movies:12` → status for everything on the user's lists, used to light up
+// the buttons on result cards without one request per card
+pub async fn statuses(user_id: i64) -> Result<HashMap<String, ListStatus>, String> {
+    let list = search(user_id, "", None, None, None).await?;
+    Ok(list
+        .entries
+        .into_iter()
+        .map(|e| (e.key(), e.status))
+        .collect())
+}
+
+pub async fn export(user_id: i64) -> Result<WatchlistExport, String> {
+    let list = search(user_id, "", None, None, None).await?;
+    Ok(WatchlistExport::from_entries(&list.entries))
+}
+
+// Entries already on the list are overwritten by the imported status
+pub async fn import(user_id: i64, export: WatchlistExport) -> Result<ImportResult, String> {
+    export.validate()?;
+
+    let mut entries = Vec::new();
+    for index_name in ["movies", "books", "series"] {
+        let items: Vec<&ExportedEntry> = export
+            .entries
+            .iter()
+            .filter(|e| e.index == index_name)
+            .collect();
+        for batch in items.chunks(IMPORT_BATCH) {
+            let ids: Vec<i64> = batch.iter().map(|item| item.id).collect();
+            let hits = get_hits(index_name, &ids).await?;
+            for item in batch {
+                if let Some(hit) = hits.iter().find(|hit| hit.id == item.id) {
+                    let added_at = if item.added_at == 0 { now() } else { item.added_at };
+                    entries.push(WatchlistEntry::new(user_id, hit.clone(), item.status, added_at));
+                }
+            }
+        }
+    }
+    if !entries.is_empty() {
+        store(&entries).await?;
+    }
+
+    Ok(ImportResult {
+        imported: entries.len(),
+        skipped: export.entries.len() - entries.len(),
+    })
+}
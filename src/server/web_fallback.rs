@@ -0,0 +1,194 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::model::web_result::WebResult;
+use crate::server::config::{config, AppConfig};
+use crate::server::meilisearch::{configure_web_index, get_index};
+use crate::server::{metrics, telemetry};
+use crate::server::searxng::search_web;
+
+// Queries sent to the web provider are remembered for this long
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+// At most MAX_CALLS_PER_WINDOW provider calls per RATE_WINDOW across all visitors
+const MAX_CALLS_PER_WINDOW: usize = 10;
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+// A slower provider call keeps running in the background and fills the cache
+const RESPONSE_WAIT: Duration = Duration::from_secs(3);
+
+#[derive(Default)]
+struct FallbackState {
+    cache: HashMap<String, (Instant, Vec<WebResult>)>,
+    in_flight: Vec<String>,
+    calls: VecDeque<Instant>,
+}
+
+static STATE: OnceLock<Mutex<FallbackState>> = OnceLock::new();
+
+fn state() -> &'static Mutex<FallbackState> {
+    STATE.get_or_init(|| Mutex::new(FallbackState::default()))
+}
+
+pub fn is_enabled() -> bool {
+    config().features.web_fallback
+}
+
+fn cache_key(query: &str) -> String {
+    query.trim().to_lowercase()
+}
+
+pub fn should_run(query: &str) -> bool {
+    should_run_for(config(), query)
+}
+
+fn should_run_for(config: &AppConfig, query: &str) -> bool {
+    config.features.web_fallback && !cache_key(query).is_empty()
+}
+
+enum Plan {
+    Cached(Vec<WebResult>),
+    Skip,
+    Fetch,
+}
+
+impl FallbackState {
+    fn plan(&mut self, key: &str, now: Instant) -> Plan {
+        self.cache
+            .retain(|_, (at, _)| now.duration_since(*at) < CACHE_TTL);
+        let cached = self.cache.get(key).map(|(_, results)| results.clone());
+        metrics::cache_lookup("web_fallback", cached.is_some());
+        if let Some(results) = cached {
+            return Plan::Cached(results);
+        }
+        if self.in_flight.iter().any(|k| k == key) {
+            return Plan::Skip;
+        }
+
+        while self
+            .calls
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= RATE_WINDOW)
+        {
+            self.calls.pop_front();
+        }
+        if self.calls.len() >= MAX_CALLS_PER_WINDOW {
+            return Plan::Skip;
+        }
+
+        self.calls.push_back(now);
+        self.in_flight.push(key.to_string());
+        Plan::Fetch
+    }
+
+    fn finish(&mut self, key: &str, results: Vec<WebResult>, now: Instant) {
+        self.in_flight.retain(|k| k != key);
+        self.cache.insert(key.to_string(), (now, results));
+    }
+}
+
+pub async fn fallback_results(query: &str) -> Vec<WebResult> {
+    let key = cache_key(query);
+
+    match state().lock().unwrap().plan(&key, Instant::now()) {
+        Plan::Cached(results) => return results,
+        Plan::Skip => return vec![],
+        Plan::Fetch => {}
+    }
+
+    let query = query.trim().to_string();
+    let task = actix_web::rt::spawn(telemetry::in_current_request(async move {
+        // Failures are cached as empty so a broken provider is not hit on every keystroke
+        let results = search_web(&query).await.unwrap_or_default();
+        state()
+            .lock()
+            .unwrap()
+            .finish(&key, results.clone(), Instant::now());
+        if !results.is_empty() {
+            let _ = import_web_results(&results).await;
+        }
+        results
+    }));
+
+    match actix_web::rt::time::timeout(RESPONSE_WAIT, task).await {
+        Ok(Ok(results)) => results,
+        _ => vec![],
+    }
+}
+
+pub async fn import_web_results(results: &[WebResult]) -> Result<(), String> {
+    configure_web_index().await?;
+
+    get_index("web")
+        .add_documents(results, Some("id"))
+        .await
+        .map_err(|e| format!("Failed to index web results: {e}"))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(id: i64) -> WebResult {
+        WebResult {
+            id,
+            title: format!("result {id}"),
+            title_en: None,
+            description: String::new(),
+            url: format!("https://example.com/{id}"),
+            source_engine: None,
+            year: 2021,
+            genres: vec![],
+            rating: 0.0,
+            image_url: None,
+            language: "en".to_string(),
+            published_date: None,
+        }
+    }
+
+    fn ids(plan: Plan) -> Option<Vec<i64>> {
+        match plan {
+            Plan::Cached(results) => Some(results.iter().map(|r| r.id).collect()),
+            Plan::Skip | Plan::Fetch => None,
+        }
+    }
+
+    #[test]
+    fn results_are_cached_until_the_ttl_passes() {
+        let mut state = FallbackState::default();
+        let start = Instant::now();
+        assert!(matches!(state.plan("dune", start), Plan::Fetch));
+        // A second visitor does not start another call for the same query
+        assert!(matches!(state.plan("dune", start), Plan::Skip));
+        state.finish("dune", vec![result(1)], start);
+
+        let before_expiry = start + CACHE_TTL - Duration::from_secs(1);
+        assert_eq!(ids(state.plan("dune", before_expiry)), Some(vec![1]));
+        assert!(matches!(state.plan("dune", start + CACHE_TTL), Plan::Fetch));
+    }
+
+    #[test]
+    fn provider_calls_are_rate_limited_per_window() {
+        let mut state = FallbackState::default();
+        let start = Instant::now();
+        for i in 0..MAX_CALLS_PER_WINDOW {
+            assert!(matches!(state.plan(&format!("q{i}"), start), Plan::Fetch));
+        }
+        assert!(matches!(state.plan("one more", start), Plan::Skip));
+        let later = start + RATE_WINDOW;
+        assert!(matches!(state.plan("one more", later), Plan::Fetch));
+    }
+
+    #[test]
+    fn fallback_follows_the_feature_toggle() {
+        let load = |value: &str| {
+            AppConfig::from_sources(None, |var| (var == "WEB_FALLBACK").then(|| value.to_string()))
+                .unwrap()
+        };
+        assert!(!should_run_for(&AppConfig::default(), "dune"));
+        assert!(should_run_for(&load("true"), "dune"));
+        assert!(!should_run_for(&load("true"), "   "));
+        assert!(!should_run_for(&load("false"), "dune"));
+    }
+}
@@ -0,0 +1,55 @@
+// Scrapes `/metrics` from an app wired like `main.rs`. Run with `--features ssr`.
+#![cfg(feature = "ssr")]
+
+use std::time::Duration;
+
+use actix_web::{middleware, test, web, App, HttpResponse};
+use meilisearch_search_app::server::metrics;
+
+#[actix_web::test]
+async fn scrape_reports_requests_and_backend_calls() {
+    metrics::name_server_fn("/api/search_items42", "SearchItems");
+    metrics::observe_meilisearch("search", Duration::from_millis(12), true);
+    metrics::observe_meilisearch("search", Duration::from_millis(30), false);
+    metrics::observe_searxng(Duration::from_millis(250), true);
+    metrics::cache_lookup("web_fallback", true);
+    metrics::cache_lookup("web_fallback", false);
+
+    let app = test::init_service(
+        App::new()
+            .wrap(middleware::from_fn(metrics::track_requests))
+            .route("/metrics", web::get().to(metrics::endpoint))
+            .route(
+                "/api/search_items42",
+                web::post().to(|| async { HttpResponse::Ok().finish() }),
+            ),
+    )
+    .await;
+
+    for _ in 0..2 {
+        let request = test::TestRequest::post().uri("/api/search_items42").to_request();
+        test::call_service(&app, request).await;
+    }
+    let request = test::TestRequest::get().uri("/movie/1").to_request();
+    test::call_service(&app, request).await;
+
+    let request = test::TestRequest::get().uri("/metrics").to_request();
+    let response = test::call_service(&app, request).await;
+    assert!(response.status().is_success());
+    let content_type = response.headers().get("content-type").unwrap().to_str().unwrap();
+    assert!(content_type.starts_with("text/plain"), "{content_type}");
+
+    let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+    for line in [
+        r#"http_requests_total{handler="SearchItems",status="200"} 2"#,
+        r#"http_requests_total{handler="page",status="404"} 1"#,
+        r#"http_request_duration_seconds_count{handler="SearchItems"} 2"#,
+        r#"meilisearch_request_duration_seconds_count{operation="search"} 2"#,
+        r#"meilisearch_request_errors_total{operation="search"} 1"#,
+        r#"searxng_request_duration_seconds_count{outcome="ok"} 1"#,
+        r#"cache_lookups_total{cache="web_fallback",result="hit"} 1"#,
+        r#"cache_lookups_total{cache="web_fallback",result="miss"} 1"#,
+    ] {
+        assert!(body.contains(line), "missing `{line}` in:\n{body}");
+    }
+}
@@ -0,0 +1,40 @@
+// The request id middleware as wired in `main.rs`. Run with `--features ssr`.
+#![cfg(feature = "ssr")]
+
+use actix_web::{middleware, test, web, App, HttpResponse};
+use meilisearch_search_app::server::telemetry::{self, REQUEST_ID_HEADER};
+
+async fn failing() -> HttpResponse {
+    HttpResponse::InternalServerError().body(telemetry::server_error("Search failed").to_string())
+}
+
+#[actix_web::test]
+async fn request_id_is_returned_and_shown_in_errors() {
+    let app = test::init_service(
+        App::new()
+            .wrap(middleware::from_fn(telemetry::trace_requests))
+            .route("/fail", web::post().to(failing)),
+    )
+    .await;
+
+    let response = test::call_service(&app, test::TestRequest::post().uri("/fail").to_request()).await;
+    let id = response.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap().to_string();
+    assert!(telemetry::valid_request_id(&id));
+    let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+    assert!(body.contains(&format!("リクエストID: {id}")), "{body}");
+
+    // An id set by a proxy is kept; one that is not plain is replaced
+    let request = test::TestRequest::post()
+        .uri("/fail")
+        .insert_header((REQUEST_ID_HEADER, "edge-1234"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.headers().get(REQUEST_ID_HEADER).unwrap(), "edge-1234");
+
+    let request = test::TestRequest::post()
+        .uri("/fail")
+        .insert_header((REQUEST_ID_HEADER, "not plain!"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_ne!(response.headers().get(REQUEST_ID_HEADER).unwrap(), "not plain!");
+}